@@ -0,0 +1,148 @@
+//! Encoding commands from borrowed arguments into fixed buffers, for
+//! clients that can't build a `RESP` value.
+//!
+//! ```
+//! use resp_parser_rs_core::command::encode_command_into;
+//!
+//! let mut buffer = [0; 64];
+//! let length = encode_command_into(&["SET", "key", "1"], &mut buffer).unwrap();
+//! assert_eq!(&buffer[..length], b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$1\r\n1\r\n");
+//! ```
+
+use core::fmt;
+
+/// The buffer can't hold the encoded command. Nothing was written to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    pub required: usize,
+    pub available: usize,
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "encoded command takes {} bytes, buffer holds {}",
+            self.required, self.available
+        )
+    }
+}
+
+/// Digits of `value` in decimal.
+fn digits(value: usize) -> usize {
+    value.checked_ilog10().map_or(1, |x| x as usize + 1)
+}
+
+/// Length of a type byte followed by `value` and CRLF.
+fn header_len(value: usize) -> usize {
+    1 + digits(value) + 2
+}
+
+/// Bytes taken by `args` encoded as an array of bulk strings.
+pub fn command_len<A: AsRef<[u8]>>(args: &[A]) -> usize {
+    args.iter().fold(header_len(args.len()), |total, x| {
+        let length = x.as_ref().len();
+        total + header_len(length) + length + 2
+    })
+}
+
+/// Writes `prefix` followed by `value` and CRLF at the start of `buffer`,
+/// returning what is left of it.
+fn write_header(buffer: &mut [u8], prefix: u8, value: usize) -> &mut [u8] {
+    let length = header_len(value);
+    let (header, rest) = buffer.split_at_mut(length);
+    header[0] = prefix;
+    let mut value = value;
+    for digit in header[1..length - 2].iter_mut().rev() {
+        *digit = b'0' + (value % 10) as u8;
+        value /= 10;
+    }
+    header[length - 2..].copy_from_slice(b"\r\n");
+    rest
+}
+
+/// Encodes `args` as an array of bulk strings at the start of `buffer`,
+/// returning how many bytes were written.
+pub fn encode_command_into<A: AsRef<[u8]>>(
+    args: &[A],
+    buffer: &mut [u8],
+) -> Result<usize, BufferTooSmall> {
+    let required = command_len(args);
+    if required > buffer.len() {
+        return Err(BufferTooSmall {
+            required,
+            available: buffer.len(),
+        });
+    }
+    let mut rest = write_header(buffer, b'*', args.len());
+    for arg in args {
+        let arg = arg.as_ref();
+        let (payload, tail) = write_header(rest, b'$', arg.len()).split_at_mut(arg.len());
+        payload.copy_from_slice(arg);
+        tail[..2].copy_from_slice(b"\r\n");
+        rest = &mut tail[2..];
+    }
+    Ok(required)
+}
+
+/// Like [`encode_command_into`], appending to a fixed capacity vector.
+/// Nothing is appended when it doesn't fit.
+#[cfg(feature = "heapless")]
+pub fn encode_command_heapless<A: AsRef<[u8]>, const N: usize>(
+    args: &[A],
+    buffer: &mut heapless::Vec<u8, N>,
+) -> Result<usize, BufferTooSmall> {
+    let start = buffer.len();
+    let required = command_len(args);
+    if buffer.resize(start + required, 0).is_err() {
+        return Err(BufferTooSmall {
+            required,
+            available: N - start,
+        });
+    }
+    encode_command_into(args, &mut buffer[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_into() {
+        let mut buffer = [0; 64];
+        let args: [&[u8]; 3] = [b"RPUSH", b"", &[b'x'; 10]];
+        let length = encode_command_into(&args, &mut buffer).unwrap();
+        assert_eq!(length, command_len(&args));
+        assert_eq!(
+            &buffer[..length],
+            b"*3\r\n$5\r\nRPUSH\r\n$0\r\n\r\n$10\r\nxxxxxxxxxx\r\n"
+        );
+        assert_eq!(encode_command_into::<&str>(&[], &mut buffer), Ok(4));
+        assert_eq!(&buffer[..4], b"*0\r\n");
+
+        let mut small = [0; 8];
+        assert_eq!(
+            encode_command_into(&["PING"], &mut small),
+            Err(BufferTooSmall {
+                required: 14,
+                available: 8
+            })
+        );
+        assert_eq!(small, [0; 8]);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn command_heapless() {
+        let mut buffer = heapless::Vec::<u8, 24>::new();
+        assert_eq!(encode_command_heapless(&["PING"], &mut buffer), Ok(14));
+        assert_eq!(
+            encode_command_heapless(&["PING"], &mut buffer),
+            Err(BufferTooSmall {
+                required: 14,
+                available: 10
+            })
+        );
+        assert_eq!(&buffer[..], b"*1\r\n$4\r\nPING\r\n");
+    }
+}
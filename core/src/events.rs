@@ -0,0 +1,461 @@
+//! Allocation-free pull parser, yielding values as a stream of events that
+//! borrow from the input, for targets where building a `RESP` tree isn't an
+//! option.
+//!
+//! Nesting is bounded by a const generic, so the reader's whole state lives
+//! on the stack, and errors only hold static text and byte ranges.
+//!
+//! ```
+//! use resp_parser_rs_core::events::{Aggregate, Event, EventReader};
+//!
+//! let mut events = EventReader::<4>::new(b"*2\r\n+OK\r\n:1\r\n");
+//! assert_eq!(events.next(), Some(Ok(Event::Start(Aggregate::Array, 2))));
+//! assert_eq!(events.next(), Some(Ok(Event::SimpleString("OK"))));
+//! assert_eq!(events.next(), Some(Ok(Event::Integer(1))));
+//! assert_eq!(events.next(), Some(Ok(Event::End(Aggregate::Array))));
+//! assert_eq!(events.next(), None);
+//! ```
+
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+const SIMPLE_STRING: u8 = b'+';
+const SIMPLE_ERROR: u8 = b'-';
+const INTEGER: u8 = b':';
+const BULK_STRING: u8 = b'$';
+const ARRAY: u8 = b'*';
+const NULL: u8 = b'_';
+const BOOLEAN: u8 = b'#';
+const DOUBLE: u8 = b',';
+const BIG_NUMBER: u8 = b'(';
+const BULK_ERROR: u8 = b'!';
+const VERBATIM_STRING: u8 = b'=';
+const MAP: u8 = b'%';
+const ATTRIBUTE: u8 = b'|';
+const SET: u8 = b'~';
+const PUSH: u8 = b'>';
+
+const CRLF: &[u8] = b"\r\n";
+const EXPECTED_CRLF: &str = "\"\\r\\n\"";
+
+/// Kind of an aggregate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Array,
+    Set,
+    Push,
+    Map,
+    /// Attributes, followed by the value they annotate once they end.
+    Attributes,
+}
+
+/// A value, or the boundary of an aggregate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    SimpleString(&'a str),
+    SimpleError(&'a str),
+    Integer(i64),
+    BulkString(&'a [u8]),
+    NullBulkString,
+    NullArray,
+    Null,
+    Boolean(bool),
+    Double(f64),
+    BigNumber(&'a str),
+    BulkError(&'a [u8]),
+    VerbatimString {
+        encoding: &'a str,
+        data: &'a [u8],
+    },
+    /// Start of an aggregate of the given amount of elements, or of entries
+    /// for maps and attributes, which are sent as keys followed by values.
+    Start(Aggregate, usize),
+    End(Aggregate),
+}
+
+/// Why the input couldn't be read, pointing into it instead of copying
+/// what was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventError {
+    /// The input ended before what was expected.
+    EndOfInput { expected: &'static str },
+    /// Something else than what was expected was found at `span` of the
+    /// input.
+    Unexpected {
+        expected: &'static str,
+        span: Range<usize>,
+    },
+}
+
+impl EventError {
+    pub fn expected(&self) -> &'static str {
+        match self {
+            Self::EndOfInput { expected } | Self::Unexpected { expected, .. } => expected,
+        }
+    }
+
+    /// Byte range of the input found instead of what was expected, `None`
+    /// if the input ended.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::EndOfInput { .. } => None,
+            Self::Unexpected { span, .. } => Some(span.clone()),
+        }
+    }
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EndOfInput { expected } => write!(f, "expected {expected}, found end of input"),
+            Self::Unexpected { expected, span } => {
+                write!(f, "expected {expected}, found bytes {span:?}")
+            }
+        }
+    }
+}
+
+/// Iterator over the events of every frame in a buffer.
+///
+/// Errors end the iteration, including a truncated last frame.
+#[derive(Debug, Clone)]
+pub struct EventReader<'a, const MAX_DEPTH: usize> {
+    data: &'a [u8],
+    position: usize,
+    /// Open aggregates and the amount of values they still hold.
+    stack: [(Aggregate, usize); MAX_DEPTH],
+    depth: usize,
+}
+
+impl<'a, const MAX_DEPTH: usize> EventReader<'a, MAX_DEPTH> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            stack: [(Aggregate::Array, 0); MAX_DEPTH],
+            depth: 0,
+        }
+    }
+
+    /// Bytes consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Whether the last event finished a whole frame.
+    pub fn at_frame_boundary(&self) -> bool {
+        self.depth == 0
+    }
+
+    /// Error for `part`, a slice of the input, not being `expected`.
+    fn unexpected(&self, part: &[u8], expected: &'static str) -> EventError {
+        let start = part.as_ptr() as usize - self.data.as_ptr() as usize;
+        EventError::Unexpected {
+            expected,
+            span: start..start + part.len(),
+        }
+    }
+
+    fn text(&self, data: &'a [u8], expected: &'static str) -> Result<&'a str, EventError> {
+        core::str::from_utf8(data).map_err(|_| self.unexpected(data, expected))
+    }
+
+    fn number<T: FromStr>(&self, data: &'a [u8], expected: &'static str) -> Result<T, EventError> {
+        let line = self.text(data, expected)?;
+        line.parse().map_err(|_| self.unexpected(data, expected))
+    }
+
+    fn line(&mut self) -> Result<&'a [u8], EventError> {
+        let end = self.data[self.position..]
+            .windows(CRLF.len())
+            .position(|x| x == CRLF)
+            .ok_or(EventError::EndOfInput {
+                expected: EXPECTED_CRLF,
+            })?;
+        let line = &self.data[self.position..self.position + end];
+        self.position += end + CRLF.len();
+        Ok(line)
+    }
+
+    /// Reads a payload of at least `min` bytes, `expected` describing that
+    /// bound, or a null if `min` is -1.
+    fn payload(
+        &mut self,
+        min: isize,
+        expected: &'static str,
+    ) -> Result<Option<&'a [u8]>, EventError> {
+        let line = self.line()?;
+        let length = self.number::<isize>(line, "length")?;
+        if length == -1 && min == -1 {
+            return Ok(None);
+        }
+        if length < min {
+            return Err(self.unexpected(line, expected));
+        }
+        let end = self
+            .position
+            .checked_add(length as usize)
+            .filter(|&x| x <= self.data.len())
+            .ok_or(EventError::EndOfInput {
+                expected: "payload",
+            })?;
+        let terminator = &self.data[end..self.data.len().min(end + CRLF.len())];
+        if terminator != CRLF {
+            return Err(match CRLF.starts_with(terminator) {
+                true => EventError::EndOfInput {
+                    expected: EXPECTED_CRLF,
+                },
+                false => self.unexpected(terminator, EXPECTED_CRLF),
+            });
+        }
+        let payload = &self.data[self.position..end];
+        self.position = end + CRLF.len();
+        Ok(Some(payload))
+    }
+
+    fn open(&mut self, kind: Aggregate) -> Result<Event<'a>, EventError> {
+        let line = self.line()?;
+        let length = self.number::<isize>(line, "length")?;
+        if length == -1 && kind == Aggregate::Array {
+            return Ok(Event::NullArray);
+        }
+        let Ok(length) = usize::try_from(length) else {
+            return Err(self.unexpected(line, "length of at least 0"));
+        };
+        if self.depth == MAX_DEPTH {
+            return Err(self.unexpected(line, "at most MAX_DEPTH levels of nesting"));
+        }
+        let values = match kind {
+            Aggregate::Map | Aggregate::Attributes => length.checked_mul(2),
+            _ => Some(length),
+        }
+        .ok_or_else(|| self.unexpected(line, "length that fits in memory"))?;
+        self.stack[self.depth] = (kind, values);
+        self.depth += 1;
+        Ok(Event::Start(kind, length))
+    }
+
+    fn event(&mut self) -> Result<Event<'a>, EventError> {
+        let kind = &self.data[self.position..self.position + 1];
+        self.position += 1;
+        Ok(match kind[0] {
+            SIMPLE_STRING => {
+                let line = self.line()?;
+                Event::SimpleString(self.text(line, "UTF-8 text")?)
+            }
+            SIMPLE_ERROR => {
+                let line = self.line()?;
+                Event::SimpleError(self.text(line, "UTF-8 text")?)
+            }
+            INTEGER => {
+                let line = self.line()?;
+                Event::Integer(self.number(line, "integer")?)
+            }
+            BULK_STRING => match self.payload(-1, "length of at least -1")? {
+                Some(data) => Event::BulkString(data),
+                None => Event::NullBulkString,
+            },
+            NULL => match self.line()? {
+                b"" => Event::Null,
+                x => return Err(self.unexpected(x, "empty null")),
+            },
+            BOOLEAN => match self.line()? {
+                b"t" => Event::Boolean(true),
+                b"f" => Event::Boolean(false),
+                x => return Err(self.unexpected(x, "t or f")),
+            },
+            DOUBLE => {
+                let line = self.line()?;
+                Event::Double(self.number(line, "double")?)
+            }
+            BIG_NUMBER => {
+                let data = self.line()?;
+                let line = self.text(data, "big number")?;
+                let digits = line.strip_prefix(['+', '-']).unwrap_or(line);
+                if digits.is_empty() || !digits.bytes().all(|x| x.is_ascii_digit()) {
+                    return Err(self.unexpected(data, "big number"));
+                }
+                Event::BigNumber(line.strip_prefix('+').unwrap_or(line))
+            }
+            BULK_ERROR => {
+                Event::BulkError(self.payload(0, "length of at least 0")?.unwrap_or_default())
+            }
+            VERBATIM_STRING => {
+                let data = self.payload(4, "length of at least 4")?.unwrap_or_default();
+                if data[3] != b':' {
+                    return Err(self.unexpected(data, "encoding prefix"));
+                }
+                Event::VerbatimString {
+                    encoding: self.text(&data[..3], "3 byte encoding")?,
+                    data: &data[4..],
+                }
+            }
+            PUSH if self.depth > 0 => {
+                return Err(self.unexpected(kind, "value other than a push in an aggregate"));
+            }
+            ARRAY => self.open(Aggregate::Array)?,
+            SET => self.open(Aggregate::Set)?,
+            PUSH => self.open(Aggregate::Push)?,
+            MAP => self.open(Aggregate::Map)?,
+            ATTRIBUTE => self.open(Aggregate::Attributes)?,
+            _ => return Err(self.unexpected(kind, "type byte")),
+        })
+    }
+}
+
+impl<'a, const MAX_DEPTH: usize> Iterator for EventReader<'a, MAX_DEPTH> {
+    type Item = Result<Event<'a>, EventError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(&(kind, 0)) = self.depth.checked_sub(1).map(|x| &self.stack[x]) {
+            self.depth -= 1;
+            return Some(Ok(Event::End(kind)));
+        }
+        if self.position >= self.data.len() {
+            if self.depth == 0 {
+                return None;
+            }
+            self.depth = 0;
+            return Some(Err(EventError::EndOfInput {
+                expected: "rest of the frame",
+            }));
+        }
+        // Attributes annotate the value following them, which alone counts
+        // as an element of the parent
+        if let Some(parent) = self.depth.checked_sub(1) {
+            if self.data[self.position] != ATTRIBUTE {
+                self.stack[parent].1 -= 1;
+            }
+        }
+        let event = self.event();
+        if event.is_err() {
+            self.position = self.data.len();
+            self.depth = 0;
+        }
+        Some(event)
+    }
+}
+
+impl<const MAX_DEPTH: usize> core::iter::FusedIterator for EventReader<'_, MAX_DEPTH> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::ToString;
+    use std::vec::Vec;
+    use std::{format, vec};
+
+    use super::*;
+
+    fn events<const N: usize>(data: &[u8]) -> Vec<Result<Event<'_>, EventError>> {
+        EventReader::<N>::new(data).collect()
+    }
+
+    fn unexpected(expected: &'static str, span: Range<usize>) -> EventError {
+        EventError::Unexpected { expected, span }
+    }
+
+    #[test]
+    fn events_nested() {
+        assert_eq!(
+            events::<2>(b"%1\r\n+key\r\n*0\r\n$5\r\nHello\r\n=7\r\ntxt:abc\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Map, 1)),
+                Ok(Event::SimpleString("key")),
+                Ok(Event::Start(Aggregate::Array, 0)),
+                Ok(Event::End(Aggregate::Array)),
+                Ok(Event::End(Aggregate::Map)),
+                Ok(Event::BulkString(b"Hello")),
+                Ok(Event::VerbatimString {
+                    encoding: "txt",
+                    data: b"abc",
+                }),
+            ]
+        );
+        assert_eq!(
+            events::<1>(b"|1\r\n+ttl\r\n:3\r\n*-1\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Attributes, 1)),
+                Ok(Event::SimpleString("ttl")),
+                Ok(Event::Integer(3)),
+                Ok(Event::End(Aggregate::Attributes)),
+                Ok(Event::NullArray),
+            ]
+        );
+        assert_eq!(
+            events::<2>(b"*1\r\n|1\r\n+a\r\n:1\r\n:5\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Array, 1)),
+                Ok(Event::Start(Aggregate::Attributes, 1)),
+                Ok(Event::SimpleString("a")),
+                Ok(Event::Integer(1)),
+                Ok(Event::End(Aggregate::Attributes)),
+                Ok(Event::Integer(5)),
+                Ok(Event::End(Aggregate::Array)),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_errors() {
+        assert_eq!(
+            events::<1>(b"*1\r\n*1\r\n:1\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Array, 1)),
+                Err(unexpected("at most MAX_DEPTH levels of nesting", 5..6)),
+            ]
+        );
+        assert_eq!(
+            events::<4>(b"*2\r\n:1\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Array, 2)),
+                Ok(Event::Integer(1)),
+                Err(EventError::EndOfInput {
+                    expected: "rest of the frame"
+                }),
+            ]
+        );
+        assert_eq!(
+            events::<4>(b"$5\r\nHel"),
+            vec![Err(EventError::EndOfInput {
+                expected: "payload"
+            })]
+        );
+        assert_eq!(
+            events::<4>(b"$2\r\nHello\r\n"),
+            vec![Err(unexpected(EXPECTED_CRLF, 6..8))]
+        );
+        assert_eq!(
+            events::<4>(b"$2\r\nHe\r"),
+            vec![Err(EventError::EndOfInput {
+                expected: EXPECTED_CRLF
+            })]
+        );
+        assert_eq!(
+            events::<4>(b"*1\r\n>0\r\n"),
+            vec![
+                Ok(Event::Start(Aggregate::Array, 1)),
+                Err(unexpected("value other than a push in an aggregate", 4..5)),
+            ]
+        );
+        assert_eq!(
+            events::<4>(b":1\r\n#x\r\n"),
+            vec![Ok(Event::Integer(1)), Err(unexpected("t or f", 5..6))]
+        );
+    }
+
+    #[test]
+    fn event_error_message() {
+        let data = b"$2\r\nHello\r\n";
+        let err = events::<4>(data).remove(0).unwrap_err();
+        assert_eq!(err.to_string(), r#"expected "\r\n", found bytes 6..8"#);
+        assert_eq!(&data[err.span().unwrap()], b"ll");
+        let err = EventError::EndOfInput {
+            expected: "payload",
+        };
+        assert_eq!(format!("{err}"), "expected payload, found end of input");
+        assert_eq!(err.span(), None);
+    }
+}
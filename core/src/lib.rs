@@ -0,0 +1,7 @@
+//! Parts of `resp-parser-rs` that neither allocate nor need the standard
+//! library, for targets without either. The main crate re-exports them.
+
+#![no_std]
+
+pub mod command;
+pub mod events;
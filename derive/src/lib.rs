@@ -0,0 +1,322 @@
+//! Derive macros for `resp-parser-rs`, enabled with its `derive` feature and
+//! used through its re-exports.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, LitStr, Member, Result, Token, Type,
+};
+
+/// Implements `ToResp` for a struct describing a command, building it as an
+/// array of bulk strings: the command name followed by the fields, in order.
+///
+/// The name is given with `#[command(name = "...")]`, and defaults to the
+/// struct's name in upper case. Names of several words, like
+/// `"CLIENT SETNAME"`, are written as one argument per word. Fields are written with their `Display`
+/// implementation, and attributes change how:
+///
+/// - `#[command(flag = "NX")]` on a `bool` writes `NX` when it's true.
+/// - `#[command(token = "EX")]` on an `Option` writes `EX` followed by the
+///   value when there is one.
+/// - `#[command(optional)]` on an `Option` writes the value when there is
+///   one.
+/// - `#[command(multiple)]` on a collection writes each of its elements.
+///
+/// ```
+/// use resp_parser_rs::{ToCommand, ToResp};
+///
+/// #[derive(ToCommand)]
+/// #[command(name = "SET")]
+/// struct Set {
+///     key: String,
+///     value: String,
+///     #[command(token = "EX")]
+///     seconds: Option<u64>,
+///     #[command(flag = "NX")]
+///     if_missing: bool,
+/// }
+///
+/// let set = Set {
+///     key: "a".into(),
+///     value: "1".into(),
+///     seconds: Some(10),
+///     if_missing: false,
+/// };
+/// let encoded = set.to_resp().encode().unwrap();
+/// assert!(encoded.ends_with(b"$2\r\nEX\r\n$2\r\n10\r\n"));
+/// ```
+#[proc_macro_derive(ToCommand, attributes(command))]
+pub fn derive_to_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_command(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is written as arguments.
+enum Argument {
+    Value,
+    Flag(LitStr),
+    Token(LitStr),
+    Optional,
+    Multiple,
+}
+
+fn to_command(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(x) => &x.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "ToCommand can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut name = None;
+    for attribute in input.attrs.iter().filter(|x| x.path().is_ident("command")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let value = meta.value()?.parse::<LitStr>()?;
+                if value.value().trim().is_empty() {
+                    return Err(Error::new_spanned(value, "the name can't be empty"));
+                }
+                name = Some(value.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        })?;
+    }
+    let name = name.unwrap_or_else(|| input.ident.to_string().to_uppercase());
+    let words = name.split_whitespace();
+
+    let mut arguments = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(x) => Member::Named(x.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        let argument = field_argument(field)?;
+        arguments.push(match argument {
+            Argument::Value => quote! {
+                arguments.push(bulk(&self.#member));
+            },
+            Argument::Flag(token) => quote! {
+                if self.#member {
+                    arguments.push(bulk(&#token));
+                }
+            },
+            Argument::Token(token) => quote! {
+                if let ::std::option::Option::Some(value) = &self.#member {
+                    arguments.push(bulk(&#token));
+                    arguments.push(bulk(value));
+                }
+            },
+            Argument::Optional => quote! {
+                if let ::std::option::Option::Some(value) = &self.#member {
+                    arguments.push(bulk(value));
+                }
+            },
+            Argument::Multiple => quote! {
+                for value in &self.#member {
+                    arguments.push(bulk(value));
+                }
+            },
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::resp_parser_rs::ToResp for #ident #type_generics #where_clause {
+            fn to_resp(&self) -> ::resp_parser_rs::RESP {
+                fn bulk<T: ::std::fmt::Display + ?::std::marker::Sized>(
+                    value: &T,
+                ) -> ::resp_parser_rs::RESP {
+                    ::resp_parser_rs::RESP::BulkString(::std::string::ToString::to_string(value))
+                }
+
+                let mut arguments = ::std::vec![#(bulk(#words)),*];
+                #(#arguments)*
+                ::resp_parser_rs::RESP::Array(arguments)
+            }
+        }
+    })
+}
+
+fn field_argument(field: &Field) -> Result<Argument> {
+    let mut argument = None;
+    for attribute in field.attrs.iter().filter(|x| x.path().is_ident("command")) {
+        attribute.parse_nested_meta(|meta| {
+            let kind = if meta.path.is_ident("flag") {
+                Argument::Flag(meta.value()?.parse()?)
+            } else if meta.path.is_ident("token") {
+                Argument::Token(meta.value()?.parse()?)
+            } else if meta.path.is_ident("optional") {
+                Argument::Optional
+            } else if meta.path.is_ident("multiple") {
+                Argument::Multiple
+            } else {
+                return Err(meta.error("expected `flag`, `token`, `optional` or `multiple`"));
+            };
+            if argument.replace(kind).is_some() {
+                return Err(meta.error("a field is written in a single way"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(argument.unwrap_or(Argument::Value))
+}
+
+/// Implements `FromResp` for an enum of the strings a status field is
+/// replied with, matching them ignoring ASCII case, along with an `as_str`
+/// method.
+///
+/// Variants match their name in lower case, or the strings listed with
+/// `#[reply("...", ...)]`, the first one being returned by `as_str`. The
+/// enum must have an `Unknown(String)` variant, holding any other string so
+/// values added by later server versions don't fail the conversion.
+///
+/// ```
+/// use resp_parser_rs::{FromResp, ReplyEnum, RESP};
+///
+/// #[derive(Debug, PartialEq, Eq, ReplyEnum)]
+/// enum Role {
+///     Master,
+///     #[reply("slave", "replica")]
+///     Replica,
+///     Sentinel,
+///     Unknown(String),
+/// }
+///
+/// assert_eq!(Role::from_resp(RESP::BulkString("SLAVE".into())), Ok(Role::Replica));
+/// assert_eq!(Role::Replica.as_str(), "slave");
+/// let unknown = Role::from_resp(RESP::SimpleString("arbiter".into()));
+/// assert_eq!(unknown, Ok(Role::Unknown("arbiter".to_owned())));
+/// ```
+///
+/// `Unknown` holding anything else is rejected:
+///
+/// ```compile_fail
+/// use resp_parser_rs::ReplyEnum;
+///
+/// #[derive(ReplyEnum)]
+/// enum Code {
+///     Ok,
+///     Unknown(u32),
+/// }
+/// ```
+#[proc_macro_derive(ReplyEnum, attributes(reply))]
+pub fn derive_reply_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    reply_enum(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn reply_enum(input: DeriveInput) -> Result<TokenStream2> {
+    let variants = match &input.data {
+        Data::Enum(x) => &x.variants,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "ReplyEnum can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut unknown = false;
+    let mut names = Vec::new();
+    let mut texts = Vec::new();
+    for variant in variants {
+        let ident = &variant.ident;
+        if ident == "Unknown" {
+            let field = match &variant.fields {
+                Fields::Unnamed(x) if x.unnamed.len() == 1 => &x.unnamed[0],
+                _ => return Err(Error::new_spanned(variant, "expected `Unknown(String)`")),
+            };
+            if !is_string(&field.ty) {
+                return Err(Error::new_spanned(field, "Unknown must hold a String"));
+            }
+            unknown = true;
+            continue;
+        }
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "variants other than `Unknown` can't have fields",
+            ));
+        }
+        let mut text = Vec::new();
+        for attribute in variant.attrs.iter().filter(|x| x.path().is_ident("reply")) {
+            text.extend(
+                attribute.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?,
+            );
+        }
+        if text.is_empty() {
+            text.push(LitStr::new(&ident.to_string().to_lowercase(), ident.span()));
+        }
+        names.push(ident);
+        texts.push(text);
+    }
+    if !unknown {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ReplyEnum needs an `Unknown(String)` variant",
+        ));
+    }
+
+    let ident = &input.ident;
+    let first = texts.iter().map(|x| &x[0]);
+    let matches = names.iter().zip(&texts).map(|(name, text)| {
+        quote! {
+            #(
+                if text.eq_ignore_ascii_case(#text) {
+                    return ::std::result::Result::Ok(Self::#name);
+                }
+            )*
+        }
+    });
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #type_generics #where_clause {
+            /// The string replied for this value.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    #(Self::#names => #first,)*
+                    Self::Unknown(x) => x,
+                }
+            }
+        }
+
+        impl #impl_generics ::resp_parser_rs::FromResp for #ident #type_generics #where_clause {
+            fn from_resp(
+                value: ::resp_parser_rs::RESP,
+            ) -> ::std::result::Result<Self, ::resp_parser_rs::ConversionError> {
+                let text = <::std::string::String as ::resp_parser_rs::FromResp>::from_resp(value)?;
+                #(#matches)*
+                ::std::result::Result::Ok(Self::Unknown(text))
+            }
+        }
+    })
+}
+
+/// Whether `ty` names `String`, alone or through a path like
+/// `std::string::String`.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(x) => {
+            x.qself.is_none()
+                && x.path
+                    .segments
+                    .last()
+                    .is_some_and(|x| x.ident == "String" && x.arguments.is_none())
+        }
+        Type::Group(x) => is_string(&x.elem),
+        Type::Paren(x) => is_string(&x.elem),
+        _ => false,
+    }
+}
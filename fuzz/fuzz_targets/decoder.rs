@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use resp_parser_rs::Decoder;
+
+fuzz_target!(|data: &[u8]| {
+    // Feed in two halves so partial frames are exercised as well
+    let (head, tail) = data.split_at(data.len() / 2);
+    let mut decoder = Decoder::new();
+    decoder.feed(head);
+    while let Ok(Some(_)) = decoder.decode() {}
+    decoder.feed(tail);
+    while let Ok(Some(_)) = decoder.decode() {}
+});
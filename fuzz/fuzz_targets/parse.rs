@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use resp_parser_rs::{ParseConfig, RESP};
+
+fuzz_target!(|data: &str| {
+    let _ = RESP::try_parse(data);
+    let config = ParseConfig {
+        decimal_doubles: true,
+        ..Default::default()
+    };
+    let _ = RESP::try_parse_with(data, &config);
+});
@@ -0,0 +1,394 @@
+use crate::{ShortString, RESP};
+
+/// Constructors for every variant, which keep working as variants are added
+/// while `RESP` is `#[non_exhaustive]`.
+impl RESP {
+    pub fn simple_string(value: impl Into<ShortString>) -> Self {
+        Self::SimpleString(value.into())
+    }
+
+    pub fn simple_error(message: impl Into<ShortString>) -> Self {
+        Self::SimpleError(message.into())
+    }
+
+    pub fn integer(value: i64) -> Self {
+        Self::Integer(value)
+    }
+
+    pub fn bulk_string(value: impl Into<String>) -> Self {
+        Self::BulkString(value.into())
+    }
+
+    pub fn null_bulk_string() -> Self {
+        Self::NullBulkString
+    }
+
+    pub fn array(elements: impl IntoIterator<Item = RESP>) -> Self {
+        Self::Array(elements.into_iter().collect())
+    }
+
+    pub fn null_array() -> Self {
+        Self::NullArray
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn null() -> Self {
+        Self::Null
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn boolean(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn double(value: f64) -> Self {
+        Self::Double(value)
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    pub fn decimal(value: rust_decimal::Decimal) -> Self {
+        Self::Decimal(value)
+    }
+
+    /// Big number made of `digits`, which aren't checked here but are when
+    /// the value is encoded.
+    #[cfg(feature = "resp3")]
+    pub fn big_number(digits: impl Into<String>) -> Self {
+        Self::BigNumber(digits.into())
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn bulk_error(message: impl Into<String>) -> Self {
+        Self::BulkError(message.into())
+    }
+
+    /// Verbatim string whose `encoding` is three bytes long, like `txt`.
+    #[cfg(feature = "resp3")]
+    pub fn verbatim_string(encoding: impl Into<ShortString>, data: impl Into<String>) -> Self {
+        Self::VerbatimString {
+            encoding: encoding.into(),
+            data: data.into(),
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn map(entries: impl IntoIterator<Item = (RESP, RESP)>) -> Self {
+        Self::Map(entries.into_iter().collect())
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn set(elements: impl IntoIterator<Item = RESP>) -> Self {
+        Self::Set(elements.into_iter().collect())
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn push(elements: impl IntoIterator<Item = RESP>) -> Self {
+        Self::Push(elements.into_iter().collect())
+    }
+
+    pub fn inline<I>(arguments: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self::Inline(arguments.into_iter().map(Into::into).collect())
+    }
+
+    /// `value` annotated with `attributes`.
+    #[cfg(feature = "resp3")]
+    pub fn with_attributes(
+        attributes: impl IntoIterator<Item = (RESP, RESP)>,
+        value: RESP,
+    ) -> Self {
+        Self::WithAttributes {
+            attributes: attributes.into_iter().collect(),
+            value: Box::new(value),
+        }
+    }
+}
+
+/// Accessors returning `None` when the value is of another type. Like
+/// conversions, they look through attributes, which are available from
+/// [`attributes`](RESP::attributes).
+impl RESP {
+    /// The value without its attributes.
+    pub fn inner(&self) -> &RESP {
+        match self {
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { value, .. } => value.inner(),
+            x => x,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn attributes(&self) -> Option<&[(RESP, RESP)]> {
+        match self {
+            Self::WithAttributes { attributes, .. } => Some(attributes),
+            _ => None,
+        }
+    }
+
+    pub fn as_simple_string(&self) -> Option<&str> {
+        match self.inner() {
+            Self::SimpleString(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_simple_error(&self) -> Option<&str> {
+        match self.inner() {
+            Self::SimpleError(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self.inner() {
+            Self::Integer(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bulk_string(&self) -> Option<&str> {
+        match self.inner() {
+            Self::BulkString(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[RESP]> {
+        match self.inner() {
+            Self::Array(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is the RESP3 null, a null bulk string or a null
+    /// array.
+    pub fn is_null(&self) -> bool {
+        match self.inner() {
+            Self::NullBulkString | Self::NullArray => true,
+            #[cfg(feature = "resp3")]
+            Self::Null => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self.inner() {
+            Self::Boolean(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_double(&self) -> Option<f64> {
+        match self.inner() {
+            Self::Double(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    pub fn as_decimal(&self) -> Option<rust_decimal::Decimal> {
+        match self.inner() {
+            Self::Decimal(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Digits of a big number, with a leading `-` when it's negative.
+    #[cfg(feature = "resp3")]
+    pub fn as_big_number(&self) -> Option<&str> {
+        match self.inner() {
+            Self::BigNumber(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_bulk_error(&self) -> Option<&str> {
+        match self.inner() {
+            Self::BulkError(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Encoding and data of a verbatim string.
+    #[cfg(feature = "resp3")]
+    pub fn as_verbatim_string(&self) -> Option<(&str, &str)> {
+        match self.inner() {
+            Self::VerbatimString { encoding, data } => Some((encoding, data)),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_map(&self) -> Option<&[(RESP, RESP)]> {
+        match self.inner() {
+            Self::Map(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_set(&self) -> Option<&[RESP]> {
+        match self.inner() {
+            Self::Set(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    pub fn as_push(&self) -> Option<&[RESP]> {
+        match self.inner() {
+            Self::Push(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_inline(&self) -> Option<&[String]> {
+        match self.inner() {
+            Self::Inline(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "resp3")]
+fn pairs_eq_ignoring_attributes(left: &[(RESP, RESP)], right: &[(RESP, RESP)]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .all(|((left_key, left), (right_key, right))| {
+                left_key.eq_ignoring_attributes(right_key) && left.eq_ignoring_attributes(right)
+            })
+}
+
+fn elements_eq_ignoring_attributes(left: &[RESP], right: &[RESP]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .all(|(left, right)| left.eq_ignoring_attributes(right))
+}
+
+/// Attributes carry metadata, like key popularity hints, that can change
+/// between replies holding the same data.
+impl RESP {
+    /// Whether both values hold the same data once attributes are dropped,
+    /// at any depth.
+    pub fn eq_ignoring_attributes(&self, other: &RESP) -> bool {
+        match (self.inner(), other.inner()) {
+            (Self::Array(left), Self::Array(right)) => elements_eq_ignoring_attributes(left, right),
+            #[cfg(feature = "resp3")]
+            (Self::Set(left), Self::Set(right)) | (Self::Push(left), Self::Push(right)) => {
+                elements_eq_ignoring_attributes(left, right)
+            }
+            #[cfg(feature = "resp3")]
+            (Self::Map(left), Self::Map(right)) => pairs_eq_ignoring_attributes(left, right),
+            (left, right) => left == right,
+        }
+    }
+
+    /// The value with attributes dropped at any depth.
+    pub fn strip_attributes(self) -> Self {
+        let strip = |x: Vec<RESP>| x.into_iter().map(Self::strip_attributes).collect();
+        match self {
+            Self::Array(x) => Self::Array(strip(x)),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) => Self::Set(strip(x)),
+            #[cfg(feature = "resp3")]
+            Self::Push(x) => Self::Push(strip(x)),
+            #[cfg(feature = "resp3")]
+            Self::Map(x) => Self::Map(
+                x.into_iter()
+                    .map(|(key, value)| (key.strip_attributes(), value.strip_attributes()))
+                    .collect(),
+            ),
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { value, .. } => value.strip_attributes(),
+            x => x,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors_match_parser() {
+        let parsed =
+            RESP::parse("*4\r\n+OK\r\n$-1\r\n=7\r\ntxt:abc\r\n%1\r\n:1\r\n#t\r\n").unwrap();
+        let built = RESP::array([
+            RESP::simple_string("OK"),
+            RESP::null_bulk_string(),
+            RESP::verbatim_string("txt", "abc"),
+            RESP::map([(RESP::integer(1), RESP::boolean(true))]),
+        ]);
+        assert_eq!(parsed, built);
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn inline_constructor() {
+        assert_eq!(
+            RESP::parse("PING now\r\n"),
+            Some(RESP::inline(["PING", "now"]))
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let parsed = RESP::parse("*3\r\n$1\r\na\r\n:2\r\n_\r\n").unwrap();
+        let elements = parsed.as_array().unwrap();
+        assert_eq!(elements[0].as_bulk_string(), Some("a"));
+        assert_eq!(elements[0].as_simple_string(), None);
+        assert_eq!(elements[1].as_integer(), Some(2));
+        assert!(elements[2].is_null());
+        assert_eq!(parsed.as_set(), None);
+        assert_eq!(
+            RESP::parse("=7\r\nmkd:abc\r\n")
+                .unwrap()
+                .as_verbatim_string(),
+            Some(("mkd", "abc"))
+        );
+    }
+
+    #[test]
+    fn accessors_through_attributes() {
+        let parsed = RESP::parse("|1\r\n+ttl\r\n:3\r\n(12345678901234567890\r\n").unwrap();
+        assert_eq!(parsed.as_big_number(), Some("12345678901234567890"));
+        assert_eq!(
+            parsed.attributes(),
+            Some(&[(RESP::simple_string("ttl"), RESP::integer(3))][..])
+        );
+        assert_eq!(parsed.inner(), &RESP::big_number("12345678901234567890"));
+        assert_eq!(RESP::integer(1).attributes(), None);
+    }
+
+    #[test]
+    fn ignoring_attributes() {
+        let left = RESP::parse("*2\r\n|1\r\n+popularity\r\n,0.9\r\n$1\r\na\r\n%1\r\n+b\r\n:1\r\n")
+            .unwrap();
+        let right =
+            RESP::parse("|1\r\n+ttl\r\n:3\r\n*2\r\n$1\r\na\r\n%1\r\n+b\r\n|0\r\n:1\r\n").unwrap();
+        assert_ne!(left, right);
+        assert!(left.eq_ignoring_attributes(&right));
+        assert!(!left.eq_ignoring_attributes(&RESP::parse("*1\r\n$1\r\na\r\n").unwrap()));
+
+        let stripped = right.strip_attributes();
+        assert_eq!(stripped, left.strip_attributes());
+        assert_eq!(
+            stripped,
+            RESP::array([
+                RESP::bulk_string("a"),
+                RESP::map([(RESP::simple_string("b"), RESP::integer(1))]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Input handling shared by the command line tools.
+
+use std::fs;
+use std::io;
+
+use resp_parser_rs::capture::{CaptureReader, Direction, MAGIC};
+use resp_parser_rs::{RawFrames, RESP};
+
+/// A frame read from a capture or a raw stream.
+pub struct Frame {
+    /// `None` for raw streams, which don't record it.
+    pub direction: Option<Direction>,
+    pub raw: Vec<u8>,
+    /// The decoded frame, or why it couldn't be decoded.
+    pub value: Result<RESP, String>,
+}
+
+/// Decodes a whole frame.
+pub fn parse(raw: &[u8]) -> Result<RESP, String> {
+    let text = std::str::from_utf8(raw).map_err(|_| "frame is not valid UTF-8".to_owned())?;
+    RESP::try_parse(text).map_err(|err| err.to_string())
+}
+
+/// Reads every frame of the file at `path`, either a capture or raw RESP
+/// such as an AOF file. A truncated or malformed tail of a raw stream is
+/// kept as a last frame failing to decode.
+pub fn load(path: &str) -> io::Result<Vec<Frame>> {
+    let data = fs::read(path)?;
+    if data.starts_with(MAGIC) {
+        return CaptureReader::new(&data[..])?
+            .map(|record| {
+                let record = record?;
+                Ok(Frame {
+                    direction: Some(record.direction),
+                    value: parse(&record.frame),
+                    raw: record.frame,
+                })
+            })
+            .collect();
+    }
+
+    let mut frames = RawFrames::new(&data);
+    let mut loaded = Vec::new();
+    loop {
+        let rest = frames.remaining();
+        let (raw, value) = match frames.next() {
+            None => return Ok(loaded),
+            Some(Ok(raw)) => (raw, parse(raw)),
+            Some(Err(err)) => (rest, Err(err.to_string())),
+        };
+        loaded.push(Frame {
+            direction: None,
+            raw: raw.to_vec(),
+            value,
+        });
+    }
+}
+
+/// Parses the value of `--only`.
+pub fn parse_direction(text: &str) -> Result<Direction, String> {
+    match text {
+        "inbound" => Ok(Direction::Inbound),
+        "outbound" => Ok(Direction::Outbound),
+        _ => Err(format!("expected inbound or outbound, found {text:?}")),
+    }
+}
+
+/// Keeps the frames travelling in `direction`, frames of raw streams always
+/// being kept.
+pub fn only(frames: Vec<Frame>, direction: Option<Direction>) -> Vec<Frame> {
+    frames
+        .into_iter()
+        .filter(|frame| {
+            direction.is_none() || frame.direction.is_none() || frame.direction == direction
+        })
+        .collect()
+}
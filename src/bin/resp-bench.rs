@@ -0,0 +1,256 @@
+//! Sends a mix of commands to a Redis server over several connections and
+//! reports throughput and latency, encoding requests with `FrameWriter` and
+//! decoding replies with `Decoder`.
+//!
+//! Latency is measured per pipelined batch, from sending its first request
+//! to decoding its last reply.
+
+use std::io::Read;
+use std::net::TcpStream;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use resp_parser_rs::{Decoder, FrameWriter, RESP};
+
+const USAGE: &str = "usage: resp-bench [--connections N] [--requests N] [--pipeline N] \
+    [--mix COMMAND=WEIGHT,...] [--keys N] [--value-size N] ADDRESS";
+
+/// Commands the mix can be made of.
+const COMMANDS: &[&str] = &["get", "set", "incr", "del", "ping"];
+
+struct Args {
+    address: String,
+    connections: usize,
+    requests: usize,
+    pipeline: usize,
+    mix: Vec<(&'static str, usize)>,
+    keys: usize,
+    value_size: usize,
+}
+
+fn parse_count(option: &str, value: Option<String>) -> Result<usize, String> {
+    let value = value.ok_or(format!("{option} needs a count"))?;
+    match value.parse() {
+        Ok(0) | Err(_) => Err(format!("expected a positive count, found {value:?}")),
+        Ok(x) => Ok(x),
+    }
+}
+
+/// Parses the value of `--mix`, like `get=80,set=20`.
+fn parse_mix(text: &str) -> Result<Vec<(&'static str, usize)>, String> {
+    let mut mix = Vec::new();
+    for entry in text.split(',') {
+        let (name, weight) = entry.split_once('=').unwrap_or((entry, "1"));
+        let command = COMMANDS
+            .iter()
+            .find(|x| x.eq_ignore_ascii_case(name))
+            .ok_or(format!(
+                "unknown command {name:?}, expected one of {COMMANDS:?}"
+            ))?;
+        let weight = weight
+            .parse()
+            .map_err(|_| format!("expected a weight, found {weight:?}"))?;
+        mix.push((*command, weight));
+    }
+    match mix.iter().map(|(_, weight)| weight).sum::<usize>() {
+        0 => Err("the mix needs a positive weight".to_owned()),
+        _ => Ok(mix),
+    }
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut connections = 4;
+    let mut requests = 100_000;
+    let mut pipeline = 1;
+    let mut mix = vec![("get", 80), ("set", 20)];
+    let mut keys = 1000;
+    let mut value_size = 32;
+    let mut addresses = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--connections" => connections = parse_count(&arg, args.next())?,
+            "--requests" => requests = parse_count(&arg, args.next())?,
+            "--pipeline" => pipeline = parse_count(&arg, args.next())?,
+            "--keys" => keys = parse_count(&arg, args.next())?,
+            "--value-size" => value_size = parse_count(&arg, args.next())?,
+            "--mix" => mix = parse_mix(&args.next().ok_or("--mix needs commands")?)?,
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => addresses.push(arg),
+        }
+    }
+    match <[String; 1]>::try_from(addresses) {
+        Ok([address]) => Ok(Args {
+            address,
+            connections,
+            requests,
+            pipeline,
+            mix,
+            keys,
+            value_size,
+        }),
+        Err(_) => Err(USAGE.to_owned()),
+    }
+}
+
+/// Picks commands following the weights of the mix, with a fixed seed so
+/// runs are comparable.
+struct Generator<'a> {
+    args: &'a Args,
+    state: u64,
+    value: String,
+}
+
+impl Generator<'_> {
+    /// xorshift64, enough to spread commands and keys.
+    fn next_random(&mut self) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state as usize
+    }
+
+    fn command(&mut self) -> RESP {
+        let total: usize = self.args.mix.iter().map(|(_, weight)| weight).sum();
+        let mut pick = self.next_random() % total;
+        let name = self
+            .args
+            .mix
+            .iter()
+            .find(|(_, weight)| match pick.checked_sub(*weight) {
+                Some(rest) => {
+                    pick = rest;
+                    false
+                }
+                None => true,
+            })
+            .map_or("ping", |(name, _)| name);
+        let key = format!("key:{}", self.next_random() % self.args.keys);
+        let command = name.to_uppercase();
+        let arguments = match name {
+            "set" => vec![&command, &key, &self.value],
+            "ping" => vec![&command],
+            _ => vec![&command, &key],
+        };
+        RESP::Array(
+            arguments
+                .into_iter()
+                .map(|x| RESP::BulkString(x.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// What a connection measured.
+#[derive(Default)]
+struct Results {
+    requests: usize,
+    errors: usize,
+    latencies: Vec<Duration>,
+}
+
+/// Sends `requests` commands over one connection.
+fn run_connection(args: &Args, seed: u64, requests: usize) -> Result<Results, String> {
+    let stream = TcpStream::connect(&args.address).map_err(|err| err.to_string())?;
+    stream.set_nodelay(true).map_err(|err| err.to_string())?;
+    let mut writer = FrameWriter::new(&stream);
+    let mut reader = &stream;
+    let mut decoder = Decoder::new();
+    let mut generator = Generator {
+        args,
+        state: seed,
+        value: "x".repeat(args.value_size),
+    };
+    let mut results = Results::default();
+    let mut chunk = vec![0; 64 * 1024];
+    while results.requests < requests {
+        let batch = args.pipeline.min(requests - results.requests);
+        let started = Instant::now();
+        for _ in 0..batch {
+            writer
+                .write_frame(&generator.command())
+                .map_err(|err| err.to_string())?;
+        }
+        writer.flush().map_err(|err| err.to_string())?;
+        let mut replies = 0;
+        while replies < batch {
+            match decoder.decode().map_err(|err| err.to_string())? {
+                Some(RESP::SimpleError(_)) => {
+                    results.errors += 1;
+                    replies += 1;
+                }
+                Some(_) => replies += 1,
+                None => match reader.read(&mut chunk).map_err(|err| err.to_string())? {
+                    0 => return Err("connection closed by the server".to_owned()),
+                    read => decoder.feed(&chunk[..read]),
+                },
+            }
+        }
+        results.latencies.push(started.elapsed());
+        results.requests += batch;
+    }
+    Ok(results)
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let started = Instant::now();
+    let connections: Vec<Results> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..args.connections)
+            .map(|index| {
+                let requests = args.requests / args.connections
+                    + usize::from(index < args.requests % args.connections);
+                let seed = 0x9e37_79b9_7f4a_7c15 ^ index as u64;
+                scope.spawn(move || run_connection(args, seed, requests))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("connection panicked"))
+            .collect::<Result<_, _>>()
+    })?;
+    let elapsed = started.elapsed();
+
+    let requests: usize = connections.iter().map(|x| x.requests).sum();
+    let errors: usize = connections.iter().map(|x| x.errors).sum();
+    let mut latencies: Vec<_> = connections.into_iter().flat_map(|x| x.latencies).collect();
+    latencies.sort_unstable();
+    let percentile = |percent: usize| {
+        let rank = (latencies.len() * percent).div_ceil(100).max(1);
+        latencies.get(rank - 1).copied().unwrap_or_default()
+    };
+    println!(
+        "{requests} requests in {:.2?} over {} connections, pipeline {}",
+        elapsed, args.connections, args.pipeline
+    );
+    println!(
+        "throughput: {:.0} requests/s",
+        requests as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "batch latency: p50 {:.2?}, p90 {:.2?}, p99 {:.2?}, max {:.2?}",
+        percentile(50),
+        percentile(90),
+        percentile(99),
+        percentile(100)
+    );
+    println!("errors: {errors}");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("resp-bench: {err}");
+            ExitCode::from(1)
+        }
+    }
+}
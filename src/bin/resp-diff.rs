@@ -0,0 +1,109 @@
+//! Compares two captures or raw RESP streams frame by frame, listing where
+//! their decoded frames differ, e.g. to check that a proxy or a migration
+//! preserves replies.
+//!
+//! Exits with status 1 when they differ and 2 when they can't be read.
+
+use std::process::ExitCode;
+
+use resp_parser_rs::capture::Direction;
+use resp_parser_rs::testing::diff;
+
+// Shared with the other tools, which use all of it
+#[allow(dead_code)]
+mod common;
+
+use common::{load, only, parse_direction, Frame};
+
+const USAGE: &str = "usage: resp-diff [--only inbound|outbound] LEFT RIGHT";
+
+struct Args {
+    direction: Option<Direction>,
+    left: String,
+    right: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut direction = None;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--only" => {
+                let value = args.next().ok_or("--only needs a direction")?;
+                direction = Some(parse_direction(&value)?);
+            }
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => paths.push(arg),
+        }
+    }
+    match <[String; 2]>::try_from(paths) {
+        Ok([left, right]) => Ok(Args {
+            direction,
+            left,
+            right,
+        }),
+        Err(_) => Err(USAGE.to_owned()),
+    }
+}
+
+/// Differences between two frames, empty when they match.
+fn compare(left: &Frame, right: &Frame) -> Vec<String> {
+    match (&left.value, &right.value) {
+        (Ok(left), Ok(right)) => diff(left, right),
+        (Err(left), Err(right)) if left == right => Vec::new(),
+        (Err(err), _) => vec![format!("left doesn't decode: {err}")],
+        (_, Err(err)) => vec![format!("right doesn't decode: {err}")],
+    }
+}
+
+fn run(args: &Args) -> Result<bool, String> {
+    let read = |path: &str| {
+        load(path)
+            .map(|frames| only(frames, args.direction))
+            .map_err(|err| format!("{path}: {err}"))
+    };
+    let left = read(&args.left)?;
+    let right = read(&args.right)?;
+
+    let mut differing = 0;
+    for (index, (left, right)) in left.iter().zip(&right).enumerate() {
+        let differences = compare(left, right);
+        if !differences.is_empty() {
+            differing += 1;
+            println!("frame {index}:");
+            for difference in differences {
+                println!("  {difference}");
+            }
+        }
+    }
+    let compared = left.len().min(right.len());
+    if left.len() != right.len() {
+        let (side, extra) = if left.len() > right.len() {
+            ("left", left.len() - compared)
+        } else {
+            ("right", right.len() - compared)
+        };
+        println!("{side} has {extra} more frames");
+    }
+    println!("{differing} of {compared} frames differ");
+    Ok(differing == 0 && left.len() == right.len())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("resp-diff: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Prints a capture as newline-delimited JSON, one record per line, to load
+//! it into jq or a log pipeline.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::process::ExitCode;
+
+use resp_parser_rs::capture::{export_ndjson, CaptureReader};
+
+const USAGE: &str = "usage: resp-export CAPTURE";
+
+fn run(path: &str) -> io::Result<()> {
+    let reader = CaptureReader::new(BufReader::new(File::open(path)?))?;
+    export_ndjson(reader, BufWriter::new(io::stdout().lock()))?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match &args[..] {
+        [path] if path != "-h" && path != "--help" => path,
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(path) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("resp-export: {path}: {err}");
+            ExitCode::from(1)
+        }
+    }
+}
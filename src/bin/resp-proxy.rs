@@ -0,0 +1,200 @@
+//! Listens locally and forwards connections to a Redis server, logging every
+//! request along with its reply, to watch what a client actually sends.
+//!
+//! Frames are forwarded byte for byte as soon as they are complete. With
+//! `--capture FILE`, they are also recorded to a capture that `resp-diff` and
+//! `resp-stats` read, requests as inbound frames and replies as outbound ones.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use resp_parser_rs::capture::{CaptureWriter, Direction};
+use resp_parser_rs::relay;
+
+// Shared with the other tools, which use all of it
+#[allow(dead_code)]
+mod common;
+
+use common::parse;
+
+const USAGE: &str =
+    "usage: resp-proxy [--listen ADDRESS] [--capture FILE] [--max-bytes N] UPSTREAM";
+
+struct Args {
+    listen: String,
+    upstream: String,
+    capture: Option<String>,
+    max_bytes: usize,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut listen = "127.0.0.1:6380".to_owned();
+    let mut capture = None;
+    let mut max_bytes = 200;
+    let mut addresses = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+            "--capture" => capture = Some(args.next().ok_or("--capture needs a file")?),
+            "--max-bytes" => {
+                let value = args.next().ok_or("--max-bytes needs a count")?;
+                max_bytes = value
+                    .parse()
+                    .map_err(|_| format!("expected a count, found {value:?}"))?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => addresses.push(arg),
+        }
+    }
+    match <[String; 1]>::try_from(addresses) {
+        Ok([upstream]) => Ok(Args {
+            listen,
+            upstream,
+            capture,
+            max_bytes,
+        }),
+        Err(_) => Err(USAGE.to_owned()),
+    }
+}
+
+type Capture = Arc<Mutex<Option<CaptureWriter<BufWriter<File>>>>>;
+
+/// State shared by the two directions of a connection.
+struct Connection {
+    id: usize,
+    max_bytes: usize,
+    capture: Capture,
+    /// Requests forwarded and still waiting for their reply, already
+    /// formatted.
+    pending: Mutex<VecDeque<String>>,
+}
+
+impl Connection {
+    fn describe(&self, frame: &[u8]) -> String {
+        match parse(frame) {
+            Ok(value) => value.redacted().inspect(self.max_bytes).to_string(),
+            Err(err) => format!("<{err}>"),
+        }
+    }
+
+    fn record(&self, direction: Direction, frame: &[u8]) {
+        let mut capture = self.capture.lock().unwrap();
+        if let Some(writer) = capture.as_mut() {
+            if let Err(err) = writer.record(direction, frame).and_then(|_| writer.flush()) {
+                eprintln!("resp-proxy: capture: {err}, stopping it");
+                *capture = None;
+            }
+        }
+    }
+
+    fn request(&self, frame: &[u8]) -> bool {
+        // Passwords are kept out of captures as well as the log
+        let redacted = parse(frame).ok().and_then(|x| x.redacted().encode().ok());
+        self.record(Direction::Inbound, redacted.as_deref().unwrap_or(frame));
+        let request = self.describe(frame);
+        self.pending.lock().unwrap().push_back(request);
+        true
+    }
+
+    fn reply(&self, frame: &[u8]) -> bool {
+        self.record(Direction::Outbound, frame);
+        let reply = self.describe(frame);
+        let request = match is_push(frame) {
+            true => None,
+            false => self.pending.lock().unwrap().pop_front(),
+        };
+        match request {
+            Some(request) => println!("[{}] {request} -> {reply}", self.id),
+            None => println!("[{}] -> {reply}", self.id),
+        }
+        true
+    }
+}
+
+/// Whether `frame` is a push, which isn't the reply to a request.
+fn is_push(frame: &[u8]) -> bool {
+    frame.starts_with(b">")
+}
+
+/// Forwards one client connection until either side closes it.
+fn proxy(client: TcpStream, connection: Connection, upstream: &str) -> Result<(), String> {
+    let server = TcpStream::connect(upstream).map_err(|err| format!("{upstream}: {err}"))?;
+    let connection = Arc::new(connection);
+    let requests = {
+        let client = client.try_clone().map_err(|err| err.to_string())?;
+        let server = server.try_clone().map_err(|err| err.to_string())?;
+        let connection = Arc::clone(&connection);
+        thread::spawn(move || {
+            let result = relay(&client, &server, |frame| connection.request(frame));
+            let _ = server.shutdown(Shutdown::Write);
+            result
+        })
+    };
+    let replies = relay(&server, &client, |frame| connection.reply(frame));
+    let _ = client.shutdown(Shutdown::Both);
+    let requests = requests.join().expect("request relay panicked");
+    requests
+        .and(replies)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(&args.listen).map_err(|err| format!("{}: {err}", args.listen))?;
+    let capture = match &args.capture {
+        Some(path) => {
+            let file = File::create(path).map_err(|err| format!("{path}: {err}"))?;
+            let writer =
+                CaptureWriter::new(BufWriter::new(file)).map_err(|err| format!("{path}: {err}"))?;
+            Some(writer)
+        }
+        None => None,
+    };
+    let capture: Capture = Arc::new(Mutex::new(capture));
+    eprintln!(
+        "resp-proxy: forwarding {} to {}",
+        args.listen, args.upstream
+    );
+
+    let upstream = Arc::new(args.upstream);
+    for (id, client) in listener.incoming().enumerate() {
+        let client = client.map_err(|err| err.to_string())?;
+        let connection = Connection {
+            id,
+            max_bytes: args.max_bytes,
+            capture: Arc::clone(&capture),
+            pending: Mutex::default(),
+        };
+        let upstream = Arc::clone(&upstream);
+        thread::spawn(move || {
+            if let Err(err) = proxy(client, connection, &upstream) {
+                eprintln!("resp-proxy: [{id}] {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("resp-proxy: {err}");
+            ExitCode::from(1)
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Summarizes a capture, or a raw RESP stream such as an AOF file: how often
+//! each command and frame type appears, how large frames are and which keys
+//! are used the most.
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use resp_parser_rs::capture::Direction;
+use resp_parser_rs::RESP;
+
+mod common;
+
+use common::{load, only, parse_direction, Frame};
+
+const USAGE: &str = "usage: resp-stats [--only inbound|outbound] [--top N] FILE";
+
+struct Args {
+    direction: Option<Direction>,
+    top: usize,
+    path: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut direction = None;
+    let mut top = 10;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--only" => {
+                let value = args.next().ok_or("--only needs a direction")?;
+                direction = Some(parse_direction(&value)?);
+            }
+            "--top" => {
+                let value = args.next().ok_or("--top needs a count")?;
+                top = value
+                    .parse()
+                    .map_err(|_| format!("expected a count, found {value:?}"))?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => paths.push(arg),
+        }
+    }
+    match <[String; 1]>::try_from(paths) {
+        Ok([path]) => Ok(Args {
+            direction,
+            top,
+            path,
+        }),
+        Err(_) => Err(USAGE.to_owned()),
+    }
+}
+
+/// Arguments of a command, sent as an array of bulk strings or inline.
+fn arguments(value: &RESP) -> Option<Vec<&str>> {
+    match value {
+        RESP::Array(elements) => elements
+            .iter()
+            .map(|x| match x {
+                RESP::BulkString(x) => Some(x.as_str()),
+                _ => None,
+            })
+            .collect(),
+        RESP::Inline(arguments) => Some(arguments.iter().map(String::as_str).collect()),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    frames: usize,
+    failed: usize,
+    bytes: usize,
+    sizes: Vec<usize>,
+    types: HashMap<&'static str, usize>,
+    commands: HashMap<String, usize>,
+    keys: HashMap<String, usize>,
+}
+
+impl Stats {
+    fn add(&mut self, frame: &Frame) {
+        self.frames += 1;
+        self.bytes += frame.raw.len();
+        self.sizes.push(frame.raw.len());
+        let Ok(value) = &frame.value else {
+            self.failed += 1;
+            return;
+        };
+        *self.types.entry(value.type_name()).or_default() += 1;
+        let Some(arguments) = arguments(value) else {
+            return;
+        };
+        if let Some(command) = arguments.first() {
+            *self.commands.entry(command.to_uppercase()).or_default() += 1;
+        }
+        // The first argument is the key for most commands
+        if let Some(key) = arguments.get(1) {
+            *self.keys.entry((*key).to_owned()).or_default() += 1;
+        }
+    }
+
+    /// Size below which `percent` of the frames are, by nearest rank.
+    fn percentile(&self, percent: usize) -> usize {
+        let rank = (self.sizes.len() * percent).div_ceil(100).max(1);
+        self.sizes[rank - 1]
+    }
+}
+
+/// Entries by decreasing count, the first `limit` of them.
+fn ranked<K: ToString>(counts: &HashMap<K, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut ranked: Vec<_> = counts
+        .iter()
+        .map(|(name, count)| (name.to_string(), *count))
+        .collect();
+    ranked.sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+fn print_section(title: &str, entries: Vec<(String, usize)>) {
+    if entries.is_empty() {
+        return;
+    }
+    println!();
+    println!("{title}:");
+    let width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, count) in entries {
+        println!("  {name:<width$}  {count}");
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let frames = load(&args.path).map_err(|err| format!("{}: {err}", args.path))?;
+    let mut stats = Stats::default();
+    for frame in only(frames, args.direction) {
+        stats.add(&frame);
+    }
+
+    println!(
+        "frames: {} ({} failed to decode)",
+        stats.frames, stats.failed
+    );
+    println!("bytes: {}", stats.bytes);
+    if stats.frames > 0 {
+        stats.sizes.sort_unstable();
+        println!(
+            "frame sizes: p50 {}, p90 {}, p99 {}, max {}",
+            stats.percentile(50),
+            stats.percentile(90),
+            stats.percentile(99),
+            stats.percentile(100)
+        );
+    }
+    print_section("types", ranked(&stats.types, usize::MAX));
+    print_section("commands", ranked(&stats.commands, args.top));
+    print_section("top keys", ranked(&stats.keys, args.top));
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("resp-stats: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
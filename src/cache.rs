@@ -0,0 +1,120 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use lru::LruCache;
+
+use crate::{ParseConfig, ParseError, RESP};
+
+/// Default size of the largest frame a [`FrameCache`] keeps.
+pub const DEFAULT_MAX_CACHED_FRAME_LEN: usize = 512;
+
+/// Least recently used cache of decoded frames keyed by their raw bytes, so
+/// frames seen over and over like `+OK` are only decoded once.
+///
+/// Only small frames are cached, as large ones rarely repeat and would evict
+/// the rest.
+#[derive(Debug)]
+pub struct FrameCache {
+    entries: LruCache<Box<[u8]>, Arc<RESP>>,
+    config: ParseConfig,
+    max_frame_len: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl FrameCache {
+    /// A cache holding up to `capacity` frames.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self::with_config(capacity, ParseConfig::default())
+    }
+
+    pub fn with_config(capacity: NonZeroUsize, config: ParseConfig) -> Self {
+        Self {
+            entries: LruCache::new(capacity),
+            config,
+            max_frame_len: DEFAULT_MAX_CACHED_FRAME_LEN,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Frames longer than `max_frame_len` bytes are decoded without being
+    /// cached.
+    pub fn max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Decodes `frame`, which has to hold exactly one frame like those
+    /// yielded by [`RawFrames`](crate::RawFrames), reusing the value decoded
+    /// from the same bytes before if it's still cached.
+    pub fn decode(&mut self, frame: &[u8]) -> Result<Arc<RESP>, ParseError> {
+        if let Some(value) = self.entries.get(frame) {
+            self.hits += 1;
+            return Ok(Arc::clone(value));
+        }
+        self.misses += 1;
+        let data = std::str::from_utf8(frame)
+            .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence"))?;
+        let value = Arc::new(RESP::parse_complete_with(data, &self.config)?);
+        if frame.len() <= self.max_frame_len {
+            self.entries.put(frame.into(), Arc::clone(&value));
+        }
+        Ok(value)
+    }
+
+    /// Frames decoded from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Frames that had to be decoded.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+    use crate::RawFrames;
+
+    #[test]
+    fn cache_hits() {
+        let mut cache = FrameCache::new(NonZeroUsize::new(2).unwrap());
+        let data = b"+OK\r\n:1\r\n+OK\r\n-ERR\r\n:2\r\n+OK\r\n";
+        let values = RawFrames::new(data)
+            .map(|x| cache.decode(x.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(Arc::ptr_eq(&values[0], &values[2]));
+        assert_eq!(*values[3], RESP::SimpleError("ERR".into()));
+        // Evicted by the two frames before it
+        assert!(!Arc::ptr_eq(&values[0], &values[5]));
+        assert_eq!(values[0], values[5]);
+        assert_eq!((cache.hits(), cache.misses()), (1, 5));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn cache_limits() {
+        let mut cache = FrameCache::new(NonZeroUsize::new(8).unwrap()).max_frame_len(4);
+        cache.decode(b"+OK\r\n").unwrap();
+        assert!(cache.is_empty());
+        assert!(matches!(cache.decode(b"+OK\r\n+OK\r\n"), Err(_)));
+        assert!(matches!(cache.decode(b":1\r\n"), Ok(_)));
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,427 @@
+//! On-disk format for recorded traffic, used for reproducible bug reports and
+//! offline analysis.
+//!
+//! A capture starts with [`MAGIC`] followed by records, each made of the
+//! timestamp in microseconds since the Unix epoch (`u64`), the direction
+//! (`u8`, 0 for inbound and 1 for outbound), the frame length (`u64`) and the
+//! raw frame bytes. Integers are big endian.
+//!
+//! Captures are read back with [`CaptureReader`] and re-emitted with
+//! [`Replayer`], or exported as newline-delimited JSON with
+//! [`export_ndjson`].
+
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::json::write_string;
+use crate::RESP;
+
+/// Bytes every capture starts with, the last one being the format version.
+pub const MAGIC: &[u8; 8] = b"RESPCAP\x01";
+
+/// Which way a recorded frame was travelling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the peer.
+    Inbound,
+    /// Sent to the peer.
+    Outbound,
+}
+
+impl Direction {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Inbound => 0,
+            Self::Outbound => 1,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        }
+    }
+
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(Self::Inbound),
+            1 => Ok(Self::Outbound),
+            _ => Err(invalid_data(format!("invalid direction {byte}"))),
+        }
+    }
+}
+
+/// A single recorded frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time elapsed since the Unix epoch when the frame was recorded.
+    pub timestamp: Duration,
+    pub direction: Direction,
+    /// Raw bytes of the frame, exactly as they were on the wire.
+    pub frame: Vec<u8>,
+}
+
+impl Record {
+    /// The record as a JSON object on a single line, with the fields
+    /// `timestamp_us`, `direction`, `size` and either `value`, the decoded
+    /// frame as [`RESP::to_json_string`] renders it, or `error` when the frame
+    /// doesn't decode.
+    pub fn to_json(&self) -> String {
+        let mut output = format!(
+            "{{\"timestamp_us\":{},\"direction\":\"{}\",\"size\":{},",
+            self.timestamp.as_micros(),
+            self.direction.name(),
+            self.frame.len()
+        );
+        let decoded = std::str::from_utf8(&self.frame)
+            .map_err(|_| "frame is not valid UTF-8".to_owned())
+            .and_then(|x| RESP::try_parse(x).map_err(|err| err.to_string()));
+        match decoded {
+            Ok(value) => {
+                output.push_str("\"value\":");
+                output.push_str(&value.to_json_string());
+            }
+            Err(err) => {
+                output.push_str("\"error\":");
+                write_string(&mut output, &err);
+            }
+        }
+        output.push('}');
+        output
+    }
+}
+
+/// Writes every record of a capture to `writer` as newline-delimited JSON,
+/// one [`Record::to_json`] object per line, for tools like jq or log
+/// pipelines. Returns how many records were written.
+pub fn export_ndjson<R: Read, W: Write>(
+    reader: CaptureReader<R>,
+    mut writer: W,
+) -> io::Result<usize> {
+    let mut count = 0;
+    for record in reader {
+        writeln!(writer, "{}", record?.to_json())?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes records to a capture.
+#[derive(Debug)]
+pub struct CaptureWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Starts a new capture, writing its header to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        Ok(Self { writer })
+    }
+
+    /// Records `frame` as seen now.
+    pub fn record(&mut self, direction: Direction, frame: &[u8]) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.write_frame(timestamp, direction, frame)
+    }
+
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_frame(record.timestamp, record.direction, &record.frame)
+    }
+
+    fn write_frame(
+        &mut self,
+        timestamp: Duration,
+        direction: Direction,
+        frame: &[u8],
+    ) -> io::Result<()> {
+        let micros = u64::try_from(timestamp.as_micros()).unwrap_or(u64::MAX);
+        self.writer.write_all(&micros.to_be_bytes())?;
+        self.writer.write_all(&[direction.to_byte()])?;
+        self.writer.write_all(&(frame.len() as u64).to_be_bytes())?;
+        self.writer.write_all(frame)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the records of a capture, in the order they were written.
+#[derive(Debug)]
+pub struct CaptureReader<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Opens a capture, checking its header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a RESP capture".to_owned()));
+        }
+        Ok(Self {
+            reader,
+            done: false,
+        })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let mut header = [0; 17];
+        // A capture may only end between records
+        let read = self.reader.read(&mut header)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut header[read..])?;
+
+        let micros = u64::from_be_bytes(header[..8].try_into().unwrap());
+        let direction = Direction::from_byte(header[8])?;
+        let length = u64::from_be_bytes(header[9..].try_into().unwrap());
+
+        // Grow the buffer as data arrives rather than trusting the length
+        let mut frame = Vec::new();
+        (&mut self.reader).take(length).read_to_end(&mut frame)?;
+        if (frame.len() as u64) < length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Some(Record {
+            timestamp: Duration::from_micros(micros),
+            direction,
+            frame,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+/// How fast a [`Replayer`] re-emits frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timing {
+    /// As fast as the destination accepts them.
+    #[default]
+    MaxSpeed,
+    /// Keeping the gaps between frames that were recorded.
+    Original,
+}
+
+/// Re-emits the frames of a capture, for testing servers and proxies against
+/// recorded traffic.
+#[derive(Debug)]
+pub struct Replayer<R> {
+    reader: CaptureReader<R>,
+    timing: Timing,
+    direction: Option<Direction>,
+}
+
+impl<R: Read> Replayer<R> {
+    pub fn new(reader: CaptureReader<R>) -> Self {
+        Self {
+            reader,
+            timing: Timing::default(),
+            direction: None,
+        }
+    }
+
+    pub fn timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Only replays frames that travelled in `direction`, usually
+    /// [`Direction::Outbound`] when replaying a client against a server.
+    pub fn only(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Hands every frame to `callback`, stopping at the first error.
+    pub fn replay_with<F>(self, mut callback: F) -> io::Result<usize>
+    where
+        F: FnMut(&Record) -> io::Result<()>,
+    {
+        let mut replayed = 0;
+        let mut start = None;
+        for record in self.reader {
+            let record = record?;
+            if self.direction.is_some_and(|x| x != record.direction) {
+                continue;
+            }
+            if self.timing == Timing::Original {
+                let (started, first) = *start.get_or_insert((Instant::now(), record.timestamp));
+                let due = record.timestamp.saturating_sub(first);
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+            callback(&record)?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    /// Writes every frame to `writer`, such as a socket, flushing after each.
+    pub fn replay_to<W: Write>(self, writer: &mut W) -> io::Result<usize> {
+        self.replay_with(|record| {
+            writer.write_all(&record.frame)?;
+            writer.flush()
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_round_trip() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.record(Direction::Outbound, b"PING\r\n").unwrap();
+        writer
+            .write_record(&Record {
+                timestamp: Duration::from_micros(42),
+                direction: Direction::Inbound,
+                frame: b"+PONG\r\n".to_vec(),
+            })
+            .unwrap();
+        let data = writer.into_inner();
+
+        let records = CaptureReader::new(&data[..])
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Outbound);
+        assert_eq!(records[0].frame, b"PING\r\n");
+        assert!(records[0].timestamp > Duration::ZERO);
+        assert_eq!(records[1].timestamp, Duration::from_micros(42));
+        assert_eq!(records[1].frame, b"+PONG\r\n");
+    }
+
+    fn recorded() -> Vec<u8> {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for (millis, direction, frame) in [
+            (0, Direction::Outbound, &b"PING\r\n"[..]),
+            (10, Direction::Inbound, b"+PONG\r\n"),
+            (30, Direction::Outbound, b"*1\r\n$4\r\nQUIT\r\n"),
+        ] {
+            writer
+                .write_record(&Record {
+                    timestamp: Duration::from_millis(1_000 + millis),
+                    direction,
+                    frame: frame.to_vec(),
+                })
+                .unwrap();
+        }
+        writer.into_inner()
+    }
+
+    #[test]
+    fn replay_to_writer() {
+        let data = recorded();
+        let mut output = Vec::new();
+        let replayed = Replayer::new(CaptureReader::new(&data[..]).unwrap())
+            .only(Direction::Outbound)
+            .replay_to(&mut output)
+            .unwrap();
+        assert_eq!(replayed, 2);
+        assert_eq!(output, b"PING\r\n*1\r\n$4\r\nQUIT\r\n");
+    }
+
+    #[test]
+    fn replay_original_timing() {
+        let data = recorded();
+        let start = Instant::now();
+        let mut frames = Vec::new();
+        Replayer::new(CaptureReader::new(&data[..]).unwrap())
+            .timing(Timing::Original)
+            .replay_with(|record| {
+                frames.push(record.frame.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn capture_invalid() {
+        assert!(matches!(CaptureReader::new(&b"RESPCAP\x02"[..]), Err(_)));
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.record(Direction::Inbound, b"+OK\r\n").unwrap();
+        let data = writer.into_inner();
+        let mut reader = CaptureReader::new(&data[..data.len() - 1]).unwrap();
+        assert!(matches!(reader.next(), Some(Err(_))));
+        assert!(matches!(reader.next(), None));
+    }
+
+    #[test]
+    fn ndjson_export() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for (micros, direction, frame) in [
+            (1_500_000, Direction::Inbound, &b"*1\r\n$4\r\nPING\r\n"[..]),
+            (1_500_042, Direction::Outbound, &b"-ERR \"x\"\r\n"[..]),
+            (1_500_043, Direction::Outbound, &b"$9\r\nnope\r\n"[..]),
+        ] {
+            writer
+                .write_record(&Record {
+                    timestamp: Duration::from_micros(micros),
+                    direction,
+                    frame: frame.to_vec(),
+                })
+                .unwrap();
+        }
+        let data = writer.into_inner();
+
+        let mut output = Vec::new();
+        let count = export_ndjson(CaptureReader::new(&data[..]).unwrap(), &mut output).unwrap();
+        assert_eq!(count, 3);
+        let lines: Vec<_> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(
+            lines[..2],
+            [
+                r#"{"timestamp_us":1500000,"direction":"inbound","size":14,"value":["PING"]}"#,
+                r#"{"timestamp_us":1500042,"direction":"outbound","size":10,"value":{"error":"ERR \"x\""}}"#,
+            ]
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"timestamp_us":1500043,"direction":"outbound","size":10,"error":"expected 9 bytes, found end of input"}"#
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Frame parsers usable as [winnow] combinators, for grammars embedding RESP
+//! payloads, such as log formats wrapping recorded frames.
+//!
+//! They work on any `&str` or `&[u8]` stream, including [`Partial`] ones
+//! for which incomplete frames ask for more input.
+//!
+//! ```
+//! use resp_parser_rs::{combinators, RESP};
+//! use winnow::combinator::{delimited, separated};
+//! use winnow::{ModalResult, Parser};
+//!
+//! fn line(input: &mut &str) -> ModalResult<Vec<RESP>> {
+//!     delimited("[", separated(1.., combinators::frame, ","), "]").parse_next(input)
+//! }
+//!
+//! let frames = line.parse("[+OK\r\n,:1\r\n]").unwrap();
+//! assert_eq!(frames, vec![RESP::SimpleString("OK".into()), RESP::Integer(1)]);
+//! ```
+//!
+//! [`Partial`]: winnow::Partial
+
+use winnow::error::{FromExternalError, Needed, ParserError};
+use winnow::stream::{Stream, StreamIsPartial};
+
+use crate::{framing, ParseConfig, ParseError, RESP};
+
+/// Length of the first frame of `data`, failing as `E` when there is none.
+fn frame_len<I, E>(input: &I, data: &[u8]) -> Result<usize, E>
+where
+    I: Stream + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    match framing::frame_len(data) {
+        Ok(Some(length)) => Ok(length),
+        Ok(None) if input.is_partial() => Err(E::incomplete(input, Needed::Unknown)),
+        Ok(None) => Err(E::from_external_error(
+            input,
+            ParseError::end_of_input("rest of the frame"),
+        )),
+        Err(err) => Err(E::from_external_error(input, err)),
+    }
+}
+
+/// Parses a frame with the default configuration.
+pub fn frame<'a, I, E>(input: &mut I) -> Result<RESP, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    frame_with(&ParseConfig::default())(input)
+}
+
+/// Parses a frame with `config`.
+pub fn frame_with<'a, I, E>(config: &ParseConfig) -> impl FnMut(&mut I) -> Result<RESP, E> + '_
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    move |input: &mut I| {
+        let data = input.peek_slice(input.eof_offset());
+        let length = frame_len(input, data.as_bytes())?;
+        let value = RESP::try_parse_with(&data[..length], config)
+            .map_err(|err| E::from_external_error(input, err))?;
+        input.next_slice(length);
+        Ok(value)
+    }
+}
+
+/// Takes the raw bytes of a frame without decoding it.
+pub fn raw_frame<'a, I, E>(input: &mut I) -> Result<&'a [u8], E>
+where
+    I: Stream<Slice = &'a [u8]> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    let data = input.peek_slice(input.eof_offset());
+    let length = frame_len(input, data)?;
+    Ok(input.next_slice(length))
+}
+
+/// Parses a frame and maps it with `f`, backtracking when it gives `None`.
+fn typed<'a, I, E, T>(input: &mut I, f: fn(RESP) -> Option<T>) -> Result<T, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    let start = input.checkpoint();
+    let value = frame(input)?;
+    f(value).ok_or_else(|| {
+        input.reset(&start);
+        E::from_input(input)
+    })
+}
+
+pub fn simple_string<'a, I, E>(input: &mut I) -> Result<String, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    typed(input, |value| match value {
+        RESP::SimpleString(x) => Some(x.to_string()),
+        _ => None,
+    })
+}
+
+/// Parses a bulk string, its null form not included.
+pub fn bulk_string<'a, I, E>(input: &mut I) -> Result<String, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    typed(input, |value| match value {
+        RESP::BulkString(x) => Some(x),
+        _ => None,
+    })
+}
+
+pub fn integer<'a, I, E>(input: &mut I) -> Result<i64, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    typed(input, |value| match value {
+        RESP::Integer(x) => Some(x),
+        _ => None,
+    })
+}
+
+/// Parses an array, its null form not included.
+pub fn array<'a, I, E>(input: &mut I) -> Result<Vec<RESP>, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial,
+    E: ParserError<I> + FromExternalError<I, ParseError>,
+{
+    typed(input, |value| match value {
+        RESP::Array(x) => Some(x),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winnow::combinator::{alt, repeat};
+    use winnow::error::{ContextError, ErrMode};
+    use winnow::{ModalResult, Parser, Partial};
+
+    #[test]
+    fn combinators_repeat() {
+        let mut input = "+OK\r\n:1\r\n$3\r\nabc\r\n";
+        let frames: Vec<RESP> = repeat(0.., frame::<_, ContextError>)
+            .parse_next(&mut input)
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(input, "");
+    }
+
+    #[test]
+    fn combinators_typed() {
+        fn either(input: &mut &str) -> ModalResult<String> {
+            alt((bulk_string, integer.map(|x| x.to_string()))).parse_next(input)
+        }
+        assert_eq!(either.parse(":12\r\n"), Ok("12".to_owned()));
+        assert_eq!(either.parse("$2\r\nab\r\n"), Ok("ab".to_owned()));
+        assert!(either.parse("+ab\r\n").is_err());
+        assert_eq!(
+            array::<_, ContextError>.parse("*1\r\n+a\r\n"),
+            Ok(vec![RESP::SimpleString("a".into())])
+        );
+    }
+
+    #[test]
+    fn combinators_partial() {
+        let mut input = Partial::new("*2\r\n:1\r\n");
+        assert!(matches!(
+            frame::<_, ErrMode<ContextError>>(&mut input),
+            Err(ErrMode::Incomplete(_))
+        ));
+        let mut input = Partial::new(&b"+OK\r\n:1"[..]);
+        assert_eq!(
+            raw_frame::<_, ErrMode<ContextError>>(&mut input),
+            Ok(&b"+OK\r\n"[..])
+        );
+        assert!(matches!(
+            raw_frame::<_, ErrMode<ContextError>>(&mut input),
+            Err(ErrMode::Incomplete(_))
+        ));
+    }
+
+    #[test]
+    fn combinators_invalid() {
+        let mut input = "$-3\r\n";
+        assert!(frame::<_, ContextError>(&mut input).is_err());
+        assert_eq!(input, "$-3\r\n");
+    }
+}
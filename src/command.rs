@@ -0,0 +1,100 @@
+use bytes::Bytes;
+
+use crate::{ConversionError, FromResp, RESP};
+
+/// A request as a server receives it, either an array of bulk strings or an
+/// inline command.
+///
+/// ```
+/// use resp_parser_rs::{Command, FromResp, RESP};
+///
+/// let request = RESP::parse("*3\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\n1\r\n").unwrap();
+/// let command = Command::from_resp(request).unwrap();
+/// assert!(command.is("SET"));
+/// assert_eq!(command.args, ["a", "1"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub name: Bytes,
+    pub args: Vec<Bytes>,
+}
+
+impl Command {
+    /// Whether this is the command `name`, ignoring ASCII case as servers do.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name.as_bytes())
+    }
+
+    /// Whether the first argument is `name`, ignoring ASCII case, for
+    /// commands like `CLIENT SETNAME` or `CONFIG GET`.
+    pub fn subcommand_is(&self, name: &str) -> bool {
+        self.args
+            .first()
+            .is_some_and(|x| x.eq_ignore_ascii_case(name.as_bytes()))
+    }
+}
+
+impl FromResp for Command {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        let mut parts = match value {
+            RESP::Array(elements)
+                if !elements.is_empty()
+                    && elements.iter().all(|x| matches!(x, RESP::BulkString(_))) =>
+            {
+                elements
+                    .into_iter()
+                    .filter_map(|x| match x {
+                        RESP::BulkString(x) => Some(Bytes::from(x)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            }
+            RESP::Inline(arguments) if !arguments.is_empty() => {
+                arguments.into_iter().map(Bytes::from).collect()
+            }
+            x => {
+                return Err(ConversionError::unexpected_type(
+                    "array of bulk strings or inline command",
+                    &x,
+                ))
+            }
+        };
+        let name = parts.remove(0);
+        Ok(Self { name, args: parts })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_from_array() {
+        let request = RESP::parse("*3\r\n$6\r\nCLIENT\r\n$7\r\nsetname\r\n$1\r\nx\r\n").unwrap();
+        let command = Command::from_resp(request).unwrap();
+        assert!(command.is("client"));
+        assert!(!command.is("CLIENTS"));
+        assert!(command.subcommand_is("SETNAME"));
+        assert_eq!(command.name, "CLIENT");
+        assert_eq!(command.args, ["setname", "x"]);
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn command_from_inline() {
+        let command = Command::from_resp(RESP::parse("ping hello\r\n").unwrap()).unwrap();
+        assert!(command.is("PING"));
+        assert_eq!(command.args, ["hello"]);
+    }
+
+    #[test]
+    fn command_invalid() {
+        for data in ["*0\r\n", "*1\r\n:1\r\n", "+PING\r\n", "*-1\r\n"] {
+            assert!(matches!(
+                Command::from_resp(RESP::parse(data).unwrap()),
+                Err(ConversionError::UnexpectedType { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/// Default cap on the digits of a big number, generous enough for any
+/// legitimate value while stopping multi-megabyte digit floods early.
+pub const DEFAULT_MAX_BIG_NUMBER_DIGITS: usize = 4096;
+
+/// Default cap on how deeply aggregates can be nested.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Version of the protocol spoken on a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// The original protocol, without maps, doubles or the other RESP3 types.
+    Resp2,
+    #[default]
+    Resp3,
+}
+
+/// What to do when a map contains the same key more than once.
+///
+/// Keys are compared as [`RespKey`](crate::RespKey)s, so a simple and a bulk
+/// string with the same text are the same key, as are two NaN doubles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Keep every pair, in the order they were received.
+    #[default]
+    KeepAll,
+    /// Keep the first value seen for a key.
+    FirstWins,
+    /// Keep the last value seen for a key, in the position of the first one.
+    LastWins,
+    /// Reject the whole map.
+    Error,
+}
+
+/// Which end of a connection the parser is used at, restricting what the
+/// peer may send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    /// Accept any frame.
+    #[default]
+    Any,
+    /// Parse requests, so only commands are accepted: non-empty arrays of
+    /// bulk strings, or inline commands.
+    Server,
+    /// Parse replies, so inline commands are never read as servers don't
+    /// send them.
+    Client,
+}
+
+/// Knobs controlling how permissive the parser is.
+#[derive(Debug, Clone)]
+pub struct ParseConfig {
+    /// Maximum amount of digits (sign excluded) accepted in a big number,
+    /// use `usize::MAX` to disable the limit.
+    pub max_big_number_digits: usize,
+    /// Maximum nesting of aggregates. Parsing itself doesn't recurse, but
+    /// dropping a decoded value does, so keep this bounded for untrusted input.
+    pub max_depth: usize,
+    /// Policy applied to repeated keys in maps.
+    pub duplicate_keys: DuplicateKeys,
+    /// Decode verbatim strings as plain bulk strings, dropping the encoding.
+    /// The frame is still validated as a verbatim string.
+    pub verbatim_as_bulk: bool,
+    /// Read lines that don't start with a type byte as inline commands, like
+    /// servers do for telnet sessions. Clients can turn this off so such
+    /// bytes are errors instead.
+    #[cfg(feature = "inline")]
+    pub inline_commands: bool,
+    /// End of the connection the parser is used at.
+    pub side: Side,
+    /// Decode finite doubles as exact [`rust_decimal::Decimal`] values instead
+    /// of `f64`, infinities and NaN still decode as `f64`.
+    #[cfg(feature = "rust_decimal")]
+    pub decimal_doubles: bool,
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        Self {
+            max_big_number_digits: DEFAULT_MAX_BIG_NUMBER_DIGITS,
+            max_depth: DEFAULT_MAX_DEPTH,
+            duplicate_keys: DuplicateKeys::default(),
+            verbatim_as_bulk: false,
+            #[cfg(feature = "inline")]
+            inline_commands: true,
+            side: Side::default(),
+            #[cfg(feature = "rust_decimal")]
+            decimal_doubles: false,
+        }
+    }
+}
@@ -0,0 +1,884 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::Write;
+use std::str::FromStr;
+
+#[cfg(feature = "resp3")]
+use crate::encode::write_double;
+use crate::framing::write_len_header;
+use crate::{ConversionError, EncodeError, RespKey, RESP};
+
+/// Types that can be built from a decoded value, taking ownership of it.
+///
+/// Conversions are strict: integers only come from integers and big
+/// numbers, not from strings holding digits, unless [`Coercion`] rules are
+/// passed to
+/// [`from_resp_with`](Self::from_resp_with). Attributes are ignored.
+///
+/// Every null, whether `Null`, a null bulk string or a null array, becomes
+/// `None` when converting to an `Option`, and is an error for any other
+/// type. `Vec<Option<T>>` reads replies like `MGET` where some elements
+/// are missing.
+pub trait FromResp: Sized {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError>;
+
+    /// Like [`from_resp`](Self::from_resp), applying the `coercion` rules.
+    /// Only numbers and the types holding them are affected.
+    fn from_resp_with(value: RESP, _coercion: Coercion) -> Result<Self, ConversionError> {
+        Self::from_resp(value)
+    }
+}
+
+/// Rules letting [`FromResp`] convert numbers from values of another type,
+/// as Redis often replies with numbers in bulk strings. All are disabled
+/// by default.
+///
+/// ```
+/// use resp_parser_rs::{Coercion, RESP};
+///
+/// let score = RESP::BulkString("1.5".into());
+/// assert_eq!(score.try_into_value_with::<f64>(Coercion::LENIENT), Ok(1.5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coercion {
+    /// Parse numbers from simple and bulk strings.
+    pub strings_to_numbers: bool,
+    /// Convert integers to doubles.
+    pub integers_to_doubles: bool,
+    /// Convert booleans to 0 or 1.
+    pub booleans_to_integers: bool,
+}
+
+impl Coercion {
+    /// No coercion, the default.
+    pub const STRICT: Self = Self {
+        strings_to_numbers: false,
+        integers_to_doubles: false,
+        booleans_to_integers: false,
+    };
+
+    /// Every coercion.
+    pub const LENIENT: Self = Self {
+        strings_to_numbers: true,
+        integers_to_doubles: true,
+        booleans_to_integers: true,
+    };
+}
+
+/// Number written in `text`, coerced from `value`.
+fn parse_number<T: FromStr>(text: &str, value: &RESP) -> Result<T, ConversionError> {
+    let expected = std::any::type_name::<T>();
+    text.parse().map_err(|_| {
+        let digits = text.strip_prefix('-').unwrap_or(text);
+        match !digits.is_empty() && digits.bytes().all(|x| x.is_ascii_digit()) {
+            true => ConversionError::out_of_range(expected, value),
+            false => ConversionError::unexpected_type(expected, value),
+        }
+    })
+}
+
+/// The value without its attributes.
+fn strip_attributes(value: RESP) -> RESP {
+    match value {
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { value, .. } => strip_attributes(*value),
+        x => x,
+    }
+}
+
+impl FromResp for RESP {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        Ok(value)
+    }
+}
+
+impl FromResp for String {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        match strip_attributes(value) {
+            RESP::SimpleString(x) => Ok(x.to_string()),
+            RESP::BulkString(x) => Ok(x),
+            #[cfg(feature = "resp3")]
+            RESP::VerbatimString { data, .. } => Ok(data),
+            x => Err(ConversionError::unexpected_type("string", &x)),
+        }
+    }
+}
+
+macro_rules! from_resp_number {
+    ($($type:ty),*) => {
+        $(
+            impl FromResp for $type {
+                fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+                    Self::from_resp_with(value, Coercion::STRICT)
+                }
+
+                fn from_resp_with(value: RESP, coercion: Coercion) -> Result<Self, ConversionError> {
+                    strip_attributes(value).to_number_with(coercion)
+                }
+            }
+        )*
+    };
+}
+
+from_resp_number!(i64, u64, i128, u128);
+
+impl FromResp for f64 {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        Self::from_resp_with(value, Coercion::STRICT)
+    }
+
+    fn from_resp_with(value: RESP, coercion: Coercion) -> Result<Self, ConversionError> {
+        match strip_attributes(value) {
+            #[cfg(feature = "resp3")]
+            RESP::Double(x) => Ok(x),
+            RESP::Integer(x) if coercion.integers_to_doubles => Ok(x as f64),
+            ref x @ RESP::SimpleString(ref text) if coercion.strings_to_numbers => {
+                parse_number(text.as_str(), x)
+            }
+            ref x @ RESP::BulkString(ref text) if coercion.strings_to_numbers => {
+                parse_number(text, x)
+            }
+            x => Err(ConversionError::unexpected_type("double", &x)),
+        }
+    }
+}
+
+#[cfg(feature = "resp3")]
+impl FromResp for bool {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        match strip_attributes(value) {
+            RESP::Boolean(x) => Ok(x),
+            x => Err(ConversionError::unexpected_type("boolean", &x)),
+        }
+    }
+}
+
+impl FromResp for RespKey {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        Self::try_from(&value)
+    }
+}
+
+impl<T: FromResp> FromResp for Option<T> {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_resp(value).map(Some)
+        }
+    }
+
+    fn from_resp_with(value: RESP, coercion: Coercion) -> Result<Self, ConversionError> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_resp_with(value, coercion).map(Some)
+        }
+    }
+}
+
+impl<T: FromResp> FromResp for Vec<T> {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        Self::from_resp_with(value, Coercion::STRICT)
+    }
+
+    fn from_resp_with(value: RESP, coercion: Coercion) -> Result<Self, ConversionError> {
+        let convert = |x| T::from_resp_with(x, coercion);
+        match strip_attributes(value) {
+            RESP::Array(x) => x.into_iter().map(convert).collect(),
+            #[cfg(feature = "resp3")]
+            RESP::Set(x) | RESP::Push(x) => x.into_iter().map(convert).collect(),
+            x => Err(ConversionError::unexpected_type("array", &x)),
+        }
+    }
+}
+
+/// Types that can be turned into a value to encode, the counterpart of
+/// [`FromResp`].
+///
+/// Strings become bulk strings, `None` a null bulk string, which RESP2
+/// clients understand too, and sequences arrays.
+pub trait ToResp {
+    fn to_resp(&self) -> RESP;
+
+    /// Encodes the value to `writer`. The default goes through
+    /// [`to_resp`](Self::to_resp), implementations write directly instead
+    /// when they can.
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        self.to_resp().write_to(writer)
+    }
+}
+
+/// Converts `value` and encodes it to `writer` in one pass, without building
+/// a [`RESP`] value for types that write themselves.
+///
+/// ```
+/// let mut output = Vec::new();
+/// resp_parser_rs::to_writer(&mut output, &vec!["GET", "key"]).unwrap();
+/// assert_eq!(output, b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n");
+/// ```
+pub fn to_writer<W: Write, T: ToResp + ?Sized>(
+    mut writer: W,
+    value: &T,
+) -> Result<(), EncodeError> {
+    value.write_resp(&mut writer)
+}
+
+impl<T: ToResp + ?Sized> ToResp for &T {
+    fn to_resp(&self) -> RESP {
+        (**self).to_resp()
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        (**self).write_resp(writer)
+    }
+}
+
+impl ToResp for str {
+    fn to_resp(&self) -> RESP {
+        RESP::BulkString(self.to_owned())
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        write_len_header(writer, b'$', Some(self.len()))?;
+        writer.write_all(self.as_bytes())?;
+        Ok(writer.write_all(b"\r\n")?)
+    }
+}
+
+impl ToResp for String {
+    fn to_resp(&self) -> RESP {
+        self.as_str().to_resp()
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        self.as_str().write_resp(writer)
+    }
+}
+
+macro_rules! to_resp_integer {
+    ($($type:ty),*) => {
+        $(
+            impl ToResp for $type {
+                /// Values beyond the range of integers become big numbers, or
+                /// bulk strings in RESP2 like Redis replies with.
+                fn to_resp(&self) -> RESP {
+                    match i64::try_from(*self) {
+                        Ok(x) => RESP::Integer(x),
+                        #[cfg(feature = "resp3")]
+                        Err(_) => RESP::BigNumber(self.to_string()),
+                        #[cfg(not(feature = "resp3"))]
+                        Err(_) => RESP::BulkString(self.to_string()),
+                    }
+                }
+
+                fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+                    match i64::try_from(*self) {
+                        Ok(_) => Ok(write!(writer, ":{self}\r\n")?),
+                        #[cfg(feature = "resp3")]
+                        Err(_) => Ok(write!(writer, "({self}\r\n")?),
+                        #[cfg(not(feature = "resp3"))]
+                        Err(_) => self.to_string().write_resp(writer),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+to_resp_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(feature = "resp3")]
+impl ToResp for f64 {
+    fn to_resp(&self) -> RESP {
+        RESP::Double(*self)
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_all(b",")?;
+        write_double(writer, *self)?;
+        Ok(writer.write_all(b"\r\n")?)
+    }
+}
+
+#[cfg(feature = "resp3")]
+impl ToResp for bool {
+    fn to_resp(&self) -> RESP {
+        RESP::Boolean(*self)
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        Ok(writer.write_all(if *self { b"#t\r\n" } else { b"#f\r\n" })?)
+    }
+}
+
+impl ToResp for RespKey {
+    fn to_resp(&self) -> RESP {
+        self.clone().into()
+    }
+}
+
+impl<T: ToResp> ToResp for Option<T> {
+    fn to_resp(&self) -> RESP {
+        match self {
+            Some(x) => x.to_resp(),
+            None => RESP::NullBulkString,
+        }
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        match self {
+            Some(x) => x.write_resp(writer),
+            None => Ok(write_len_header(writer, b'$', None)?),
+        }
+    }
+}
+
+impl<T: ToResp> ToResp for [T] {
+    fn to_resp(&self) -> RESP {
+        RESP::Array(self.iter().map(T::to_resp).collect())
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        write_len_header(writer, b'*', Some(self.len()))?;
+        self.iter().try_for_each(|x| x.write_resp(writer))
+    }
+}
+
+impl<T: ToResp> ToResp for Vec<T> {
+    fn to_resp(&self) -> RESP {
+        self.as_slice().to_resp()
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        self.as_slice().write_resp(writer)
+    }
+}
+
+#[cfg(feature = "resp3")]
+impl<K: ToResp, V: ToResp, S> ToResp for HashMap<K, V, S> {
+    fn to_resp(&self) -> RESP {
+        RESP::Map(
+            self.iter()
+                .map(|(key, value)| (key.to_resp(), value.to_resp()))
+                .collect(),
+        )
+    }
+
+    fn write_resp<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        write_len_header(writer, b'%', Some(self.len()))?;
+        for (key, value) in self {
+            key.write_resp(writer)?;
+            value.write_resp(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl RESP {
+    fn to_number<T>(&self) -> Result<T, ConversionError>
+    where
+        T: TryFrom<i64> + FromStr,
+    {
+        let expected = std::any::type_name::<T>();
+        let value = self.inner();
+        match value {
+            Self::Integer(x) => {
+                T::try_from(*x).map_err(|_| ConversionError::out_of_range(expected, value))
+            }
+            // Big numbers are already validated, so failing here means overflow
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(x) => x
+                .parse::<T>()
+                .map_err(|_| ConversionError::out_of_range(expected, value)),
+            _ => Err(ConversionError::unexpected_type(expected, value)),
+        }
+    }
+
+    fn to_number_with<T>(&self, coercion: Coercion) -> Result<T, ConversionError>
+    where
+        T: TryFrom<i64> + FromStr,
+    {
+        let value = self.inner();
+        match value {
+            Self::SimpleString(x) if coercion.strings_to_numbers => parse_number(x.as_str(), value),
+            Self::BulkString(x) if coercion.strings_to_numbers => parse_number(x, value),
+            #[cfg(feature = "resp3")]
+            Self::Boolean(x) if coercion.booleans_to_integers => T::try_from(*x as i64)
+                .map_err(|_| ConversionError::out_of_range(std::any::type_name::<T>(), value)),
+            _ => value.to_number(),
+        }
+    }
+
+    pub fn to_u64(&self) -> Result<u64, ConversionError> {
+        self.to_number()
+    }
+
+    pub fn to_u128(&self) -> Result<u128, ConversionError> {
+        self.to_number()
+    }
+
+    pub fn to_i128(&self) -> Result<i128, ConversionError> {
+        self.to_number()
+    }
+
+    /// Converts the value with [`FromResp`].
+    pub fn try_into_value<T: FromResp>(self) -> Result<T, ConversionError> {
+        T::from_resp(self)
+    }
+
+    /// Converts the value with [`FromResp`], applying the `coercion` rules.
+    pub fn try_into_value_with<T: FromResp>(
+        self,
+        coercion: Coercion,
+    ) -> Result<T, ConversionError> {
+        T::from_resp_with(self, coercion)
+    }
+
+    /// Converts the elements of an array, as replies to batch reads like
+    /// `MGET` or `HMGET` have, null elements becoming `None`.
+    ///
+    /// ```
+    /// use resp_parser_rs::RESP;
+    ///
+    /// let reply = RESP::parse("*3\r\n$1\r\n1\r\n$-1\r\n$1\r\n3\r\n").unwrap();
+    /// assert_eq!(
+    ///     reply.try_into_options::<String>(),
+    ///     Ok(vec![Some("1".to_owned()), None, Some("3".to_owned())])
+    /// );
+    /// ```
+    pub fn try_into_options<T: FromResp>(self) -> Result<Vec<Option<T>>, ConversionError> {
+        Vec::from_resp(self)
+    }
+
+    /// Converts a map, or a flat array of alternating keys and values as
+    /// RESP2 replies like HGETALL or CONFIG GET have, into a typed
+    /// [`HashMap`]. Later duplicates of a key replace earlier ones.
+    pub fn try_into_map<K, V>(self) -> Result<HashMap<K, V>, ConversionError>
+    where
+        K: FromResp + Eq + Hash,
+        V: FromResp,
+    {
+        self.try_into_map_with(Coercion::STRICT)
+    }
+
+    /// Like [`try_into_map`](Self::try_into_map), converting keys and values
+    /// with the `coercion` rules, for RESP2 replies holding numbers in bulk
+    /// strings.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use resp_parser_rs::{Coercion, RESP};
+    ///
+    /// let reply = RESP::parse("*2\r\n$7\r\nmaxconn\r\n$3\r\n100\r\n").unwrap();
+    /// let config: HashMap<String, i64> = reply.try_into_map_with(Coercion::LENIENT).unwrap();
+    /// assert_eq!(config["maxconn"], 100);
+    /// ```
+    pub fn try_into_map_with<K, V>(
+        self,
+        coercion: Coercion,
+    ) -> Result<HashMap<K, V>, ConversionError>
+    where
+        K: FromResp + Eq + Hash,
+        V: FromResp,
+    {
+        let entry = |key, value| {
+            Ok((
+                K::from_resp_with(key, coercion)?,
+                V::from_resp_with(value, coercion)?,
+            ))
+        };
+        match strip_attributes(self) {
+            #[cfg(feature = "resp3")]
+            RESP::Map(entries) => entries
+                .into_iter()
+                .map(|(key, value)| entry(key, value))
+                .collect(),
+            RESP::Array(elements) if elements.len().is_multiple_of(2) => {
+                let mut elements = elements.into_iter();
+                let mut map = HashMap::with_capacity(elements.len() / 2);
+                while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+                    let (key, value) = entry(key, value)?;
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+            x => Err(ConversionError::unexpected_type(
+                "map or array of key value pairs",
+                &x,
+            )),
+        }
+    }
+
+    /// Turns a flat array of alternating keys and values, the RESP2 shape of
+    /// replies like HGETALL or CONFIG GET, into a map so it can be handled
+    /// like its RESP3 counterpart. Maps are returned as they are, and
+    /// attributes are kept.
+    #[cfg(feature = "resp3")]
+    pub fn pairs_to_map(self) -> Result<RESP, ConversionError> {
+        match self {
+            Self::WithAttributes { attributes, value } => Ok(Self::WithAttributes {
+                attributes,
+                value: Box::new(value.pairs_to_map()?),
+            }),
+            Self::Map(x) => Ok(Self::Map(x)),
+            Self::Array(elements) if elements.len().is_multiple_of(2) => {
+                let mut elements = elements.into_iter();
+                let mut entries = Vec::with_capacity(elements.len() / 2);
+                while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+                    entries.push((key, value));
+                }
+                Ok(Self::Map(entries))
+            }
+            x => Err(ConversionError::unexpected_type(
+                "array of key value pairs",
+                &x,
+            )),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    /// Writing directly must give the same bytes as encoding the value.
+    fn assert_streams<T: ToResp + ?Sized>(value: &T, expected: &[u8]) {
+        let mut output = Vec::new();
+        to_writer(&mut output, value).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(value.to_resp().encode().unwrap(), expected);
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(Debug, PartialEq, Eq, crate::ReplyEnum)]
+    enum Encoding {
+        #[reply("listpack", "ziplist")]
+        Listpack,
+        Quicklist,
+        #[reply("int")]
+        Integer,
+        Unknown(String),
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_reply_enum() {
+        let decode = |x: &str| Encoding::from_resp(RESP::parse(x).unwrap());
+        assert_eq!(decode("+ZipList\r\n"), Ok(Encoding::Listpack));
+        assert_eq!(decode("$9\r\nquicklist\r\n"), Ok(Encoding::Quicklist));
+        assert_eq!(decode("+INT\r\n"), Ok(Encoding::Integer));
+        assert_eq!(
+            decode("+embstr\r\n"),
+            Ok(Encoding::Unknown("embstr".to_owned()))
+        );
+        assert!(decode(":1\r\n").is_err());
+        assert_eq!(Encoding::Listpack.as_str(), "listpack");
+        assert_eq!(Encoding::Quicklist.as_str(), "quicklist");
+        assert_eq!(Encoding::Unknown("hashtable".into()).as_str(), "hashtable");
+    }
+
+    #[test]
+    fn to_writer_streams() {
+        assert_streams("a\r\nb", b"$4\r\na\r\nb\r\n");
+        assert_streams(&-5i32, b":-5\r\n");
+        assert_streams(&u64::MAX, b"(18446744073709551615\r\n");
+        assert_streams(&1.5, b",1.5\r\n");
+        assert_streams(&1e21, b",1e+21\r\n");
+        assert_streams(&f64::NEG_INFINITY, b",-inf\r\n");
+        assert_streams(&[Some(true), None][..], b"*2\r\n#t\r\n$-1\r\n");
+        assert_streams(
+            &vec![vec!["a".to_owned()], vec![]],
+            b"*2\r\n*1\r\n$1\r\na\r\n*0\r\n",
+        );
+        assert_streams(&HashMap::from([("db", 0)]), b"%1\r\n$2\r\ndb\r\n:0\r\n");
+    }
+
+    #[test]
+    fn to_resp_key() {
+        assert_streams(&RespKey::Integer(3), b":3\r\n");
+    }
+
+    #[test]
+    fn integer_to_unsigned() {
+        assert_eq!(RESP::Integer(123).to_u64(), Ok(123));
+        assert_eq!(RESP::Integer(123).to_u128(), Ok(123));
+        assert!(matches!(
+            RESP::Integer(-1).to_u64(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            RESP::Integer(-1).to_u128(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn attributed_integer_to_number() {
+        let parsed = RESP::parse("|1\r\n+ttl\r\n:3\r\n:5\r\n").unwrap();
+        assert_eq!(parsed.to_u64(), Ok(5));
+        assert_eq!(parsed.to_u128(), Ok(5));
+        assert_eq!(parsed.to_i128(), Ok(5));
+        assert_eq!(u64::from_resp(parsed), Ok(5));
+
+        let parsed = RESP::parse("|1\r\n+ttl\r\n:3\r\n+OK\r\n").unwrap();
+        assert!(matches!(
+            parsed.to_u64(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn integer_to_i128() {
+        assert_eq!(RESP::Integer(-123).to_i128(), Ok(-123));
+        assert_eq!(RESP::Integer(i64::MIN).to_i128(), Ok(i64::MIN as i128));
+    }
+
+    #[test]
+    fn big_number_to_unsigned() {
+        let parsed = RESP::parse("(18446744073709551615\r\n").unwrap();
+        assert_eq!(parsed.to_u64(), Ok(u64::MAX));
+        assert_eq!(parsed.to_u128(), Ok(u64::MAX as u128));
+
+        let parsed = RESP::parse("(18446744073709551616\r\n").unwrap();
+        assert!(matches!(
+            parsed.to_u64(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+        assert_eq!(parsed.to_u128(), Ok(u64::MAX as u128 + 1));
+
+        let parsed = RESP::parse("(-1\r\n").unwrap();
+        assert!(matches!(
+            parsed.to_u64(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            parsed.to_u128(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn big_number_to_i128() {
+        let parsed = RESP::parse("(-170141183460469231731687303715884105728\r\n").unwrap();
+        assert_eq!(parsed.to_i128(), Ok(i128::MIN));
+
+        let parsed = RESP::parse("(170141183460469231731687303715884105728\r\n").unwrap();
+        assert!(matches!(
+            parsed.to_i128(),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn typed_map() {
+        let reply = RESP::parse("%2\r\n+proto\r\n:3\r\n+id\r\n:7\r\n").unwrap();
+        let map = reply.try_into_map::<String, i64>().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["proto"], 3);
+
+        let reply =
+            RESP::parse("*4\r\n$7\r\nmaxconn\r\n$2\r\n10\r\n$4\r\nsave\r\n$0\r\n\r\n").unwrap();
+        let map = reply.try_into_map::<String, String>().unwrap();
+        assert_eq!(map["maxconn"], "10");
+        assert_eq!(map["save"], "");
+
+        let reply = RESP::parse("%1\r\n:1\r\n*2\r\n#t\r\n#f\r\n").unwrap();
+        let map = reply.try_into_map::<u64, Vec<bool>>().unwrap();
+        assert_eq!(map[&1], vec![true, false]);
+    }
+
+    #[test]
+    fn typed_map_invalid() {
+        let odd = RESP::parse("*3\r\n+a\r\n+b\r\n+c\r\n").unwrap();
+        assert!(matches!(
+            odd.try_into_map::<String, String>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        let reply = RESP::parse("%1\r\n+a\r\n+b\r\n").unwrap();
+        assert!(matches!(
+            reply.try_into_map::<String, i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn number_unexpected_type() {
+        let parsed = RESP::SimpleString("123".into());
+        assert!(matches!(
+            parsed.to_u64(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            parsed.to_u128(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            parsed.to_i128(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn from_resp_nulls() {
+        let reply =
+            RESP::parse("*4\r\n$1\r\na\r\n$-1\r\n_\r\n|1\r\n+ttl\r\n:3\r\n*-1\r\n").unwrap();
+        assert_eq!(
+            Vec::<Option<String>>::from_resp(reply),
+            Ok(vec![Some("a".to_owned()), None, None, None])
+        );
+        assert_eq!(Option::<i64>::from_resp(RESP::Integer(1)), Ok(Some(1)));
+        assert_eq!(Option::<Vec<i64>>::from_resp(RESP::NullArray), Ok(None));
+        assert!(matches!(
+            String::from_resp(RESP::NullBulkString),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            Option::<i64>::from_resp(RESP::BulkString("1".to_owned())),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn pairs_to_map() {
+        let reply = RESP::parse("*4\r\n$4\r\nname\r\n$3\r\nbob\r\n$3\r\nage\r\n:7\r\n").unwrap();
+        assert_eq!(
+            reply.pairs_to_map(),
+            Ok(RESP::map([
+                (RESP::bulk_string("name"), RESP::bulk_string("bob")),
+                (RESP::bulk_string("age"), RESP::integer(7)),
+            ]))
+        );
+        let map = RESP::parse("%1\r\n+a\r\n:1\r\n").unwrap();
+        assert_eq!(
+            RESP::parse("%1\r\n+a\r\n:1\r\n").unwrap().pairs_to_map(),
+            Ok(map)
+        );
+        assert_eq!(
+            RESP::Array(Vec::new()).pairs_to_map(),
+            Ok(RESP::Map(Vec::new()))
+        );
+        assert!(matches!(
+            RESP::parse("*1\r\n+a\r\n").unwrap().pairs_to_map(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            RESP::NullArray.pairs_to_map(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn try_into_options() {
+        let reply = RESP::parse("*3\r\n:1\r\n_\r\n(2\r\n").unwrap();
+        assert_eq!(
+            reply.try_into_options::<u64>(),
+            Ok(vec![Some(1), None, Some(2)])
+        );
+        let reply = RESP::parse("*2\r\n:1\r\n+a\r\n").unwrap();
+        assert!(matches!(
+            reply.try_into_options::<i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            RESP::NullArray.try_into_options::<i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn coercion_strings() {
+        let number = RESP::BulkString("42".into());
+        assert!(matches!(
+            RESP::BulkString("42".into()).try_into_value::<i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert_eq!(number.try_into_value_with::<i64>(Coercion::LENIENT), Ok(42));
+        let negative = RESP::SimpleString("-7".into());
+        assert_eq!(
+            negative.try_into_value_with::<i128>(Coercion::LENIENT),
+            Ok(-7)
+        );
+        assert!(matches!(
+            RESP::BulkString("-1".into()).try_into_value_with::<u64>(Coercion::LENIENT),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            RESP::BulkString("4x".into()).try_into_value_with::<i64>(Coercion::LENIENT),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        let reply = RESP::parse("*2\r\n$1\r\n1\r\n$-1\r\n").unwrap();
+        assert_eq!(
+            reply.try_into_value_with::<Vec<Option<u64>>>(Coercion::LENIENT),
+            Ok(vec![Some(1), None])
+        );
+    }
+
+    #[test]
+    fn coercion_doubles() {
+        let coercion = Coercion {
+            integers_to_doubles: true,
+            ..Coercion::STRICT
+        };
+        assert_eq!(
+            RESP::Integer(3).try_into_value_with::<f64>(coercion),
+            Ok(3.0)
+        );
+        assert!(matches!(
+            RESP::BulkString("1.5".into()).try_into_value_with::<f64>(coercion),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert_eq!(
+            RESP::BulkString("1.5".into()).try_into_value_with::<f64>(Coercion::LENIENT),
+            Ok(1.5)
+        );
+    }
+
+    #[test]
+    fn coercion_map() {
+        let reply = || {
+            RESP::Array(vec![
+                RESP::BulkString("maxmemory".into()),
+                RESP::BulkString("1024".into()),
+                RESP::BulkString("maxclients".into()),
+                RESP::BulkString("-1".into()),
+            ])
+        };
+        assert!(matches!(
+            reply().try_into_map::<String, i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        let coercion = Coercion {
+            strings_to_numbers: true,
+            ..Coercion::STRICT
+        };
+        let map = reply().try_into_map_with::<String, i64>(coercion).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["maxmemory"], 1024);
+        assert_eq!(map["maxclients"], -1);
+
+        let reply = RESP::Map(vec![(
+            RESP::BulkString("7".into()),
+            RESP::BulkString("1.5".into()),
+        )]);
+        let map = reply.try_into_map_with::<u64, f64>(coercion).unwrap();
+        assert_eq!(map[&7], 1.5);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn coercion_booleans() {
+        assert!(matches!(
+            RESP::Boolean(true).try_into_value::<i64>(),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        let coercion = Coercion {
+            booleans_to_integers: true,
+            ..Coercion::STRICT
+        };
+        assert_eq!(
+            RESP::Boolean(true).try_into_value_with::<i64>(coercion),
+            Ok(1)
+        );
+    }
+}
@@ -0,0 +1,660 @@
+//! Serde data format reading values straight out of an encoded buffer.
+//!
+//! Strings and byte payloads are borrowed from the input whenever the target
+//! type allows it, so `#[serde(borrow)]` structs decode without copying.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, DeserializeSeed, IgnoredAny, IntoDeserializer, Visitor};
+
+use crate::framing::{self, find_crlf, CRLF};
+use crate::{preview, ParseConfig, ParseError};
+
+/// Error returned when a value can't be deserialized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The input is not valid RESP.
+    Parse(ParseError),
+    /// The input is an error reply, carrying its message.
+    Reply(String),
+    /// The target type rejected the value.
+    Message(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => err.fmt(f),
+            Self::Reply(message) => write!(f, "error reply: {message}"),
+            Self::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Deserializes a `T` from the single frame in `input`.
+pub fn from_slice<'de, T: de::Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
+    let mut deserializer = Deserializer::from_slice(input);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+pub fn from_str<'de, T: de::Deserialize<'de>>(input: &'de str) -> Result<T> {
+    from_slice(input.as_bytes())
+}
+
+/// Reads values from an encoded buffer, one frame after the other.
+#[derive(Debug)]
+pub struct Deserializer<'de> {
+    input: &'de [u8],
+    remaining_depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn from_slice(input: &'de [u8]) -> Self {
+        Self::with_config(input, &ParseConfig::default())
+    }
+
+    /// Only [`ParseConfig::max_depth`] applies here.
+    pub fn with_config(input: &'de [u8], config: &ParseConfig) -> Self {
+        Self {
+            input,
+            remaining_depth: config.max_depth,
+        }
+    }
+
+    /// Checks that the whole input was consumed.
+    pub fn end(&self) -> Result<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                "end of input",
+                format!("{} trailing bytes", self.input.len()),
+            )
+            .into())
+        }
+    }
+
+    /// Type byte of the next value, after skipping any attributes.
+    fn peek(&mut self) -> Result<u8> {
+        loop {
+            let kind = *self
+                .input
+                .first()
+                .ok_or_else(|| ParseError::end_of_input("type"))?;
+            if kind as char != crate::ATTRIBUTE {
+                return Ok(kind);
+            }
+            self.input = &self.input[1..];
+            let length = self.length()?.max(0);
+            for _ in 0..length {
+                de::Deserialize::deserialize(&mut *self).map(|IgnoredAny| ())?;
+                de::Deserialize::deserialize(&mut *self).map(|IgnoredAny| ())?;
+            }
+        }
+    }
+
+    /// Consumes the type byte and the rest of its line.
+    fn line(&mut self) -> Result<&'de [u8]> {
+        let end = find_crlf(self.input, 1).ok_or_else(|| ParseError::end_of_input("\"\\r\\n\""))?;
+        let line = &self.input[1..end];
+        self.input = &self.input[end + CRLF.len()..];
+        Ok(line)
+    }
+
+    fn parse_line<T: FromStr>(&mut self, expected: &str) -> Result<T> {
+        let line = self.line()?;
+        let line = String::from_utf8_lossy(line);
+        line.parse()
+            .map_err(|_| ParseError::new(expected, preview(&line)).into())
+    }
+
+    /// Length header of a bulk value or aggregate, after its type byte.
+    fn length(&mut self) -> Result<isize> {
+        let (length, start) = framing::read_length(self.input, 0)?
+            .ok_or_else(|| ParseError::end_of_input("\"\\r\\n\""))?;
+        self.input = &self.input[start..];
+        if length < -1 {
+            return Err(ParseError::new("length of at least -1", length.to_string()).into());
+        }
+        Ok(length)
+    }
+
+    /// Consumes a bulk value, `None` standing for its null form.
+    fn bulk(&mut self) -> Result<Option<&'de [u8]>> {
+        self.input = &self.input[1..];
+        let length = self.length()?;
+        if length < 0 {
+            return Ok(None);
+        }
+        let length = length as usize;
+        if self.input.len() < length {
+            return Err(ParseError::new(
+                format!("{length} bytes"),
+                format!("{} bytes", self.input.len()),
+            )
+            .into());
+        }
+        let (data, rest) = self.input.split_at(length);
+        if !rest.starts_with(CRLF) {
+            return Err(ParseError::end_of_input("\"\\r\\n\"").into());
+        }
+        self.input = &rest[CRLF.len()..];
+        Ok(Some(data))
+    }
+
+    /// Consumes a textual value of any kind, borrowing it from the input.
+    fn text(&mut self) -> Result<Option<&'de [u8]>> {
+        match self.peek()? as char {
+            crate::BULK_STRING => self.bulk(),
+            crate::VERBATIM_STRING => match self.bulk()? {
+                Some(data) if data.len() >= 4 && data[3] == b':' => Ok(Some(&data[4..])),
+                Some(_) => Err(ParseError::new("encoding", "malformed verbatim string").into()),
+                None => Ok(None),
+            },
+            crate::SIMPLE_STRING | crate::INTEGER | crate::DOUBLE | crate::BIG_NUMBER => {
+                self.line().map(Some)
+            }
+            _ => Err(self.unexpected("string")),
+        }
+    }
+
+    fn text_str(&mut self) -> Result<Option<&'de str>> {
+        self.text()?
+            .map(|data| {
+                std::str::from_utf8(data)
+                    .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence").into())
+            })
+            .transpose()
+    }
+
+    /// Parses a number out of a numeric or textual value.
+    fn number<T: FromStr>(&mut self, expected: &str) -> Result<T> {
+        let text = self.text()?.unwrap_or_default();
+        let text = String::from_utf8_lossy(text);
+        text.parse()
+            .map_err(|_| ParseError::new(expected, preview(&text)).into())
+    }
+
+    /// Error for a value that can't become what the caller wants, turning
+    /// error replies into [`Error::Reply`].
+    fn unexpected(&mut self, expected: &str) -> Error {
+        match self.input.first().map(|&x| x as char) {
+            Some(crate::SIMPLE_ERROR) => match self.line() {
+                Ok(line) => Error::Reply(String::from_utf8_lossy(line).into_owned()),
+                Err(err) => err,
+            },
+            Some(crate::BULK_ERROR) => match self.bulk() {
+                Ok(data) => {
+                    Error::Reply(String::from_utf8_lossy(data.unwrap_or_default()).into_owned())
+                }
+                Err(err) => err,
+            },
+            Some(kind) => ParseError::new(expected, format!("{kind:?}")).into(),
+            None => ParseError::end_of_input(expected).into(),
+        }
+    }
+
+    /// Consumes the null forms, returning whether one was found.
+    fn null(&mut self) -> Result<bool> {
+        match self.peek()? as char {
+            crate::NULL => {
+                self.line()?;
+                Ok(true)
+            }
+            crate::BULK_STRING | crate::ARRAY if self.input.get(1..5) == Some(b"-1\r\n") => {
+                self.line()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Consumes an aggregate header, returning its element count, or `None`
+    /// for null aggregates.
+    fn aggregate(&mut self) -> Result<Option<usize>> {
+        self.input = &self.input[1..];
+        let length = self.length()?;
+        if length < 0 {
+            return Ok(None);
+        }
+        if self.remaining_depth == 0 {
+            return Err(ParseError::new("less nesting", "too deeply nested value").into());
+        }
+        Ok(Some(length as usize))
+    }
+
+    fn visit_aggregate<V: Visitor<'de>>(&mut self, visitor: V, pairs: bool) -> Result<V::Value> {
+        let flat = self.input.first() != Some(&(crate::MAP as u8));
+        let Some(mut length) = self.aggregate()? else {
+            return visitor.visit_none();
+        };
+        // Flat arrays hold keys and values as separate elements
+        if pairs && flat {
+            if length % 2 != 0 {
+                return Err(ParseError::new("even amount of elements", length.to_string()).into());
+            }
+            length /= 2;
+        }
+        self.remaining_depth -= 1;
+        let value = if pairs {
+            visitor.visit_map(Access::new(self, length))
+        } else {
+            visitor.visit_seq(Access::new(self, length))
+        };
+        self.remaining_depth += 1;
+        value
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident: $type:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.number::<$type>(stringify!($type))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.peek()? as char {
+            crate::SIMPLE_STRING | crate::VERBATIM_STRING | crate::BIG_NUMBER => {
+                self.deserialize_str(visitor)
+            }
+            crate::BULK_STRING => match self.bulk()? {
+                Some(data) => match std::str::from_utf8(data) {
+                    Ok(data) => visitor.visit_borrowed_str(data),
+                    Err(_) => visitor.visit_borrowed_bytes(data),
+                },
+                None => visitor.visit_none(),
+            },
+            crate::INTEGER => visitor.visit_i64(self.parse_line("integer")?),
+            crate::DOUBLE => visitor.visit_f64(self.parse_line("double")?),
+            crate::BOOLEAN => self.deserialize_bool(visitor),
+            crate::NULL => {
+                self.line()?;
+                visitor.visit_unit()
+            }
+            crate::ARRAY | crate::SET | crate::PUSH => self.visit_aggregate(visitor, false),
+            crate::MAP => self.visit_aggregate(visitor, true),
+            _ => Err(self.unexpected("value")),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.peek()? as char {
+            crate::BOOLEAN => match self.line()? {
+                b"t" => visitor.visit_bool(true),
+                b"f" => visitor.visit_bool(false),
+                x => Err(ParseError::new("t or f", preview(&String::from_utf8_lossy(x))).into()),
+            },
+            crate::INTEGER => match self.line()? {
+                b"1" => visitor.visit_bool(true),
+                b"0" => visitor.visit_bool(false),
+                x => Err(ParseError::new("0 or 1", preview(&String::from_utf8_lossy(x))).into()),
+            },
+            _ => Err(self.unexpected("boolean")),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.text_str()? {
+            Some(text) => visitor.visit_borrowed_str(text),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.text()? {
+            Some(data) => visitor.visit_borrowed_bytes(data),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.null()? {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.null()? {
+            visitor.visit_unit()
+        } else {
+            Err(self.unexpected("null"))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.peek()? as char {
+            crate::ARRAY | crate::SET | crate::PUSH => self.visit_aggregate(visitor, false),
+            _ => Err(self.unexpected("array")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    /// Maps are also accepted as flat arrays of alternating keys and values,
+    /// the way RESP2 servers reply to commands like `HGETALL`.
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.peek()? as char {
+            crate::MAP | crate::ARRAY => self.visit_aggregate(visitor, true),
+            _ => Err(self.unexpected("map")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    /// Unit variants are read from strings, other variants from single entry
+    /// maps keyed by the variant name.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.peek()? as char {
+            crate::MAP => match self.aggregate()? {
+                Some(1) => {
+                    self.remaining_depth -= 1;
+                    let value = visitor.visit_enum(Enum { de: self });
+                    self.remaining_depth += 1;
+                    value
+                }
+                length => Err(ParseError::new(
+                    "map of 1 entry",
+                    format!("map of {} entries", length.unwrap_or_default()),
+                )
+                .into()),
+            },
+            _ => match self.text_str()? {
+                Some(variant) => visitor.visit_enum(variant.into_deserializer()),
+                None => Err(self.unexpected("enum variant")),
+            },
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    /// Skips the next frame without decoding it, error replies included.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let length = framing::frame_len(self.input)?
+            .ok_or_else(|| ParseError::end_of_input("rest of the frame"))?;
+        self.input = &self.input[length..];
+        visitor.visit_unit()
+    }
+}
+
+/// Elements of an aggregate, or entries when read as a map.
+struct Access<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'a, 'de> Access<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, remaining: usize) -> Self {
+        Self { de, remaining }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for Access<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Access<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Client<'a> {
+        name: &'a str,
+        #[serde(borrow)]
+        addr: Cow<'a, str>,
+        flags: &'a [u8],
+        db: u32,
+        lib: Option<String>,
+    }
+
+    #[test]
+    fn borrowed_struct() {
+        let input = b"%5\r\n+name\r\n$3\r\nbob\r\n+addr\r\n$14\r\n127.0.0.1:6379\r\n\
+            +flags\r\n+N\r\n+db\r\n:3\r\n+lib\r\n_\r\n";
+        let client: Client = from_slice(input).unwrap();
+        assert_eq!(client.name, "bob");
+        assert!(matches!(client.addr, Cow::Borrowed("127.0.0.1:6379")));
+        assert_eq!(client.flags, b"N");
+        assert_eq!(client.db, 3);
+        assert_eq!(client.lib, None);
+
+        // Borrowed straight from the input
+        let range = input.as_ptr_range();
+        assert!(range.contains(&client.name.as_ptr()));
+    }
+
+    #[test]
+    fn flat_array_as_map() {
+        let input = "*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n";
+        let map: HashMap<&str, i64> = from_str(input).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], 1);
+        assert_eq!(map["b"], 2);
+    }
+
+    #[test]
+    fn sequences_and_options() {
+        let input = "*3\r\n$2\r\nhi\r\n$-1\r\n+yo\r\n";
+        let values: Vec<Option<&str>> = from_str(input).unwrap();
+        assert_eq!(values, vec![Some("hi"), None, Some("yo")]);
+
+        let values: (bool, f64, i128) =
+            from_str("*3\r\n#t\r\n,1.5\r\n(12345678901234567890\r\n").unwrap();
+        assert_eq!(values, (true, 1.5, 12345678901234567890));
+    }
+
+    #[test]
+    fn attributes_skipped() {
+        let value: i64 = from_str("|1\r\n+ttl\r\n:10\r\n:42\r\n").unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn enums() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Role {
+            Master,
+            Replica { offset: u64 },
+        }
+
+        assert_eq!(from_str::<Role>("+master\r\n").unwrap(), Role::Master);
+        assert_eq!(
+            from_str::<Role>("%1\r\n+replica\r\n%1\r\n+offset\r\n:7\r\n").unwrap(),
+            Role::Replica { offset: 7 }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            from_str::<String>("-ERR unknown command\r\n").unwrap_err(),
+            Error::Reply("ERR unknown command".to_owned())
+        );
+        assert!(matches!(from_str::<u8>(":300\r\n"), Err(Error::Parse(_))));
+        assert!(matches!(
+            from_str::<i64>(":1\r\n:2\r\n"),
+            Err(Error::Parse(_))
+        ));
+        assert!(matches!(
+            from_str::<Vec<i64>>("*2\r\n:1\r\n"),
+            Err(Error::Parse(_))
+        ));
+
+        let ignored: Vec<IgnoredAny> = from_str("*2\r\n-ERR\r\n:1\r\n").unwrap();
+        assert_eq!(ignored.len(), 2);
+    }
+}
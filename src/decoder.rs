@@ -0,0 +1,999 @@
+#[cfg(feature = "allocator_api")]
+use std::alloc::{AllocError, Allocator, Global, Layout};
+use std::io::Write;
+#[cfg(feature = "allocator_api")]
+use std::ptr::NonNull;
+#[cfg(feature = "resp3")]
+use std::sync::mpsc::Sender;
+#[cfg(feature = "allocator_api")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::capture::{CaptureWriter, Direction};
+use crate::{framing, DecodeError, ParseConfig, ParseError, RESP};
+
+/// Something the [`Decoder`] produced from its input.
+#[derive(Debug, PartialEq)]
+pub enum Decoded {
+    /// A fully decoded frame.
+    Frame(RESP),
+    /// A bulk string of the given length whose payload was written to the sink.
+    Streamed(usize),
+}
+
+/// A decoded frame alongside the exact bytes it was decoded from, so it can
+/// be inspected and still forwarded verbatim.
+#[cfg(feature = "bytes")]
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub raw: bytes::Bytes,
+    pub value: RESP,
+}
+
+/// How long a frame may stay incomplete before the [`Decoder`] gives up on
+/// it, `None` meaning no limit. Servers use these to drop clients that hold
+/// a buffer hostage by sending a frame slowly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StallLimits {
+    /// Bytes an incomplete frame can take.
+    pub bytes: Option<usize>,
+    /// Time since the first byte of an incomplete frame was fed.
+    pub duration: Option<Duration>,
+}
+
+/// Memory held by a [`Decoder`], returned by
+/// [`memory_usage`](Decoder::memory_usage) to check per-connection budgets.
+///
+/// Decoded frames are handed out right away, so what is buffered is input
+/// not decoded yet: complete frames waiting for a call to decode, and the
+/// incomplete frame after them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// Bytes received but not decoded yet.
+    pub buffered: usize,
+    /// Most bytes buffered at once since the decoder was created or
+    /// [`reset_peak`](Decoder::reset_peak) was called.
+    pub peak_buffered: usize,
+    /// Buffered bytes making up complete frames.
+    pub complete_frames: usize,
+    /// Buffered bytes of the incomplete frame after them.
+    pub partial_frame: usize,
+    /// Capacity of the buffer, which can exceed what it holds.
+    pub allocated: usize,
+}
+
+/// Allocator of the input buffer, the global one unless the decoder was
+/// created with one.
+#[cfg(feature = "allocator_api")]
+#[derive(Clone, Default)]
+struct BufferAllocator(Option<Arc<dyn Allocator + Send + Sync>>);
+
+#[cfg(feature = "allocator_api")]
+impl std::fmt::Debug for BufferAllocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Custom"),
+            None => f.write_str("Global"),
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl BufferAllocator {
+    fn get(&self) -> &dyn Allocator {
+        match &self.0 {
+            Some(x) => x.as_ref(),
+            None => &Global,
+        }
+    }
+}
+
+// Forwards everything to the allocator it holds
+#[cfg(feature = "allocator_api")]
+unsafe impl Allocator for BufferAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.get().allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.get().deallocate(ptr, layout) }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.get().grow(ptr, old_layout, new_layout) }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.get().shrink(ptr, old_layout, new_layout) }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+#[derive(Debug)]
+struct Buffer(Vec<u8, BufferAllocator>);
+
+#[cfg(feature = "allocator_api")]
+impl Default for Buffer {
+    fn default() -> Self {
+        Self(Vec::new_in(BufferAllocator::default()))
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl std::ops::Deref for Buffer {
+    type Target = Vec<u8, BufferAllocator>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl std::ops::DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+type Buffer = Vec<u8>;
+
+#[derive(Debug)]
+struct Stream {
+    length: usize,
+    remaining: usize,
+}
+
+/// Incremental decoder that buffers input until whole frames are available.
+///
+/// Once an error is returned the input can't be resynchronized, so the
+/// connection feeding it should be dropped.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Buffer,
+    /// Bytes at the start of `buffer` already decoded, dropped on the next
+    /// feed so decoding pipelined frames doesn't shift the rest each time.
+    start: usize,
+    config: ParseConfig,
+    stream_threshold: Option<usize>,
+    stream: Option<Stream>,
+    #[cfg(feature = "resp3")]
+    pushes: Option<Sender<RESP>>,
+    budget: Option<usize>,
+    yielded: usize,
+    stall_limits: StallLimits,
+    /// When the first byte of the incomplete frame was fed, if timed.
+    partial_since: Option<Instant>,
+    peak_buffered: usize,
+}
+
+#[cfg(feature = "resp3")]
+fn is_push(value: &RESP) -> bool {
+    match value {
+        RESP::Push(_) => true,
+        RESP::WithAttributes { value, .. } => is_push(value),
+        _ => false,
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParseConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a decoder whose input buffer is allocated from `allocator`,
+    /// such as a pool or slab kept for connections.
+    ///
+    /// Only the buffer is, decoded values are allocated as usual.
+    #[cfg(feature = "allocator_api")]
+    pub fn new_in<A>(allocator: A) -> Self
+    where
+        A: Allocator + Send + Sync + 'static,
+    {
+        Self::with_config_in(ParseConfig::default(), allocator)
+    }
+
+    #[cfg(feature = "allocator_api")]
+    pub fn with_config_in<A>(config: ParseConfig, allocator: A) -> Self
+    where
+        A: Allocator + Send + Sync + 'static,
+    {
+        let allocator = BufferAllocator(Some(Arc::new(allocator)));
+        Self {
+            buffer: Buffer(Vec::new_in(allocator)),
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Makes [`decode_to`](Self::decode_to) stream the payload of top level
+    /// bulk strings longer than `threshold` bytes instead of buffering them.
+    pub fn stream_bulk_strings_over(&mut self, threshold: usize) {
+        self.stream_threshold = Some(threshold);
+    }
+
+    /// Sends decoded [`Push`](RESP::Push) frames to `pushes` instead of
+    /// returning them, so out-of-band messages like pub/sub or invalidations
+    /// are kept apart from replies.
+    ///
+    /// Pushes are returned again once the receiver is dropped.
+    ///
+    /// ```
+    /// use resp_parser_rs::{Decoder, RESP};
+    ///
+    /// let (sender, pushes) = std::sync::mpsc::channel();
+    /// let mut decoder = Decoder::new();
+    /// decoder.route_pushes(sender);
+    /// decoder.feed(b">2\r\n+invalidate\r\n_\r\n:1\r\n");
+    /// assert_eq!(decoder.decode().unwrap(), Some(RESP::Integer(1)));
+    /// assert!(matches!(pushes.try_recv(), Ok(RESP::Push(_))));
+    /// ```
+    #[cfg(feature = "resp3")]
+    pub fn route_pushes(&mut self, pushes: Sender<RESP>) {
+        self.pushes = Some(pushes);
+    }
+
+    /// Caps the frames returned between two feeds to `budget`, after which
+    /// decoding returns `None` until more input is fed or the budget is
+    /// [reset](Self::reset_budget), so a task handling a flood of pipelined
+    /// commands can yield to others.
+    pub fn set_frame_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Whether decoding stopped because of the frame budget rather than for
+    /// lack of input.
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget.is_some_and(|x| self.yielded >= x)
+    }
+
+    pub fn reset_budget(&mut self) {
+        self.yielded = 0;
+    }
+
+    /// Makes decoding fail with [`DecodeError::Stalled`] once an incomplete
+    /// frame exceeds `limits`.
+    pub fn set_stall_limits(&mut self, limits: StallLimits) {
+        self.stall_limits = limits;
+    }
+
+    /// Whether the incomplete frame buffered exceeds the stall limits. Also
+    /// checked on decoding, this lets a timer catch clients that stopped
+    /// sending altogether. Complete frames left buffered, for example by the
+    /// frame budget, don't count.
+    pub fn stalled(&self) -> bool {
+        self.stream.is_none() && self.stalled_after(self.complete_frames())
+    }
+
+    fn stalled_after(&self, complete_frames: usize) -> bool {
+        let StallLimits { bytes, duration } = self.stall_limits;
+        let partial_frame = self.buffered() - complete_frames;
+        partial_frame > 0
+            && (bytes.is_some_and(|x| partial_frame > x)
+                || duration
+                    .zip(self.partial_since)
+                    .is_some_and(|(x, since)| since.elapsed() > x))
+    }
+
+    /// Drops the incomplete frame if it stalled, returning how many bytes
+    /// were freed. Complete frames before it are kept. The input can't be
+    /// resynchronized afterwards, this only releases the memory until the
+    /// connection is closed.
+    pub fn discard_stalled(&mut self) -> Option<usize> {
+        let complete_frames = self.complete_frames();
+        if self.stream.is_some() || !self.stalled_after(complete_frames) {
+            return None;
+        }
+        let discarded = self.buffered() - complete_frames;
+        self.buffer.truncate(self.start + complete_frames);
+        if complete_frames == 0 {
+            self.buffer.clear();
+            self.buffer.shrink_to_fit();
+            self.start = 0;
+        }
+        self.partial_since = None;
+        Some(discarded)
+    }
+
+    /// Length of the complete frames at the start of the buffer.
+    fn complete_frames(&self) -> usize {
+        let mut complete_frames = 0;
+        if self.stream.is_none() {
+            while let Ok(Some(length)) = framing::frame_len(&self.pending()[complete_frames..]) {
+                complete_frames += length;
+            }
+        }
+        complete_frames
+    }
+
+    /// Input not decoded yet.
+    fn pending(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    /// Marks `length` bytes of the pending input as decoded.
+    fn consume(&mut self, length: usize) {
+        self.start += length;
+        if self.start == self.buffer.len() {
+            self.buffer.clear();
+            self.start = 0;
+        }
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        self.yielded = 0;
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        if self.buffer.is_empty() && self.stall_limits.duration.is_some() {
+            self.partial_since = Some(Instant::now());
+        }
+        self.buffer.extend_from_slice(data);
+        self.peak_buffered = self.peak_buffered.max(self.buffer.len());
+    }
+
+    /// Feeds segmented input, such as the iovecs of a vectored read, without
+    /// joining the segments first.
+    pub fn feed_chunks<'a, I>(&mut self, chunks: I)
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        for chunk in chunks {
+            self.feed(chunk);
+        }
+    }
+
+    /// Consumes everything left in `buf`, chunk by chunk, so rope or ring
+    /// buffers never have to be made contiguous.
+    #[cfg(feature = "bytes")]
+    pub fn feed_buf<B: bytes::Buf>(&mut self, mut buf: B) {
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            let length = chunk.len();
+            self.feed(chunk);
+            buf.advance(length);
+        }
+    }
+
+    /// Bytes received but not decoded yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Memory currently held, split by what it's held for, and the peak of
+    /// it. Complete frames are measured by walking the buffer, so this costs
+    /// as much as framing what is buffered.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let complete_frames = self.complete_frames();
+        MemoryUsage {
+            buffered: self.buffered(),
+            peak_buffered: self.peak_buffered,
+            complete_frames,
+            partial_frame: self.buffered() - complete_frames,
+            allocated: self.buffer.capacity(),
+        }
+    }
+
+    /// Restarts tracking the peak of buffered bytes from what is buffered
+    /// now.
+    pub fn reset_peak(&mut self) {
+        self.peak_buffered = self.buffered();
+    }
+
+    /// Decodes the next complete frame, returning `None` if more input is needed.
+    pub fn decode(&mut self) -> Result<Option<RESP>, DecodeError> {
+        self.decode_with(|_| Ok(()))
+    }
+
+    /// Like [`decode`](Self::decode), but keeps the raw bytes of the frame
+    /// next to its value.
+    #[cfg(feature = "bytes")]
+    pub fn decode_frame(&mut self) -> Result<Option<Frame>, DecodeError> {
+        let mut raw = bytes::Bytes::new();
+        let value = self.decode_with(|frame| {
+            raw = bytes::Bytes::copy_from_slice(frame);
+            Ok(())
+        })?;
+        Ok(value.map(|value| Frame { raw, value }))
+    }
+
+    /// Like [`decode`](Self::decode), but also records the raw bytes of every
+    /// decoded frame into `capture` as inbound traffic.
+    pub fn decode_captured<W: Write>(
+        &mut self,
+        capture: &mut CaptureWriter<W>,
+    ) -> Result<Option<RESP>, DecodeError> {
+        self.decode_with(|frame| capture.record(Direction::Inbound, frame))
+    }
+
+    fn decode_with<F>(&mut self, tee: F) -> Result<Option<RESP>, DecodeError>
+    where
+        F: FnMut(&[u8]) -> std::io::Result<()>,
+    {
+        let decoded = self.decode_next(tee);
+        #[cfg(feature = "metrics")]
+        if let Err(err) = &decoded {
+            crate::telemetry::decode_failed(err);
+        }
+        decoded
+    }
+
+    // only routed pushes loop back for the next frame
+    #[cfg_attr(not(feature = "resp3"), allow(clippy::never_loop))]
+    fn decode_next<F>(&mut self, mut tee: F) -> Result<Option<RESP>, DecodeError>
+    where
+        F: FnMut(&[u8]) -> std::io::Result<()>,
+    {
+        if self.stream.is_some() {
+            return Err(DecodeError::StreamInProgress);
+        }
+        loop {
+            if self.budget_exhausted() {
+                return Ok(None);
+            }
+            let Some(length) = framing::frame_len(self.pending())? else {
+                // The buffer starts with the incomplete frame
+                if self.stalled_after(0) {
+                    return Err(DecodeError::Stalled {
+                        buffered: self.buffered(),
+                    });
+                }
+                return Ok(None);
+            };
+            let frame = &self.pending()[..length];
+            let data = std::str::from_utf8(frame)
+                .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence"))?;
+            let value = RESP::try_parse_with(data, &self.config)?;
+            tee(frame)?;
+            self.consume(length);
+            #[cfg(feature = "metrics")]
+            crate::telemetry::frame_decoded(length);
+            // The rest belongs to the next frame, timed from now
+            self.partial_since = match self.stall_limits.duration {
+                Some(_) if self.buffered() > 0 => Some(Instant::now()),
+                _ => None,
+            };
+
+            #[cfg(feature = "resp3")]
+            let value = match &self.pushes {
+                Some(pushes) if is_push(&value) => match pushes.send(value) {
+                    Ok(()) => continue,
+                    Err(err) => {
+                        self.pushes = None;
+                        err.0
+                    }
+                },
+                _ => value,
+            };
+            self.yielded += 1;
+            return Ok(Some(value));
+        }
+    }
+
+    /// Like [`decode`](Self::decode), but writes the payload of bulk strings
+    /// over the configured threshold into `sink` as it arrives.
+    ///
+    /// Writing blocks decoding, see [`decode_to_async`](Self::decode_to_async)
+    /// for sinks written from async tasks.
+    pub fn decode_to<W: Write>(&mut self, sink: &mut W) -> Result<Option<Decoded>, DecodeError> {
+        if !self.start_stream()? {
+            return Ok(None);
+        }
+        let Some(chunk) = self.stream_chunk() else {
+            return Ok(self.decode()?.map(Decoded::Frame));
+        };
+        sink.write_all(&self.pending()[..chunk])?;
+        self.streamed(chunk)
+    }
+
+    /// Like [`decode_to`](Self::decode_to), writing to an asynchronous sink
+    /// such as a file or socket of an async runtime.
+    #[cfg(feature = "futures")]
+    pub async fn decode_to_async<W>(&mut self, sink: &mut W) -> Result<Option<Decoded>, DecodeError>
+    where
+        W: futures::io::AsyncWrite + Unpin,
+    {
+        use futures::io::AsyncWriteExt;
+
+        if !self.start_stream()? {
+            return Ok(None);
+        }
+        let Some(chunk) = self.stream_chunk() else {
+            return Ok(self.decode()?.map(Decoded::Frame));
+        };
+        sink.write_all(&self.pending()[..chunk]).await?;
+        self.streamed(chunk)
+    }
+
+    /// Starts streaming the bulk string at the start of the buffer if it's
+    /// over the threshold, returning `false` until its header is complete.
+    fn start_stream(&mut self) -> Result<bool, DecodeError> {
+        if self.stream.is_some() {
+            return Ok(true);
+        }
+        match self.stream_threshold {
+            Some(threshold) if self.pending().first() == Some(&b'$') => {
+                let Some((length, start)) = framing::read_length(self.pending(), 1)? else {
+                    return Ok(false);
+                };
+                if length >= 0 && length as usize > threshold {
+                    self.consume(start);
+                    self.stream = Some(Stream {
+                        length: length as usize,
+                        remaining: length as usize,
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Buffered bytes of the payload being streamed, `None` when not
+    /// streaming.
+    fn stream_chunk(&self) -> Option<usize> {
+        let stream = self.stream.as_ref()?;
+        Some(stream.remaining.min(self.buffered()))
+    }
+
+    /// Drops the `chunk` written to the sink, returning the bulk string once
+    /// all of it was.
+    fn streamed(&mut self, chunk: usize) -> Result<Option<Decoded>, DecodeError> {
+        let Some(stream) = &mut self.stream else {
+            return Ok(None);
+        };
+        stream.remaining -= chunk;
+        let remaining = stream.remaining;
+        self.consume(chunk);
+
+        if remaining > 0 || self.buffered() < 2 {
+            Ok(None)
+        } else if self.pending().starts_with(b"\r\n") {
+            self.consume(2);
+            let length = self.stream.take().map_or(0, |x| x.length);
+            Ok(Some(Decoded::Streamed(length)))
+        } else {
+            Err(ParseError::new(
+                "\"\\r\\n\"",
+                format!("{:?}", String::from_utf8_lossy(&self.pending()[..2])),
+            )
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let mut decoder = Decoder::new();
+        decoder.feed(b"+OK\r\n:12");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::SimpleString(_)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        decoder.feed(b"3\r\n*1\r\n$5\r\nHel");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(123)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        decoder.feed(b"lo\r\n");
+        let decoded = decoder.decode();
+        assert!(matches!(decoded, Ok(Some(RESP::Array(_)))));
+        if let Ok(Some(RESP::Array(data))) = decoded {
+            assert_eq!(data, vec![RESP::BulkString("Hello".to_owned())]);
+        }
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[test]
+    fn decode_in_allocator() {
+        use std::sync::atomic::{AtomicIsize, Ordering};
+
+        /// Global allocator keeping count of the bytes held.
+        #[derive(Default)]
+        struct Counted(Arc<AtomicIsize>);
+
+        unsafe impl Allocator for Counted {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.fetch_add(layout.size() as isize, Ordering::Relaxed);
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.0.fetch_sub(layout.size() as isize, Ordering::Relaxed);
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+
+        let held = Arc::new(AtomicIsize::new(0));
+        let mut decoder = Decoder::new_in(Counted(held.clone()));
+        assert_eq!(held.load(Ordering::Relaxed), 0);
+        decoder.feed(b"*2\r\n$5\r\nHello\r\n:1\r\n+O");
+        assert!(held.load(Ordering::Relaxed) >= 21);
+        assert_eq!(
+            held.load(Ordering::Relaxed),
+            decoder.memory_usage().allocated as isize
+        );
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Array(_)))));
+        decoder.feed(&b":1\r\n".repeat(100));
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::SimpleString(_)))));
+        assert_eq!(
+            held.load(Ordering::Relaxed),
+            decoder.memory_usage().allocated as isize
+        );
+        drop(decoder);
+        assert_eq!(held.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn decode_pipelined() {
+        let frames = 100_000;
+        let mut decoder = Decoder::new();
+        decoder.feed(&b":1\r\n".repeat(frames));
+        decoder.feed(b"+O");
+        let fed = decoder.buffer.len();
+        for _ in 0..frames {
+            assert_eq!(decoder.decode().unwrap(), Some(RESP::Integer(1)));
+            // Decoded frames are skipped over rather than shifted out
+            assert_eq!(decoder.buffer.len(), fed);
+        }
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert_eq!(decoder.buffered(), 2);
+        decoder.feed(b"K\r\n");
+        assert_eq!(decoder.buffer.len(), 5);
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(RESP::SimpleString("OK".into()))
+        );
+        assert_eq!((decoder.buffered(), decoder.buffer.len()), (0, 0));
+    }
+
+    #[test]
+    fn decode_bulk_with_line_breaks() {
+        let mut decoder = Decoder::new();
+        decoder.feed(b"$4\r\na\r\nb\r\n$16\r\n# Server\r\nrole:");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(RESP::BulkString("a\r\nb".to_owned()))
+        );
+        assert!(matches!(decoder.decode(), Ok(None)));
+        decoder.feed(b"m\r\n");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(RESP::BulkString("# Server\r\nrole:m".to_owned()))
+        );
+        decoder.feed(b"$1\r\n\n\r\n");
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(RESP::BulkString("\n".to_owned()))
+        );
+    }
+
+    #[cfg(all(feature = "resp3", feature = "inline"))]
+    #[test]
+    fn decode_byte_by_byte() {
+        let input = b"*2\r\n$5\r\nHello\r\n%1\r\n+key\r\n,1.5\r\nPING\r\n";
+        let mut decoder = Decoder::new();
+        let mut decoded = Vec::new();
+        for byte in input {
+            decoder.feed(&[*byte]);
+            if let Some(value) = decoder.decode().unwrap() {
+                decoded.push(value);
+            }
+        }
+        assert_eq!(decoded.len(), 2);
+        assert!(matches!(decoded[0], RESP::Array(_)));
+        assert!(matches!(decoded[1], RESP::Inline(_)));
+    }
+
+    #[test]
+    fn decode_stalled_bytes() {
+        let mut decoder = Decoder::new();
+        decoder.set_stall_limits(StallLimits {
+            bytes: Some(8),
+            duration: None,
+        });
+        decoder.feed(b"+OK\r\n$5\r\nHel");
+        assert!(matches!(decoder.decode(), Ok(Some(_))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert_eq!(decoder.discard_stalled(), None);
+        decoder.feed(b"lo");
+        assert!(matches!(
+            decoder.decode(),
+            Err(DecodeError::Stalled { buffered: 9 })
+        ));
+        assert_eq!(decoder.discard_stalled(), Some(9));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn decode_stalled_duration() {
+        let mut decoder = Decoder::new();
+        decoder.set_stall_limits(StallLimits {
+            bytes: None,
+            duration: Some(Duration::from_millis(20)),
+        });
+        decoder.feed(b"*2\r\n:1\r\n");
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert!(!decoder.stalled());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(decoder.stalled());
+        decoder.feed(b":2");
+        assert!(matches!(decoder.decode(), Err(DecodeError::Stalled { .. })));
+
+        // Completing the frame in time resets the clock
+        let mut decoder = Decoder::new();
+        decoder.set_stall_limits(StallLimits {
+            bytes: None,
+            duration: Some(Duration::from_millis(20)),
+        });
+        decoder.feed(b"+OK\r");
+        std::thread::sleep(Duration::from_millis(30));
+        decoder.feed(b"\n");
+        assert!(matches!(decoder.decode(), Ok(Some(_))));
+        assert!(!decoder.stalled());
+    }
+
+    #[test]
+    fn decode_stalled_with_budget() {
+        let mut decoder = Decoder::new();
+        decoder.set_frame_budget(Some(1));
+        decoder.set_stall_limits(StallLimits {
+            bytes: Some(4),
+            duration: Some(Duration::from_millis(20)),
+        });
+        decoder.feed(b":1\r\n:2\r\n:3\r\n");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(1)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        std::thread::sleep(Duration::from_millis(30));
+        // Only complete frames are left
+        assert!(!decoder.stalled());
+        assert_eq!(decoder.discard_stalled(), None);
+
+        decoder.feed(b"$5\r\nHello");
+        assert!(decoder.stalled());
+        assert_eq!(decoder.discard_stalled(), Some(9));
+        assert_eq!(decoder.buffered(), 8);
+        decoder.set_frame_budget(None);
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(2)))));
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(3)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+    }
+
+    #[test]
+    fn decode_chunks() {
+        let mut decoder = Decoder::new();
+        decoder.feed_chunks([&b"*2\r\n$5\r\nHel"[..], b"lo\r\n:", b"1\r\n"]);
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Array(_)))));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn decode_frame_raw() {
+        let mut decoder = Decoder::new();
+        decoder.feed(b"*1\r\n:007\r\n+OK\r");
+        let frame = decoder.decode_frame().unwrap().unwrap();
+        assert_eq!(&frame.raw[..], b"*1\r\n:007\r\n");
+        assert_eq!(frame.value, RESP::Array(vec![RESP::Integer(7)]));
+        assert!(matches!(decoder.decode_frame(), Ok(None)));
+        decoder.feed(b"\n");
+        assert_eq!(
+            &decoder.decode_frame().unwrap().unwrap().raw[..],
+            b"+OK\r\n"
+        );
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn decode_buf() {
+        use bytes::Buf;
+        use std::collections::VecDeque;
+
+        let mut decoder = Decoder::new();
+        decoder.feed_buf((&b"+Hel"[..]).chain(&b"lo\r\n:1"[..]));
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::SimpleString(_)))));
+
+        // Wrapped ring buffer, split in two chunks
+        let mut ring = VecDeque::with_capacity(8);
+        ring.extend(b"xxxxxx");
+        ring.drain(..6);
+        ring.extend(b"2\r\n+OK\r\n");
+        decoder.feed_buf(ring);
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(12)))));
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::SimpleString(_)))));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let mut decoder = Decoder::new();
+        decoder.feed(b"#x\r\n");
+        assert!(matches!(decoder.decode(), Err(DecodeError::Parse(_))));
+
+        let mut decoder = Decoder::new();
+        decoder.feed(b"$-5\r\n");
+        assert!(matches!(decoder.decode(), Err(DecodeError::Parse(_))));
+
+        let mut decoder = Decoder::new();
+        decoder.feed(b"$3\r\nHello\r\n");
+        assert!(matches!(decoder.decode(), Err(DecodeError::Parse(_))));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn decode_routes_pushes() {
+        let (sender, pushes) = std::sync::mpsc::channel();
+        let mut decoder = Decoder::new();
+        decoder.route_pushes(sender);
+        decoder.feed(b">2\r\n+message\r\n+a\r\n+OK\r\n|1\r\n+k\r\n:1\r\n>1\r\n+b\r\n>1\r\n");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::SimpleString(_)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert!(matches!(pushes.try_recv(), Ok(RESP::Push(_))));
+        assert!(matches!(pushes.try_recv(), Ok(RESP::WithAttributes { .. })));
+        assert!(matches!(pushes.try_recv(), Err(_)));
+
+        drop(pushes);
+        decoder.feed(b"+c\r\n");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Push(_)))));
+    }
+
+    #[test]
+    fn decode_budget() {
+        let mut decoder = Decoder::new();
+        decoder.set_frame_budget(Some(2));
+        decoder.feed(b":1\r\n:2\r\n:3\r\n");
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(1)))));
+        assert!(!decoder.budget_exhausted());
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(2)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert!(decoder.budget_exhausted());
+
+        decoder.reset_budget();
+        assert!(matches!(decoder.decode(), Ok(Some(RESP::Integer(3)))));
+        assert!(matches!(decoder.decode(), Ok(None)));
+        assert!(!decoder.budget_exhausted());
+    }
+
+    #[test]
+    fn decode_captured() {
+        use crate::capture::CaptureReader;
+
+        let mut capture = CaptureWriter::new(Vec::new()).unwrap();
+        let mut decoder = Decoder::new();
+        decoder.feed(b"+OK\r\n:1");
+        assert!(matches!(decoder.decode_captured(&mut capture), Ok(Some(_))));
+        assert!(matches!(decoder.decode_captured(&mut capture), Ok(None)));
+        decoder.feed(b"2\r\n");
+        assert!(matches!(decoder.decode_captured(&mut capture), Ok(Some(_))));
+
+        let data = capture.into_inner();
+        let frames = CaptureReader::new(&data[..])
+            .unwrap()
+            .map(|record| record.unwrap().frame)
+            .collect::<Vec<_>>();
+        assert_eq!(frames, vec![b"+OK\r\n".to_vec(), b":12\r\n".to_vec()]);
+    }
+
+    #[test]
+    fn decode_to_streams_large_bulk() {
+        let mut decoder = Decoder::new();
+        decoder.stream_bulk_strings_over(4);
+        let mut sink = Vec::new();
+
+        decoder.feed(b"$3\r\nabc\r\n$10\r\n0123");
+        assert!(matches!(
+            decoder.decode_to(&mut sink),
+            Ok(Some(Decoded::Frame(RESP::BulkString(_))))
+        ));
+        assert!(matches!(decoder.decode_to(&mut sink), Ok(None)));
+        assert_eq!(sink, b"0123");
+        assert!(matches!(
+            decoder.decode(),
+            Err(DecodeError::StreamInProgress)
+        ));
+
+        decoder.feed(b"456789\r");
+        assert!(matches!(decoder.decode_to(&mut sink), Ok(None)));
+        decoder.feed(b"\n+OK\r\n");
+        assert!(matches!(
+            decoder.decode_to(&mut sink),
+            Ok(Some(Decoded::Streamed(10)))
+        ));
+        assert_eq!(sink, b"0123456789");
+        assert!(matches!(
+            decoder.decode_to(&mut sink),
+            Ok(Some(Decoded::Frame(RESP::SimpleString(_))))
+        ));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn decode_to_async_streams_large_bulk() {
+        let mut decoder = Decoder::new();
+        decoder.stream_bulk_strings_over(4);
+        let mut sink = futures::io::Cursor::new(Vec::new());
+        let decode = |decoder: &mut Decoder, sink: &mut _| {
+            futures::executor::block_on(decoder.decode_to_async(sink))
+        };
+        decoder.feed(b"$10\r\nHello");
+        assert!(matches!(decode(&mut decoder, &mut sink), Ok(None)));
+        decoder.feed(b"World\r\n:1\r\n");
+        assert!(matches!(
+            decode(&mut decoder, &mut sink),
+            Ok(Some(Decoded::Streamed(10)))
+        ));
+        assert!(matches!(
+            decode(&mut decoder, &mut sink),
+            Ok(Some(Decoded::Frame(RESP::Integer(1))))
+        ));
+        assert_eq!(sink.into_inner(), b"HelloWorld");
+    }
+
+    #[test]
+    fn decode_to_nested_bulk_is_buffered() {
+        let mut decoder = Decoder::new();
+        decoder.stream_bulk_strings_over(4);
+        let mut sink = Vec::new();
+
+        decoder.feed(b"*1\r\n$10\r\n0123456789\r\n");
+        assert!(matches!(
+            decoder.decode_to(&mut sink),
+            Ok(Some(Decoded::Frame(RESP::Array(_))))
+        ));
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn decode_to_invalid_terminator() {
+        let mut decoder = Decoder::new();
+        decoder.stream_bulk_strings_over(1);
+        let mut sink = Vec::new();
+
+        decoder.feed(b"$3\r\nabcde");
+        assert!(matches!(
+            decoder.decode_to(&mut sink),
+            Err(DecodeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.memory_usage(), MemoryUsage::default());
+        decoder.feed(b"+OK\r\n:1\r\n$5\r\nHel");
+        let usage = decoder.memory_usage();
+        assert_eq!(
+            (usage.buffered, usage.complete_frames, usage.partial_frame),
+            (16, 9, 7)
+        );
+        assert!(usage.allocated >= 16);
+
+        assert!(matches!(decoder.decode(), Ok(Some(_))));
+        assert!(matches!(decoder.decode(), Ok(Some(_))));
+        let usage = decoder.memory_usage();
+        assert_eq!((usage.buffered, usage.peak_buffered), (7, 16));
+        decoder.reset_peak();
+        assert_eq!(decoder.memory_usage().peak_buffered, 7);
+    }
+}
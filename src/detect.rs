@@ -0,0 +1,114 @@
+use crate::framing::walk_frame;
+use crate::{Protocol, RESP};
+
+/// Type bytes only RESP3 has.
+const RESP3_TYPES: &[u8] = b"_#,(!=~>%|";
+
+/// Version asked for by a `HELLO` request.
+fn hello_version(frame: &[u8]) -> Option<Protocol> {
+    let value = RESP::try_parse(std::str::from_utf8(frame).ok()?).ok()?;
+    let arguments = match (value.as_array(), value.as_inline()) {
+        (Some(x), _) => x
+            .iter()
+            .map(RESP::as_bulk_string)
+            .collect::<Option<Vec<_>>>()?,
+        (_, Some(x)) => x.iter().map(String::as_str).collect(),
+        _ => return None,
+    };
+    match arguments[..] {
+        [command, version, ..] if command.eq_ignore_ascii_case("HELLO") => match version {
+            "2" => Some(Protocol::Resp2),
+            "3" => Some(Protocol::Resp3),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Protocol {
+    /// Guesses the protocol of a connection from the first bytes sent in
+    /// either direction, so a proxy can pick a parser mode for it.
+    ///
+    /// A `HELLO` request settles it with the version it asks for, and RESP3
+    /// type bytes mean RESP3. Connections start out speaking RESP2, so
+    /// complete frames showing neither give RESP2. `None` means there is no
+    /// complete frame yet, or the input isn't RESP.
+    ///
+    /// ```
+    /// use resp_parser_rs::Protocol;
+    ///
+    /// let hello = b"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n";
+    /// assert_eq!(Protocol::detect(hello), Some(Protocol::Resp3));
+    /// assert_eq!(Protocol::detect(b"*1\r\n$4\r\nPING\r\n"), Some(Protocol::Resp2));
+    /// assert_eq!(Protocol::detect(b"*1\r\n$4\r\nPI"), None);
+    /// ```
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let mut detected = None;
+        let mut position = 0;
+        while position < data.len() {
+            let mut resp3 = false;
+            let Ok(Some(length)) = walk_frame(&data[position..], |kind| {
+                resp3 |= RESP3_TYPES.contains(&kind);
+            }) else {
+                break;
+            };
+            if resp3 {
+                return Some(Self::Resp3);
+            }
+            if let Some(version) = hello_version(&data[position..position + length]) {
+                return Some(version);
+            }
+            detected = Some(Self::Resp2);
+            position += length;
+        }
+        detected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_hello() {
+        let hello = b"*3\r\n$5\r\nhello\r\n$1\r\n2\r\n$4\r\nAUTH\r\n";
+        assert_eq!(Protocol::detect(hello), Some(Protocol::Resp2));
+        let hello = b"*1\r\n$4\r\nPING\r\n*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n";
+        assert_eq!(Protocol::detect(hello), Some(Protocol::Resp3));
+        // Without a version the connection keeps its protocol
+        assert_eq!(
+            Protocol::detect(b"*1\r\n$5\r\nHELLO\r\n"),
+            Some(Protocol::Resp2)
+        );
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn detect_inline_hello() {
+        assert_eq!(Protocol::detect(b"HELLO 3\r\n"), Some(Protocol::Resp3));
+    }
+
+    #[test]
+    fn detect_type_bytes() {
+        assert_eq!(
+            Protocol::detect(b"%1\r\n+server\r\n+redis\r\n"),
+            Some(Protocol::Resp3)
+        );
+        // Nested in a RESP2 aggregate
+        assert_eq!(
+            Protocol::detect(b"+OK\r\n*2\r\n:1\r\n_\r\n"),
+            Some(Protocol::Resp3)
+        );
+        assert_eq!(
+            Protocol::detect(b"+OK\r\n*2\r\n:1\r\n$-1\r\n"),
+            Some(Protocol::Resp2)
+        );
+    }
+
+    #[test]
+    fn detect_undecided() {
+        assert_eq!(Protocol::detect(b""), None);
+        assert_eq!(Protocol::detect(b"*2\r\n$5\r\nHELLO\r\n"), None);
+        assert_eq!(Protocol::detect(b"$x\r\n"), None);
+    }
+}
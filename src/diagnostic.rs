@@ -0,0 +1,91 @@
+//! [`miette`] diagnostics, pointing at the offending input once the report
+//! is given the parsed text with
+//! [`Report::with_source_code`](miette::Report::with_source_code).
+
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan};
+
+use crate::{DecodeError, ParseError, ReadError};
+
+impl Diagnostic for ParseError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new("resp::parse"))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label =
+            LabeledSpan::new_with_span(Some(format!("expected {}", self.expected())), self.span()?);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+impl Diagnostic for DecodeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            Self::Parse(err) => err.code(),
+            Self::StreamInProgress => Some(Box::new("resp::stream_in_progress")),
+            Self::Io(_) => Some(Box::new("resp::io")),
+            Self::Stalled { .. } => Some(Box::new("resp::stalled")),
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match self {
+            Self::Parse(err) => err.labels(),
+            _ => None,
+        }
+    }
+}
+
+impl Diagnostic for ReadError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            Self::Decode(err) => err.code(),
+            Self::Conversion(_) => Some(Box::new("resp::conversion")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RESP;
+
+    fn labels(err: &ParseError) -> Vec<(Option<String>, usize, usize)> {
+        err.labels()
+            .into_iter()
+            .flatten()
+            .map(|x| (x.label().map(ToOwned::to_owned), x.offset(), x.len()))
+            .collect()
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parse_error_labels() {
+        let err = RESP::try_parse("*2\r\n:1\r\n#x\r\n").unwrap_err();
+        assert_eq!(
+            labels(&err),
+            vec![(Some("expected t or f".to_owned()), 8, 4)]
+        );
+
+        let err = RESP::parse_complete(":1\r\n+OK\r\n").unwrap_err();
+        assert_eq!(
+            labels(&err),
+            vec![(Some("expected end of input".to_owned()), 4, 5)]
+        );
+    }
+
+    #[test]
+    fn parse_error_report() {
+        let input = "*1\r\n$5\r\nab\r\n";
+        let err = RESP::try_parse(input).unwrap_err();
+        let report = miette::Report::new(err).with_source_code(input);
+        let mut output = String::new();
+        miette::NarratableReportHandler::new()
+            .render_report(&mut output, report.as_ref())
+            .unwrap();
+        assert!(output.contains("expected 5 bytes"), "{output}");
+        assert!(output.contains("diagnostic code: resp::parse"), "{output}");
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt::Write;
+
+use crate::framing::{find_crlf, read_length, CRLF};
+use crate::RawFrames;
+
+/// Bytes shown on each row of a dump.
+const ROW_LEN: usize = 16;
+
+/// What a span of bytes is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Part {
+    Type,
+    Length,
+    Value,
+    Crlf,
+    Payload,
+    Inline,
+    Unparsed,
+}
+
+/// A span of bytes and what it means.
+pub(crate) struct Token {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) part: Part,
+}
+
+impl Token {
+    fn note(&self, data: &[u8]) -> String {
+        match self.part {
+            Part::Type => format!("type: {}", kind_name(data[self.start]).unwrap_or_default()),
+            Part::Length => "length".to_owned(),
+            Part::Value => "value".to_owned(),
+            Part::Crlf => "CRLF".to_owned(),
+            Part::Payload => format!("payload, {} bytes", self.end - self.start),
+            Part::Inline => "inline command".to_owned(),
+            Part::Unparsed => "unparsed".to_owned(),
+        }
+    }
+}
+
+fn kind_name(kind: u8) -> Option<&'static str> {
+    Some(match kind as char {
+        crate::SIMPLE_STRING => "simple string",
+        crate::SIMPLE_ERROR => "simple error",
+        crate::INTEGER => "integer",
+        crate::BULK_STRING => "bulk string",
+        crate::ARRAY => "array",
+        crate::NULL => "null",
+        crate::BOOLEAN => "boolean",
+        crate::DOUBLE => "double",
+        crate::BIG_NUMBER => "big number",
+        crate::BULK_ERROR => "bulk error",
+        crate::VERBATIM_STRING => "verbatim string",
+        crate::MAP => "map",
+        crate::SET => "set",
+        crate::PUSH => "push",
+        crate::ATTRIBUTE => "attribute",
+        _ => return None,
+    })
+}
+
+/// Splits `data` into tokens, best effort, covering whatever can't be made
+/// sense of with a last unparsed token.
+pub(crate) fn tokenize(data: &[u8], offset: usize) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut push = |start: usize, end: usize, part: Part| {
+        tokens.push(Token {
+            start: offset + start,
+            end: offset + end,
+            part,
+        })
+    };
+    let mut position = 0;
+
+    while position < data.len() {
+        let kind = data[position];
+        if kind_name(kind).is_none() {
+            let end = data[position..]
+                .iter()
+                .position(|&x| x == b'\n')
+                .map_or(data.len(), |x| position + x + 1);
+            push(position, end, Part::Inline);
+            position = end;
+            continue;
+        }
+        let Some(end) = find_crlf(data, position + 1) else {
+            break;
+        };
+        push(position, position + 1, Part::Type);
+
+        let payload = match kind as char {
+            crate::BULK_STRING | crate::BULK_ERROR | crate::VERBATIM_STRING => {
+                push(position + 1, end, Part::Length);
+                read_length(data, position + 1).ok().flatten()
+            }
+            crate::ARRAY | crate::MAP | crate::SET | crate::PUSH | crate::ATTRIBUTE => {
+                push(position + 1, end, Part::Length);
+                None
+            }
+            _ => {
+                push(position + 1, end, Part::Value);
+                None
+            }
+        };
+        push(end, end + CRLF.len(), Part::Crlf);
+        position = end + CRLF.len();
+
+        if let Some((length @ 0.., _)) = payload {
+            let length = length as usize;
+            if data.len() - position < length {
+                break;
+            }
+            push(position, position + length, Part::Payload);
+            position += length;
+            if !data[position..].starts_with(CRLF) {
+                break;
+            }
+            push(position, position + CRLF.len(), Part::Crlf);
+            position += CRLF.len();
+        }
+    }
+
+    if position < data.len() {
+        push(position, data.len(), Part::Unparsed);
+    }
+    tokens
+}
+
+fn write_rows(output: &mut String, data: &[u8], token: &Token) {
+    for (index, row) in data[token.start..token.end].chunks(ROW_LEN).enumerate() {
+        let hex = row.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x} ");
+            hex
+        });
+        let ascii = row
+            .iter()
+            .map(|&x| {
+                if x.is_ascii_graphic() || x == b' ' {
+                    x as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        let note = if index == 0 {
+            token.note(data)
+        } else {
+            String::new()
+        };
+        let line = format!(
+            "{:08x}  {hex:<width$} {ascii:<ROW_LEN$}  {note}",
+            token.start + index * ROW_LEN,
+            width = ROW_LEN * 3,
+        );
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+}
+
+/// Renders `data` as a hex dump annotated with frame boundaries, type bytes,
+/// length headers, payloads and CRLFs, handy for bug reports about input
+/// that doesn't parse.
+pub fn hex_dump(data: &[u8]) -> String {
+    let mut output = String::new();
+    let mut frames = RawFrames::new(data);
+    let mut index = 0;
+
+    loop {
+        let offset = data.len() - frames.remaining().len();
+        let Some(frame) = frames.next() else {
+            break;
+        };
+        let (tokens, error) = match frame {
+            Ok(frame) => {
+                let _ = writeln!(output, "-- frame {index}, {} bytes", frame.len());
+                (tokenize(frame, offset), None)
+            }
+            Err(err) => {
+                let _ = writeln!(output, "-- invalid frame {index}");
+                (tokenize(&data[offset..], offset), Some(err))
+            }
+        };
+        for token in &tokens {
+            write_rows(&mut output, data, token);
+        }
+        if let Some(err) = error {
+            let _ = writeln!(output, "-- {err}");
+        }
+        index += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_frames() {
+        let dump = hex_dump(b"*1\r\n$5\r\nHello\r\n:1\r\n");
+        let lines = dump.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "-- frame 0, 15 bytes");
+        assert!(lines[1].starts_with("00000000  2a "));
+        assert!(lines[1].ends_with("type: array"));
+        assert!(lines[2].ends_with("length"));
+        assert!(lines[3].ends_with("CRLF"));
+        assert!(lines[5].starts_with("00000005  35 "));
+        assert!(lines[5].ends_with("length"));
+        assert!(lines[7].starts_with("00000008  48 65 6c 6c 6f "));
+        assert!(lines[7].ends_with("payload, 5 bytes"));
+        assert_eq!(lines[9], "-- frame 1, 4 bytes");
+        assert!(lines[11].ends_with("value"));
+    }
+
+    #[test]
+    fn dump_long_payload() {
+        let data = format!("${}\r\n{}\r\n", 40, "x".repeat(40));
+        let dump = hex_dump(data.as_bytes());
+        let rows = dump
+            .lines()
+            .filter(|x| x.contains("78 78"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].ends_with("payload, 40 bytes"));
+        assert!(rows[1].starts_with("00000015  "));
+    }
+
+    #[test]
+    fn dump_invalid() {
+        let dump = hex_dump(b"+OK\r\n$5\r\nHi\r\n");
+        assert!(dump.contains("-- invalid frame 1"));
+        assert!(dump.lines().any(|x| x.ends_with("unparsed")));
+        assert!(dump.ends_with("-- expected rest of the frame, found end of input\n"));
+    }
+}
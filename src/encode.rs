@@ -0,0 +1,1205 @@
+//! Serialization of [`RESP`] values back into the wire format.
+
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "heapless")]
+pub use resp_parser_rs_core::command::encode_command_heapless;
+pub use resp_parser_rs_core::command::{command_len, encode_command_into, BufferTooSmall};
+
+use crate::framing::write_len_header;
+use crate::{EncodeError, Protocol, RESP};
+
+fn write_header<W: Write>(writer: &mut W, kind: char, length: usize) -> io::Result<()> {
+    write_len_header(writer, kind as u8, Some(length))
+}
+
+#[cfg(feature = "resp3")]
+fn write_pairs<W: Write>(writer: &mut W, kind: char, pairs: &[(RESP, RESP)]) -> io::Result<()> {
+    write_header(writer, kind, pairs.len())?;
+    for (key, value) in pairs {
+        key.write_value(writer)?;
+        value.write_value(writer)?;
+    }
+    Ok(())
+}
+
+fn write_elements<W: Write>(writer: &mut W, kind: char, elements: &[RESP]) -> io::Result<()> {
+    write_header(writer, kind, elements.len())?;
+    for element in elements {
+        element.write_value(writer)?;
+    }
+    Ok(())
+}
+
+/// Writer counting the bytes written to it.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0 += data.len();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes a bulk string of `length` bytes whose payload is copied in chunks
+/// from `reader`, so large values never have to be fully loaded in memory.
+///
+/// Fails with [`io::ErrorKind::UnexpectedEof`] if `reader` ends early.
+pub fn write_bulk_from_reader<W: Write, R: Read>(
+    writer: &mut W,
+    length: u64,
+    reader: R,
+) -> io::Result<()> {
+    write!(writer, "${length}\r\n")?;
+    let copied = io::copy(&mut reader.take(length), writer)?;
+    if copied != length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {length} bytes from reader, got {copied}"),
+        ));
+    }
+    writer.write_all(b"\r\n")
+}
+
+/// Text formatted on the stack, for the shortest digits of doubles.
+struct StackText {
+    data: [u8; 32],
+    len: usize,
+}
+
+impl std::fmt::Write for StackText {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        let end = self.len + text.len();
+        self.data
+            .get_mut(self.len..end)
+            .ok_or(std::fmt::Error)?
+            .copy_from_slice(text.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn write_zeros<W: Write>(writer: &mut W, count: usize) -> io::Result<()> {
+    const ZEROS: &[u8] = b"0000000000000000";
+    let mut left = count;
+    while left > 0 {
+        let chunk = left.min(ZEROS.len());
+        writer.write_all(&ZEROS[..chunk])?;
+        left -= chunk;
+    }
+    Ok(())
+}
+
+/// Writes a double like [`format_double`], without allocating.
+pub(crate) fn write_double<W: Write>(writer: &mut W, value: f64) -> io::Result<()> {
+    if value.is_nan() {
+        return writer.write_all(b"nan");
+    }
+    if value.is_infinite() || value == 0.0 {
+        // Also keeps the sign of -0
+        return write!(writer, "{value}");
+    }
+    // Redis prints integral values within ±2^62 as integers
+    let limit = (i64::MAX / 2) as f64;
+    if value.fract() == 0.0 && (-limit..=limit).contains(&value) {
+        return write!(writer, "{}", value as i64);
+    }
+    if value < 0.0 {
+        writer.write_all(b"-")?;
+    }
+    // Shortest round-trip digits, as `d.ddde±x`
+    let mut scientific = StackText {
+        data: [0; 32],
+        len: 0,
+    };
+    std::fmt::Write::write_fmt(&mut scientific, format_args!("{:e}", value.abs()))
+        .map_err(|_| io::Error::other("double too long"))?;
+    let scientific = &scientific.data[..scientific.len];
+    let split = scientific
+        .iter()
+        .position(|&x| x == b'e')
+        .unwrap_or(scientific.len());
+    let (mantissa, exponent) = (&scientific[..split], &scientific[split + 1..]);
+    let exponent: i32 = std::str::from_utf8(exponent)
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_default();
+    let mut digits = [0; 32];
+    let count = match mantissa {
+        [first, b'.', rest @ ..] => {
+            digits[0] = *first;
+            digits[1..=rest.len()].copy_from_slice(rest);
+            rest.len() + 1
+        }
+        _ => {
+            digits[..mantissa.len()].copy_from_slice(mantissa);
+            mantissa.len()
+        }
+    };
+    let digits = &digits[..count];
+    let count = count as i32;
+    // The value is `digits` times 10 to the power of `scale`
+    let scale = exponent - (count - 1);
+
+    if scale >= 0 && exponent.abs() < count + 7 {
+        writer.write_all(digits)?;
+        write_zeros(writer, scale as usize)
+    } else if scale < 0 && (scale > -7 || exponent.abs() < 4) {
+        let point = count + scale;
+        if point <= 0 {
+            writer.write_all(b"0.")?;
+            write_zeros(writer, -point as usize)?;
+            writer.write_all(digits)
+        } else {
+            let (integer, fraction) = digits.split_at(point as usize);
+            writer.write_all(integer)?;
+            writer.write_all(b".")?;
+            writer.write_all(fraction)
+        }
+    } else {
+        let (first, rest) = digits.split_at(1);
+        writer.write_all(first)?;
+        if !rest.is_empty() {
+            writer.write_all(b".")?;
+            writer.write_all(rest)?;
+        }
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        write!(writer, "e{exponent_sign}{}", exponent.abs())
+    }
+}
+
+/// Formats a double the way Redis replies with it: integral values up to
+/// 2^62 as integers, otherwise the shortest digits that round-trip, with
+/// scientific notation such as `1e+21` outside of the range written plainly.
+///
+/// Redis lays out digits like this since 7.2, in `d2string` and
+/// `fpconv_dtoa`, finding them with Grisu2, which in rare cases yields a
+/// digit more than the shortest used here.
+pub fn format_double(value: f64) -> String {
+    let mut text = Vec::new();
+    // Writing to a vector never fails
+    let _ = write_double(&mut text, value);
+    String::from_utf8(text).unwrap_or_default()
+}
+
+/// Text that fits on a single line.
+fn is_line(text: &str) -> bool {
+    !text.contains(['\r', '\n'])
+}
+
+#[cfg(feature = "resp3")]
+fn is_big_number(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|x| x.is_ascii_digit())
+}
+
+/// Why `value` can't be encoded on its own, ignoring its elements.
+#[cfg_attr(not(feature = "resp3"), allow(unused_variables))]
+fn check(value: &RESP, internal: bool) -> Option<&'static str> {
+    match value {
+        RESP::SimpleString(x) | RESP::SimpleError(x) if !is_line(x) => {
+            Some("text without CR or LF")
+        }
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(x) if !is_big_number(x) => Some("digits with an optional sign"),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { encoding, .. }
+            if encoding.len() != 3 || !is_line(encoding) || encoding.contains(':') =>
+        {
+            Some("3 byte encoding without ':'")
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Push(_) if internal => Some("push outside of aggregates"),
+        RESP::Inline(x)
+            if x.is_empty()
+                || x.iter()
+                    .any(|x| x.is_empty() || x.contains(char::is_whitespace)) =>
+        {
+            Some("non-empty arguments without whitespace")
+        }
+        _ => None,
+    }
+}
+
+/// Writes elements of unknown count as they are produced, with the RESP3
+/// streamed aggregate syntax (`*?` … `.`), or by collecting and counting them
+/// first in RESP2. Returns the amount of elements written.
+///
+/// The parser reads streamed aggregates back as regular ones.
+///
+/// In RESP3 an invalid element fails the write after the previous ones were
+/// sent, leaving the aggregate unterminated, so the connection should then
+/// be dropped.
+pub fn write_streamed_array<W, I>(
+    writer: &mut W,
+    elements: I,
+    protocol: Protocol,
+) -> Result<usize, EncodeError>
+where
+    W: Write,
+    I: IntoIterator<Item = RESP>,
+{
+    match protocol {
+        Protocol::Resp2 => {
+            let elements = elements.into_iter().collect::<Vec<_>>();
+            for element in &elements {
+                element.validate_nested(true)?;
+            }
+            write_elements(writer, '*', &elements)?;
+            Ok(elements.len())
+        }
+        Protocol::Resp3 => {
+            writer.write_all(b"*?\r\n")?;
+            let mut count = 0;
+            for element in elements {
+                element.validate_nested(true)?;
+                element.write_value(writer)?;
+                count += 1;
+            }
+            writer.write_all(b".\r\n")?;
+            Ok(count)
+        }
+    }
+}
+
+/// Like [`write_streamed_array`] for maps, which are written as flat arrays
+/// of keys and values in RESP2. Returns the amount of entries written.
+pub fn write_streamed_map<W, I>(
+    writer: &mut W,
+    entries: I,
+    protocol: Protocol,
+) -> Result<usize, EncodeError>
+where
+    W: Write,
+    I: IntoIterator<Item = (RESP, RESP)>,
+{
+    match protocol {
+        Protocol::Resp2 => {
+            let entries = entries.into_iter().collect::<Vec<_>>();
+            for (key, value) in &entries {
+                key.validate_nested(true)?;
+                value.validate_nested(true)?;
+            }
+            write_header(writer, '*', entries.len() * 2)?;
+            for (key, value) in &entries {
+                key.write_value(writer)?;
+                value.write_value(writer)?;
+            }
+            Ok(entries.len())
+        }
+        Protocol::Resp3 => {
+            writer.write_all(b"%?\r\n")?;
+            let mut count = 0;
+            for (key, value) in entries {
+                key.validate_nested(true)?;
+                value.validate_nested(true)?;
+                key.write_value(writer)?;
+                value.write_value(writer)?;
+                count += 1;
+            }
+            writer.write_all(b".\r\n")?;
+            Ok(count)
+        }
+    }
+}
+
+/// Builds `EVAL` and `EVALSHA` commands, writing `numkeys` from the keys
+/// given so it can't get out of sync with them.
+///
+/// ```
+/// use resp_parser_rs::encode::Eval;
+///
+/// let frame = Eval::script("return redis.call('GET', KEYS[1])")
+///     .key("user:1")
+///     .encode();
+/// assert!(frame.ends_with(b"$1\r\n1\r\n$6\r\nuser:1\r\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eval {
+    command: &'static str,
+    script: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+impl Eval {
+    /// `EVAL` with the source of a script.
+    pub fn script(source: impl Into<String>) -> Self {
+        Self {
+            command: "EVAL",
+            script: source.into(),
+            keys: Vec::new(),
+            args: Vec::new(),
+        }
+    }
+
+    /// `EVALSHA` with the SHA1 digest of a loaded script.
+    pub fn sha(sha: impl Into<String>) -> Self {
+        Self {
+            command: "EVALSHA",
+            ..Self::script(sha)
+        }
+    }
+
+    /// Uses `EVAL_RO` or `EVALSHA_RO`, which can run on replicas.
+    pub fn read_only(mut self) -> Self {
+        self.command = match self.command {
+            "EVAL" => "EVAL_RO",
+            _ => "EVALSHA_RO",
+        };
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    pub fn keys<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// The command as an array of bulk strings.
+    pub fn to_resp(&self) -> RESP {
+        let mut elements = Vec::with_capacity(3 + self.keys.len() + self.args.len());
+        elements.push(RESP::BulkString(self.command.to_owned()));
+        elements.push(RESP::BulkString(self.script.clone()));
+        elements.push(RESP::BulkString(self.keys.len().to_string()));
+        elements.extend(self.keys.iter().cloned().map(RESP::BulkString));
+        elements.extend(self.args.iter().cloned().map(RESP::BulkString));
+        RESP::Array(elements)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_resp()
+            .encode()
+            .expect("arrays of bulk strings are always valid")
+    }
+}
+
+/// Builds the `HELLO` command opening a connection, which picks the protocol
+/// and can authenticate and name the client in the same round trip.
+///
+/// ```
+/// use resp_parser_rs::encode::Hello;
+/// use resp_parser_rs::Protocol;
+///
+/// let frame = Hello::new(Protocol::Resp3)
+///     .auth("default", "secret")
+///     .client_name("worker-1")
+///     .encode();
+/// assert!(frame.starts_with(b"*7\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$4\r\nAUTH\r\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hello {
+    protocol: Protocol,
+    auth: Option<(String, String)>,
+    client_name: Option<String>,
+}
+
+impl Hello {
+    pub fn new(protocol: Protocol) -> Self {
+        Self {
+            protocol,
+            auth: None,
+            client_name: None,
+        }
+    }
+
+    /// Authenticates as `username`, `default` being the user servers
+    /// without ACLs have.
+    pub fn auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Names the connection, as `CLIENT SETNAME` does.
+    pub fn client_name(mut self, name: impl Into<String>) -> Self {
+        self.client_name = Some(name.into());
+        self
+    }
+
+    /// The `HELLO` command as an array of bulk strings.
+    pub fn to_resp(&self) -> RESP {
+        let version = match self.protocol {
+            Protocol::Resp2 => "2",
+            Protocol::Resp3 => "3",
+        };
+        let mut arguments = vec!["HELLO", version];
+        if let Some((username, password)) = &self.auth {
+            arguments.extend(["AUTH", username, password]);
+        }
+        if let Some(name) = &self.client_name {
+            arguments.extend(["SETNAME", name]);
+        }
+        bulk_strings(&arguments)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_resp()
+            .encode()
+            .expect("arrays of bulk strings are always valid")
+    }
+
+    /// Commands doing the same on servers older than Redis 6, which reply
+    /// to `HELLO` with an error and only speak RESP2: `AUTH`, with just the
+    /// password for the `default` user, then `CLIENT SETNAME`.
+    pub fn fallback(&self) -> Vec<RESP> {
+        let mut commands = Vec::new();
+        match &self.auth {
+            Some((username, password)) if username == "default" => {
+                commands.push(bulk_strings(&["AUTH", password]));
+            }
+            Some((username, password)) => {
+                commands.push(bulk_strings(&["AUTH", username, password]));
+            }
+            None => {}
+        }
+        if let Some(name) = &self.client_name {
+            commands.push(bulk_strings(&["CLIENT", "SETNAME", name]));
+        }
+        commands
+    }
+}
+
+fn bulk_strings(arguments: &[&str]) -> RESP {
+    RESP::Array(
+        arguments
+            .iter()
+            .map(|x| RESP::BulkString((*x).to_owned()))
+            .collect(),
+    )
+}
+
+impl RESP {
+    /// Checks that encoding this value produces a valid stream, which isn't
+    /// the case for e.g. simple strings holding line breaks.
+    pub fn validate(&self) -> Result<(), EncodeError> {
+        self.validate_nested(false)
+    }
+
+    /// Recurses like [`write_value`](Self::write_value) does, so validating
+    /// doesn't allocate.
+    fn validate_nested(&self, internal: bool) -> Result<(), EncodeError> {
+        if let Some(expected) = check(self, internal) {
+            return Err(EncodeError::invalid(expected, self));
+        }
+        #[cfg(feature = "resp3")]
+        let validate_pairs = |pairs: &[(RESP, RESP)]| {
+            pairs.iter().try_for_each(|(key, value)| {
+                key.validate_nested(true)?;
+                value.validate_nested(true)
+            })
+        };
+        match self {
+            Self::Array(x) => x.iter().try_for_each(|x| x.validate_nested(true)),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) | Self::Push(x) => x.iter().try_for_each(|x| x.validate_nested(true)),
+            #[cfg(feature = "resp3")]
+            Self::Map(x) => validate_pairs(x),
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { attributes, value } => {
+                validate_pairs(attributes)?;
+                value.validate_nested(internal)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the value then writes it, so nothing is written when it's
+    /// invalid.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        #[cfg(feature = "metrics")]
+        let writer = &mut crate::telemetry::Counted { writer, written: 0 };
+        match crate::interned::encoded(self) {
+            Some(encoded) => writer.write_all(encoded)?,
+            None => {
+                self.validate()?;
+                self.write_value(writer)?;
+            }
+        }
+        #[cfg(feature = "metrics")]
+        crate::telemetry::frame_encoded(writer.written);
+        Ok(())
+    }
+
+    pub(crate) fn write_value<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::SimpleString(x) => write!(writer, "+{x}\r\n"),
+            Self::SimpleError(x) => write!(writer, "-{x}\r\n"),
+            Self::Integer(x) => write!(writer, ":{x}\r\n"),
+            Self::BulkString(x) => write!(writer, "${}\r\n{x}\r\n", x.len()),
+            Self::NullBulkString => write!(writer, "$-1\r\n"),
+            Self::Array(x) => write_elements(writer, '*', x),
+            Self::NullArray => write!(writer, "*-1\r\n"),
+            #[cfg(feature = "resp3")]
+            Self::Null => write!(writer, "_\r\n"),
+            #[cfg(feature = "resp3")]
+            Self::Boolean(x) => write!(writer, "#{}\r\n", if *x { 't' } else { 'f' }),
+            #[cfg(feature = "resp3")]
+            Self::Double(x) => {
+                writer.write_all(b",")?;
+                write_double(writer, *x)?;
+                writer.write_all(b"\r\n")
+            }
+            #[cfg(feature = "rust_decimal")]
+            Self::Decimal(x) => write!(writer, ",{x}\r\n"),
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(x) => write!(writer, "({x}\r\n"),
+            #[cfg(feature = "resp3")]
+            Self::BulkError(x) => write!(writer, "!{}\r\n{x}\r\n", x.len()),
+            #[cfg(feature = "resp3")]
+            Self::VerbatimString { encoding, data } => write!(
+                writer,
+                "={}\r\n{encoding}:{data}\r\n",
+                encoding.len() + data.len() + 1
+            ),
+            #[cfg(feature = "resp3")]
+            Self::Map(x) => write_pairs(writer, '%', x),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) => write_elements(writer, '~', x),
+            #[cfg(feature = "resp3")]
+            Self::Push(x) => write_elements(writer, '>', x),
+            Self::Inline(x) => {
+                for (index, argument) in x.iter().enumerate() {
+                    if index > 0 {
+                        writer.write_all(b" ")?;
+                    }
+                    writer.write_all(argument.as_bytes())?;
+                }
+                writer.write_all(b"\r\n")
+            }
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { attributes, value } => {
+                write_pairs(writer, '|', attributes)?;
+                value.write_value(writer)
+            }
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut data = Vec::with_capacity(self.encoded_len());
+        self.write_to(&mut data)?;
+        Ok(data)
+    }
+
+    /// Size of the encoded value in bytes, computed without allocating.
+    pub fn encoded_len(&self) -> usize {
+        let mut counter = Counter(0);
+        // Counting never fails
+        let _ = self.write_value(&mut counter);
+        counter.0
+    }
+
+    /// Encodes the value into `buffer` without allocating, returning the
+    /// amount of bytes written. Nothing is written when the value doesn't
+    /// fit, the error telling how large the buffer has to be.
+    pub fn encode_into(&self, buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        self.validate()?;
+        let required = self.encoded_len();
+        if required > buffer.len() {
+            return Err(EncodeError::BufferTooSmall {
+                required,
+                available: buffer.len(),
+            });
+        }
+        self.write_value(&mut &mut buffer[..required])?;
+        Ok(required)
+    }
+
+    /// Appends the encoded value to a fixed capacity vector without
+    /// allocating while encoding. Nothing is appended when it doesn't fit.
+    ///
+    /// The value itself is built on the heap. Without an allocator, use
+    /// [`encode_command_heapless`] with borrowed arguments instead.
+    #[cfg(feature = "heapless")]
+    pub fn encode_heapless<const N: usize>(
+        &self,
+        buffer: &mut heapless::Vec<u8, N>,
+    ) -> Result<usize, EncodeError> {
+        let start = buffer.len();
+        let required = self.encoded_len();
+        // Leaves the vector untouched when it's too small
+        if buffer.resize(start + required, 0).is_err() {
+            return Err(EncodeError::BufferTooSmall {
+                required,
+                available: N - start,
+            });
+        }
+        self.encode_into(&mut buffer[start..]).inspect_err(|_| {
+            buffer.truncate(start);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Allocator counting the allocations of threads that enabled it.
+    struct CountingAllocator;
+
+    thread_local! {
+        static COUNTING: Cell<bool> = const { Cell::new(false) };
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            if COUNTING.try_with(Cell::get).unwrap_or(false) {
+                ALLOCATIONS.with(|x| x.set(x.get() + 1));
+            }
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Allocations made by `run` on this thread.
+    fn allocations(run: impl FnOnce()) -> usize {
+        ALLOCATIONS.with(|x| x.set(0));
+        COUNTING.with(|x| x.set(true));
+        run();
+        COUNTING.with(|x| x.set(false));
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    /// Doubles and how Redis 7.2 replies with them, following `d2string`
+    /// and `fpconv_dtoa`.
+    #[cfg(feature = "resp3")]
+    const REDIS_DOUBLES: &[(f64, &str)] = &[
+        (1.0, "1"),
+        (-2.0, "-2"),
+        (0.0, "0"),
+        (-0.0, "-0"),
+        (1.5, "1.5"),
+        (-1.5, "-1.5"),
+        (42.42, "42.42"),
+        (0.1, "0.1"),
+        (0.001, "0.001"),
+        (1e-5, "0.00001"),
+        (1.5e-5, "0.000015"),
+        (1e-6, "0.000001"),
+        (1e-7, "1e-7"),
+        (-1.5e-10, "-1.5e-10"),
+        (123.456, "123.456"),
+        (123456789.0, "123456789"),
+        (1e8, "100000000"),
+        (1.2345678e15, "1234567800000000"),
+        (1e17, "100000000000000000"),
+        (1.5e17, "150000000000000000"),
+        (4611686018427387904.0, "4611686018427387904"),
+        (-4611686018427387904.0, "-4611686018427387904"),
+        // Past 2^62 values are laid out from their shortest digits
+        (9223372036854775808.0, "9223372036854776000"),
+        (1e19, "1e+19"),
+        (1e21, "1e+21"),
+        (1.5e300, "1.5e+300"),
+        (1.7976931348623157e308, "1.7976931348623157e+308"),
+        (0.30000000000000004, "0.30000000000000004"),
+        (2.2250738585072014e-308, "2.2250738585072014e-308"),
+        // Subnormals
+        (1e-310, "1e-310"),
+        (5e-324, "5e-324"),
+    ];
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn double_like_redis() {
+        for &(value, expected) in REDIS_DOUBLES {
+            assert_eq!(format_double(value), expected, "{value:?}");
+            assert_eq!(expected.parse::<f64>().unwrap().to_bits(), value.to_bits());
+        }
+        assert_eq!(format_double(f64::INFINITY), "inf");
+        assert_eq!(format_double(f64::NEG_INFINITY), "-inf");
+        assert_eq!(format_double(f64::NAN), "nan");
+        assert_eq!(RESP::Double(1e21).encode().unwrap(), b",1e+21\r\n");
+    }
+
+    /// Checks the vectors above against a live server, whose address is
+    /// given in `RESP_TEST_REDIS`, like `127.0.0.1:6379`.
+    #[cfg(feature = "resp3")]
+    #[test]
+    #[ignore = "needs a Redis server, set RESP_TEST_REDIS"]
+    fn double_from_redis_server() {
+        use std::net::TcpStream;
+
+        let address = std::env::var("RESP_TEST_REDIS").expect("RESP_TEST_REDIS is set");
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut received = Vec::new();
+        let mut request = |arguments: &[&str]| {
+            let command = RESP::Array(
+                arguments
+                    .iter()
+                    .map(|x| RESP::BulkString((*x).to_owned()))
+                    .collect(),
+            );
+            command.write_to(&mut stream).unwrap();
+            loop {
+                if let Some(length) = crate::framing::frame_len(&received).unwrap() {
+                    return received.drain(..length).collect::<Vec<_>>();
+                }
+                let mut chunk = [0; 4096];
+                let read = stream.read(&mut chunk).unwrap();
+                assert!(read > 0, "connection closed");
+                received.extend_from_slice(&chunk[..read]);
+            }
+        };
+        request(&["HELLO", "3"]);
+        let script = "redis.setresp(3) return {double = tonumber(ARGV[1])}";
+        for &(value, expected) in REDIS_DOUBLES {
+            let reply = request(&["EVAL", script, "0", &format!("{value:?}")]);
+            assert_eq!(
+                String::from_utf8_lossy(&reply),
+                format!(",{expected}\r\n"),
+                "{value:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    fn round_trip(data: &str) {
+        let parsed = RESP::parse(data).unwrap();
+        assert_eq!(String::from_utf8(parsed.encode().unwrap()).unwrap(), data);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_simple() {
+        round_trip("+Hello\r\n");
+        round_trip("-ERR Hello\r\n");
+        round_trip(":-123\r\n");
+        round_trip("_\r\n");
+        round_trip("#t\r\n");
+        round_trip("#f\r\n");
+        round_trip("(-12345678901234567890\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_double() {
+        round_trip(",1.23\r\n");
+        round_trip(",-1.5\r\n");
+        round_trip(",inf\r\n");
+        round_trip(",-inf\r\n");
+        round_trip(",nan\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_bulk() {
+        round_trip("$5\r\nHello\r\n");
+        round_trip("$0\r\n\r\n");
+        round_trip("$-1\r\n");
+        round_trip("!5\r\nHello\r\n");
+        round_trip("=9\r\ntxt:Hello\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_aggregate() {
+        round_trip("*3\r\n+Hello\r\n-World\r\n:123\r\n");
+        round_trip("*-1\r\n");
+        round_trip("*0\r\n");
+        round_trip("%1\r\n+key\r\n*1\r\n$5\r\nvalue\r\n");
+        round_trip("~2\r\n:1\r\n:2\r\n");
+        round_trip(">2\r\n+message\r\n$5\r\nHello\r\n");
+        round_trip("|1\r\n+ttl\r\n:3600\r\n$5\r\nHello\r\n");
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn encode_inline() {
+        let parsed = RESP::parse("ECHO  hello world").unwrap();
+        assert_eq!(parsed.encode().unwrap(), b"ECHO hello world\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_invalid() {
+        let invalid = [
+            RESP::SimpleString("OK\r\n+PWNED".into()),
+            RESP::SimpleError("ERR\n".into()),
+            RESP::BigNumber("12a".to_owned()),
+            RESP::BigNumber("-".to_owned()),
+            RESP::VerbatimString {
+                encoding: "text".into(),
+                data: "Hello".to_owned(),
+            },
+            RESP::Inline(vec!["SET".to_owned(), "a b".to_owned()]),
+            RESP::Array(vec![RESP::Integer(1), RESP::Push(Vec::new())]),
+            RESP::Map(vec![(RESP::SimpleString("a\rb".into()), RESP::Null)]),
+        ];
+        for value in invalid {
+            let mut data = Vec::new();
+            let result = value.write_to(&mut data);
+            assert!(
+                matches!(result, Err(EncodeError::Invalid { .. })),
+                "{value:?}"
+            );
+            assert!(data.is_empty());
+        }
+
+        let err = RESP::SimpleString("a\nb".into()).encode().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't encode simple string \"a\\nb\", expected text without CR or LF"
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn streamed_array() {
+        let elements = (0..3).map(RESP::Integer);
+        let mut data = Vec::new();
+        let count = write_streamed_array(&mut data, elements.clone(), Protocol::Resp3).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(data, b"*?\r\n:0\r\n:1\r\n:2\r\n.\r\n");
+        assert_eq!(
+            RESP::parse(std::str::from_utf8(&data).unwrap()),
+            Some(RESP::Array(elements.clone().collect()))
+        );
+
+        let mut data = Vec::new();
+        write_streamed_array(&mut data, elements, Protocol::Resp2).unwrap();
+        assert_eq!(data, b"*3\r\n:0\r\n:1\r\n:2\r\n");
+
+        let mut data = Vec::new();
+        let result = write_streamed_array(&mut data, [RESP::Push(Vec::new())], Protocol::Resp2);
+        assert!(matches!(result, Err(EncodeError::Invalid { .. })));
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn streamed_map() {
+        let entries = || [(RESP::SimpleString("a".into()), RESP::Integer(1))];
+        let mut data = Vec::new();
+        write_streamed_map(&mut data, entries(), Protocol::Resp3).unwrap();
+        assert_eq!(data, b"%?\r\n+a\r\n:1\r\n.\r\n");
+        #[cfg(feature = "resp3")]
+        assert_eq!(
+            RESP::parse(std::str::from_utf8(&data).unwrap()),
+            Some(RESP::Map(entries().into()))
+        );
+
+        let mut data = Vec::new();
+        let count = write_streamed_map(&mut data, entries(), Protocol::Resp2).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(data, b"*2\r\n+a\r\n:1\r\n");
+    }
+
+    #[test]
+    fn bulk_from_reader() {
+        let mut data = Vec::new();
+        write_bulk_from_reader(&mut data, 5, &b"Hello world"[..]).unwrap();
+        assert_eq!(data, b"$5\r\nHello\r\n");
+
+        let payload = vec![b'x'; 100_000];
+        let mut data = Vec::new();
+        write_bulk_from_reader(&mut data, payload.len() as u64, &payload[..]).unwrap();
+        let parsed = RESP::parse(std::str::from_utf8(&data).unwrap());
+        assert!(matches!(parsed, Some(RESP::BulkString(_))));
+        if let Some(RESP::BulkString(x)) = parsed {
+            assert_eq!(x.len(), payload.len());
+        }
+    }
+
+    #[test]
+    fn bulk_from_short_reader() {
+        let mut data = Vec::new();
+        let result = write_bulk_from_reader(&mut data, 10, &b"Hello"[..]);
+        assert!(matches!(result, Err(x) if x.kind() == io::ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn eval_numkeys() {
+        let bulk = |x: &str| RESP::BulkString(x.to_owned());
+        assert_eq!(
+            Eval::script("return 1").to_resp(),
+            RESP::Array(vec![bulk("EVAL"), bulk("return 1"), bulk("0")])
+        );
+        assert_eq!(
+            Eval::sha("e0e1f9fabfc9d4800c877a703b823ac0578ff831")
+                .read_only()
+                .keys(["a", "b"])
+                .arg("1")
+                .key("c")
+                .to_resp(),
+            RESP::Array(vec![
+                bulk("EVALSHA_RO"),
+                bulk("e0e1f9fabfc9d4800c877a703b823ac0578ff831"),
+                bulk("3"),
+                bulk("a"),
+                bulk("b"),
+                bulk("c"),
+                bulk("1"),
+            ])
+        );
+        assert_eq!(
+            Eval::script("return 1").arg("x").encode(),
+            b"*4\r\n$4\r\nEVAL\r\n$8\r\nreturn 1\r\n$1\r\n0\r\n$1\r\nx\r\n"
+        );
+    }
+
+    #[test]
+    fn hello_handshake() {
+        let bulk = |x: &str| RESP::BulkString(x.to_owned());
+        assert_eq!(
+            Hello::new(Protocol::Resp2).to_resp(),
+            RESP::Array(vec![bulk("HELLO"), bulk("2")])
+        );
+        let hello = Hello::new(Protocol::Resp3)
+            .auth("app", "secret")
+            .client_name("worker");
+        assert_eq!(
+            hello.to_resp(),
+            RESP::Array(vec![
+                bulk("HELLO"),
+                bulk("3"),
+                bulk("AUTH"),
+                bulk("app"),
+                bulk("secret"),
+                bulk("SETNAME"),
+                bulk("worker"),
+            ])
+        );
+        assert_eq!(
+            hello.fallback(),
+            [
+                RESP::Array(vec![bulk("AUTH"), bulk("app"), bulk("secret")]),
+                RESP::Array(vec![bulk("CLIENT"), bulk("SETNAME"), bulk("worker")]),
+            ]
+        );
+        let hello = Hello::new(Protocol::Resp2).auth("default", "secret");
+        assert_eq!(
+            hello.fallback(),
+            [RESP::Array(vec![bulk("AUTH"), bulk("secret")])]
+        );
+        assert!(Hello::new(Protocol::Resp3).fallback().is_empty());
+    }
+
+    /// `ZADD` with some of its options.
+    #[cfg(feature = "derive")]
+    #[derive(Debug, Default, crate::ToCommand)]
+    #[command(name = "ZADD")]
+    struct ZAdd {
+        key: String,
+        #[command(flag = "XX")]
+        existing: bool,
+        #[command(token = "LIMIT")]
+        limit: Option<usize>,
+        #[command(optional)]
+        score: Option<f64>,
+        #[command(multiple)]
+        members: Vec<String>,
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(crate::ToCommand)]
+    struct Ping(&'static str);
+
+    #[cfg(feature = "derive")]
+    #[derive(crate::ToCommand)]
+    #[command(name = "CLIENT  SETNAME")]
+    struct ClientSetName {
+        name: String,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_to_command() {
+        use crate::ToResp;
+
+        let bulk = |x: &str| RESP::BulkString(x.to_owned());
+        let command = ZAdd {
+            key: "z".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            command.to_resp(),
+            RESP::Array(vec![bulk("ZADD"), bulk("z")])
+        );
+        let command = ZAdd {
+            key: "z".into(),
+            existing: true,
+            limit: Some(5),
+            score: Some(1.5),
+            members: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(
+            command.to_resp(),
+            RESP::Array(vec![
+                bulk("ZADD"),
+                bulk("z"),
+                bulk("XX"),
+                bulk("LIMIT"),
+                bulk("5"),
+                bulk("1.5"),
+                bulk("a"),
+                bulk("b"),
+            ])
+        );
+        let mut output = Vec::new();
+        crate::to_writer(&mut output, &command).unwrap();
+        assert!(output.starts_with(b"*8\r\n$4\r\nZADD\r\n"));
+        assert_eq!(
+            Ping("hi").to_resp(),
+            RESP::Array(vec![bulk("PING"), bulk("hi")])
+        );
+        let command = ClientSetName { name: "me".into() };
+        assert_eq!(
+            command.to_resp(),
+            RESP::Array(vec![bulk("CLIENT"), bulk("SETNAME"), bulk("me")])
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn encode_into_buffer() {
+        let value = RESP::Array(vec![
+            RESP::BulkString("Hello".to_owned()),
+            RESP::Double(1.5),
+            RESP::Map(vec![(RESP::Integer(1), RESP::Null)]),
+        ]);
+        let encoded = value.encode().unwrap();
+        assert_eq!(value.encoded_len(), encoded.len());
+
+        let mut buffer = [0; 64];
+        assert_eq!(value.encode_into(&mut buffer).unwrap(), encoded.len());
+        assert_eq!(&buffer[..encoded.len()], encoded);
+
+        let mut small = [0; 8];
+        assert!(matches!(
+            value.encode_into(&mut small),
+            Err(EncodeError::BufferTooSmall {
+                required: 32,
+                available: 8
+            })
+        ));
+        assert_eq!(small, [0; 8]);
+    }
+
+    #[test]
+    fn encode_into_without_allocating() {
+        #[cfg_attr(not(feature = "resp3"), allow(unused_mut))]
+        let mut elements = vec![
+            RESP::BulkString("Hello".to_owned()),
+            RESP::Integer(-42),
+            RESP::Inline(vec!["SET".to_owned(), "key".to_owned()]),
+            RESP::Array(vec![RESP::NullBulkString, RESP::NullArray]),
+        ];
+        #[cfg(feature = "resp3")]
+        elements.extend([
+            RESP::Double(1.5e-10),
+            RESP::Double(-0.0),
+            RESP::Double(123456789.0),
+            RESP::Double(f64::NAN),
+            RESP::Map(vec![(RESP::Boolean(true), RESP::BigNumber("1".repeat(30)))]),
+            RESP::WithAttributes {
+                attributes: vec![(RESP::SimpleString("ttl".into()), RESP::Double(1e100))],
+                value: Box::new(RESP::VerbatimString {
+                    encoding: "txt".into(),
+                    data: "text".to_owned(),
+                }),
+            },
+        ]);
+        let value = RESP::Array(elements);
+        let encoded = value.encode().unwrap();
+        let mut buffer = [0; 256];
+        let mut written = 0;
+        let count = allocations(|| {
+            written = value.encode_into(&mut buffer).unwrap();
+            assert_eq!(value.encoded_len(), written);
+        });
+        assert_eq!(count, 0);
+        assert_eq!(&buffer[..written], encoded);
+        // The allocator does see allocations
+        assert!(allocations(|| drop(value.encode())) > 0);
+
+        #[cfg(feature = "heapless")]
+        {
+            let mut buffer = heapless::Vec::<u8, 256>::new();
+            let count = allocations(|| {
+                value.encode_heapless(&mut buffer).unwrap();
+            });
+            assert_eq!(count, 0);
+            assert_eq!(&buffer[..], encoded);
+
+            let mut buffer = heapless::Vec::<u8, 64>::new();
+            let count = allocations(|| {
+                encode_command_heapless(&["SET", "key", "value"], &mut buffer).unwrap();
+            });
+            assert_eq!(count, 0);
+            assert_eq!(
+                &buffer[..],
+                &RESP::Array(vec![
+                    RESP::BulkString("SET".into()),
+                    RESP::BulkString("key".into()),
+                    RESP::BulkString("value".into()),
+                ])
+                .encode()
+                .unwrap()[..]
+            );
+        }
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn encode_heapless_vec() {
+        let mut buffer = heapless::Vec::<u8, 16>::new();
+        assert_eq!(RESP::Integer(1).encode_heapless(&mut buffer).unwrap(), 4);
+        assert_eq!(
+            RESP::SimpleString("OK".into())
+                .encode_heapless(&mut buffer)
+                .unwrap(),
+            5
+        );
+        assert_eq!(&buffer[..], b":1\r\n+OK\r\n");
+
+        let long = RESP::BulkString("Hello".to_owned());
+        assert!(matches!(
+            long.encode_heapless(&mut buffer),
+            Err(EncodeError::BufferTooSmall {
+                required: 11,
+                available: 7
+            })
+        ));
+        let invalid = RESP::SimpleString("a\nb".into());
+        assert!(matches!(
+            invalid.encode_heapless(&mut buffer),
+            Err(EncodeError::Invalid { .. })
+        ));
+        assert_eq!(buffer.len(), 9);
+    }
+}
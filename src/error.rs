@@ -0,0 +1,466 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::RESP;
+
+/// Longest preview of a value included in error messages.
+const PREVIEW_LEN: usize = 32;
+
+/// Quoted, escaped and truncated version of `data` for error messages.
+pub(crate) fn preview(data: &str) -> String {
+    match data.char_indices().nth(PREVIEW_LEN) {
+        Some((end, _)) => format!("{:?}…", &data[..end]),
+        None => format!("{data:?}"),
+    }
+}
+
+impl RESP {
+    /// Human readable name of the type of this value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::SimpleString(_) => "simple string",
+            Self::SimpleError(_) => "simple error",
+            Self::Integer(_) => "integer",
+            Self::BulkString(_) => "bulk string",
+            Self::NullBulkString => "null bulk string",
+            Self::Array(_) => "array",
+            Self::NullArray => "null array",
+            #[cfg(feature = "resp3")]
+            Self::Null => "null",
+            #[cfg(feature = "resp3")]
+            Self::Boolean(_) => "boolean",
+            #[cfg(feature = "resp3")]
+            Self::Double(_) => "double",
+            #[cfg(feature = "rust_decimal")]
+            Self::Decimal(_) => "decimal",
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(_) => "big number",
+            #[cfg(feature = "resp3")]
+            Self::BulkError(_) => "bulk error",
+            #[cfg(feature = "resp3")]
+            Self::VerbatimString { .. } => "verbatim string",
+            #[cfg(feature = "resp3")]
+            Self::Map(_) => "map",
+            #[cfg(feature = "resp3")]
+            Self::Set(_) => "set",
+            #[cfg(feature = "resp3")]
+            Self::Push(_) => "push",
+            Self::Inline(_) => "inline command",
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { value, .. } => value.type_name(),
+        }
+    }
+
+    /// Type name followed by a short preview of the value.
+    pub(crate) fn describe(&self) -> String {
+        let name = self.type_name();
+        match self {
+            Self::SimpleString(x) | Self::SimpleError(x) => format!("{name} {}", preview(x)),
+            Self::BulkString(x) => format!("{name} {}", preview(x)),
+            #[cfg(feature = "resp3")]
+            Self::BulkError(x) | Self::BigNumber(x) => format!("{name} {}", preview(x)),
+            #[cfg(feature = "resp3")]
+            Self::VerbatimString { data, .. } => format!("{name} {}", preview(data)),
+            Self::Integer(x) => format!("{name} {x}"),
+            #[cfg(feature = "resp3")]
+            Self::Boolean(x) => format!("{name} {x}"),
+            #[cfg(feature = "resp3")]
+            Self::Double(x) => format!("{name} {x}"),
+            #[cfg(feature = "rust_decimal")]
+            Self::Decimal(x) => format!("{name} {x}"),
+            Self::Array(x) => format!("{name} of {} elements", x.len()),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) | Self::Push(x) => format!("{name} of {} elements", x.len()),
+            #[cfg(feature = "resp3")]
+            Self::Map(x) => format!("{name} of {} entries", x.len()),
+            Self::Inline(x) => format!("{name} {}", preview(&x.join(" "))),
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { value, .. } => value.describe(),
+            Self::NullBulkString | Self::NullArray => name.to_owned(),
+            #[cfg(feature = "resp3")]
+            Self::Null => name.to_owned(),
+        }
+    }
+}
+
+/// Error returned when the input is not valid RESP, stating what the parser
+/// expected and what it found instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    expected: String,
+    found: String,
+    span: Option<Range<usize>>,
+}
+
+impl ParseError {
+    pub(crate) fn new(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self {
+            expected: expected.into(),
+            found: found.into(),
+            span: None,
+        }
+    }
+
+    /// Records where the error is, unless a more precise span was already
+    /// recorded.
+    pub(crate) fn at(mut self, span: Range<usize>) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    pub(crate) fn end_of_input(expected: impl Into<String>) -> Self {
+        Self::new(expected, "end of input")
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+
+    /// Byte range of the input holding the offending value, when the error
+    /// comes from the parser.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error returned when a value can't be extracted from a [`RESP`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConversionError {
+    /// The value is not of a type that can be converted.
+    UnexpectedType {
+        expected: &'static str,
+        found: String,
+    },
+    /// The value does not fit in the requested type.
+    OutOfRange {
+        expected: &'static str,
+        found: String,
+    },
+    /// A field required by the requested type is missing from the value.
+    MissingField {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl ConversionError {
+    pub(crate) fn unexpected_type(expected: &'static str, found: &RESP) -> Self {
+        Self::UnexpectedType {
+            expected,
+            found: found.describe(),
+        }
+    }
+
+    pub(crate) fn out_of_range(expected: &'static str, found: &RESP) -> Self {
+        Self::OutOfRange {
+            expected,
+            found: found.describe(),
+        }
+    }
+
+    pub(crate) fn missing_field(expected: &'static str, found: &RESP) -> Self {
+        Self::MissingField {
+            expected,
+            found: found.describe(),
+        }
+    }
+
+    pub fn expected(&self) -> &'static str {
+        match self {
+            Self::UnexpectedType { expected, .. }
+            | Self::OutOfRange { expected, .. }
+            | Self::MissingField { expected, .. } => expected,
+        }
+    }
+
+    pub fn found(&self) -> &str {
+        match self {
+            Self::UnexpectedType { found, .. }
+            | Self::OutOfRange { found, .. }
+            | Self::MissingField { found, .. } => found,
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedType { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            Self::OutOfRange { expected, found } => {
+                write!(f, "expected {expected}, found {found} (out of range)")
+            }
+            Self::MissingField { expected, found } => {
+                write!(f, "expected field {expected:?}, found {found} without it")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Error returned when a [`Decoder`](crate::Decoder) can't make sense of its input.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input is not valid RESP.
+    Parse(ParseError),
+    /// A bulk string is being streamed and must be finished through
+    /// [`Decoder::decode_to`](crate::Decoder::decode_to).
+    StreamInProgress,
+    /// Reading input, or writing a streamed bulk string or capture, failed.
+    Io(std::io::Error),
+    /// A frame stayed incomplete beyond the decoder's
+    /// [stall limits](crate::StallLimits).
+    Stalled { buffered: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "invalid frame: {err}"),
+            Self::StreamInProgress => write!(f, "a bulk string is being streamed"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Stalled { buffered } => {
+                write!(f, "frame stalled with {buffered} bytes buffered")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for DecodeError {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Error returned by [`from_reader`](crate::from_reader), when either
+/// reading the frame or converting it fails.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReadError {
+    Decode(DecodeError),
+    Conversion(ConversionError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(err) => err.fmt(f),
+            Self::Conversion(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            Self::Conversion(err) => Some(err),
+        }
+    }
+}
+
+impl From<DecodeError> for ReadError {
+    fn from(value: DecodeError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(value: ParseError) -> Self {
+        Self::Decode(value.into())
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Decode(value.into())
+    }
+}
+
+impl From<ConversionError> for ReadError {
+    fn from(value: ConversionError) -> Self {
+        Self::Conversion(value)
+    }
+}
+
+/// Error returned when a value can't be encoded.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The value breaks an invariant of its type and would corrupt the stream.
+    Invalid {
+        expected: &'static str,
+        found: String,
+    },
+    /// The buffer given to [`RESP::encode_into`] can't hold the value.
+    BufferTooSmall { required: usize, available: usize },
+    /// A value split by [`RESP::paginate`] holds an element that doesn't fit
+    /// in a page on its own.
+    ExceedsBudget { required: usize, budget: usize },
+    /// Writing the encoded value failed.
+    Io(std::io::Error),
+}
+
+impl EncodeError {
+    pub(crate) fn invalid(expected: &'static str, found: &RESP) -> Self {
+        Self::Invalid {
+            expected,
+            found: found.describe(),
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { expected, found } => {
+                write!(f, "can't encode {found}, expected {expected}")
+            }
+            Self::BufferTooSmall {
+                required,
+                available,
+            } => write!(
+                f,
+                "encoded value takes {required} bytes, buffer holds {available}"
+            ),
+            Self::ExceedsBudget { required, budget } => write!(
+                f,
+                "encoded element takes {required} bytes, pages hold {budget}"
+            ),
+            Self::Io(err) => write!(f, "failed to write encoded value: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodeError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<crate::encode::BufferTooSmall> for EncodeError {
+    fn from(value: crate::encode::BufferTooSmall) -> Self {
+        Self::BufferTooSmall {
+            required: value.required,
+            available: value.available,
+        }
+    }
+}
+
+impl From<EncodeError> for std::io::Error {
+    fn from(value: EncodeError) -> Self {
+        match value {
+            EncodeError::Io(err) => err,
+            err => Self::new(std::io::ErrorKind::InvalidInput, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_span() {
+        let err = RESP::try_parse("*2\r\n:1\r\n$5\r\nHi\r\n").unwrap_err();
+        assert_eq!(err.span(), Some(8..16));
+        let err = RESP::try_parse("*1\r\n").unwrap_err();
+        assert_eq!(err.span(), Some(4..4));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parse_error_message() {
+        let err = RESP::try_parse("#x\r\n").unwrap_err();
+        assert_eq!(err.expected(), "t or f");
+        assert_eq!(err.found(), "\"x\"");
+        assert_eq!(err.to_string(), "expected t or f, found \"x\"");
+
+        let err = RESP::try_parse("$5\r\nHi\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected 5 bytes, found end of input");
+
+        let err = RESP::try_parse("$4\r\nHi\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"\\r\\n\", found end of input");
+
+        let err = RESP::try_parse("$2\r\nHello\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"\\r\\n\", found \"llo\\r\\n\"");
+
+        let err = RESP::try_parse(":12").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"\\r\\n\", found end of input");
+
+        let err = RESP::try_parse(":1\n2\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"\\r\\n\", found '\\n'");
+
+        let err = RESP::try_parse("*-2\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected length of at least -1, found -2");
+
+        let err = RESP::try_parse(":abc\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected integer, found \"abc\"");
+    }
+
+    #[test]
+    fn parse_error_preview_truncated() {
+        let data = "x".repeat(100);
+        let err = RESP::try_parse(&format!(":{data}\r\n")).unwrap_err();
+        assert_eq!(err.found(), format!("{:?}…", "x".repeat(PREVIEW_LEN)));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn conversion_error_message() {
+        let err = RESP::BulkString("Hello".to_owned()).to_u64().unwrap_err();
+        assert_eq!(err.expected(), "u64");
+        assert_eq!(err.found(), "bulk string \"Hello\"");
+        assert_eq!(err.to_string(), "expected u64, found bulk string \"Hello\"");
+
+        let err = RESP::Integer(-1).to_u64().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected u64, found integer -1 (out of range)"
+        );
+
+        let err = RESP::Array(vec![RESP::Null]).to_i128().unwrap_err();
+        assert_eq!(err.to_string(), "expected i128, found array of 1 elements");
+    }
+}
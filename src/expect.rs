@@ -0,0 +1,281 @@
+//! Matchers describing the shape of a reply, with wildcards for incidental
+//! values like timestamps or ids.
+//!
+//! ```
+//! use resp_parser_rs::{expect, RESP};
+//!
+//! let reply = RESP::parse("*2\r\n$2\r\nOK\r\n:1712345678\r\n").unwrap();
+//! expect::array([expect::bulk("OK"), expect::any_int()]).assert(&reply);
+//! ```
+
+use std::fmt;
+
+use crate::RESP;
+
+enum Matcher {
+    Any,
+    AnyInt,
+    AnyString,
+    Int(i64),
+    Bulk(String),
+    Simple(String),
+    Error(String),
+    #[cfg(feature = "resp3")]
+    Boolean(bool),
+    Null,
+    Array(Vec<Expect>),
+    #[cfg(feature = "resp3")]
+    Set(Vec<Expect>),
+    #[cfg(feature = "resp3")]
+    Push(Vec<Expect>),
+    #[cfg(feature = "resp3")]
+    Map(Vec<(Expect, Expect)>),
+    Value(RESP),
+    Predicate(String, Box<dyn Fn(&RESP) -> bool>),
+}
+
+/// Expected shape of a [`RESP`] value, built with the functions of this
+/// module. Attributes on the checked value are ignored.
+pub struct Expect(Matcher);
+
+impl fmt::Debug for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// Matches anything.
+pub fn any() -> Expect {
+    Expect(Matcher::Any)
+}
+
+/// Matches any integer.
+pub fn any_int() -> Expect {
+    Expect(Matcher::AnyInt)
+}
+
+/// Matches any simple, bulk or verbatim string.
+pub fn any_string() -> Expect {
+    Expect(Matcher::AnyString)
+}
+
+pub fn int(value: i64) -> Expect {
+    Expect(Matcher::Int(value))
+}
+
+pub fn bulk(value: impl Into<String>) -> Expect {
+    Expect(Matcher::Bulk(value.into()))
+}
+
+pub fn simple(value: impl Into<String>) -> Expect {
+    Expect(Matcher::Simple(value.into()))
+}
+
+/// Matches a simple or bulk error whose message starts with `prefix`.
+pub fn error(prefix: impl Into<String>) -> Expect {
+    Expect(Matcher::Error(prefix.into()))
+}
+
+#[cfg(feature = "resp3")]
+pub fn boolean(value: bool) -> Expect {
+    Expect(Matcher::Boolean(value))
+}
+
+/// Matches any of the null values.
+pub fn null() -> Expect {
+    Expect(Matcher::Null)
+}
+
+pub fn array(elements: impl IntoIterator<Item = Expect>) -> Expect {
+    Expect(Matcher::Array(elements.into_iter().collect()))
+}
+
+/// Matches a set holding the given elements in that order.
+#[cfg(feature = "resp3")]
+pub fn set(elements: impl IntoIterator<Item = Expect>) -> Expect {
+    Expect(Matcher::Set(elements.into_iter().collect()))
+}
+
+#[cfg(feature = "resp3")]
+pub fn push(elements: impl IntoIterator<Item = Expect>) -> Expect {
+    Expect(Matcher::Push(elements.into_iter().collect()))
+}
+
+/// Matches a map holding the given entries in that order.
+#[cfg(feature = "resp3")]
+pub fn map(entries: impl IntoIterator<Item = (Expect, Expect)>) -> Expect {
+    Expect(Matcher::Map(entries.into_iter().collect()))
+}
+
+/// Matches a value equal to `value`.
+pub fn value(value: RESP) -> Expect {
+    Expect(Matcher::Value(value))
+}
+
+/// Matches values accepted by `predicate`, `description` being shown when
+/// the match fails.
+pub fn matching<F>(description: impl Into<String>, predicate: F) -> Expect
+where
+    F: Fn(&RESP) -> bool + 'static,
+{
+    Expect(Matcher::Predicate(description.into(), Box::new(predicate)))
+}
+
+impl Expect {
+    fn describe(&self) -> String {
+        match &self.0 {
+            Matcher::Any => "anything".to_owned(),
+            Matcher::AnyInt => "any integer".to_owned(),
+            Matcher::AnyString => "any string".to_owned(),
+            Matcher::Int(x) => format!("integer {x}"),
+            Matcher::Bulk(x) => format!("bulk string {x:?}"),
+            Matcher::Simple(x) => format!("simple string {x:?}"),
+            Matcher::Error(x) => format!("error starting with {x:?}"),
+            #[cfg(feature = "resp3")]
+            Matcher::Boolean(x) => format!("boolean {x}"),
+            Matcher::Null => "null".to_owned(),
+            Matcher::Array(x) => format!("array of {} elements", x.len()),
+            #[cfg(feature = "resp3")]
+            Matcher::Set(x) => format!("set of {} elements", x.len()),
+            #[cfg(feature = "resp3")]
+            Matcher::Push(x) => format!("push of {} elements", x.len()),
+            #[cfg(feature = "resp3")]
+            Matcher::Map(x) => format!("map of {} entries", x.len()),
+            Matcher::Value(x) => x.describe(),
+            Matcher::Predicate(x, _) => x.clone(),
+        }
+    }
+
+    /// Every place `value` doesn't match, one line each, prefixed with the
+    /// path to it like [`testing::diff`](crate::testing::diff) does.
+    pub fn mismatches(&self, value: &RESP) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        self.walk("$", value, &mut mismatches);
+        mismatches
+    }
+
+    pub fn matches(&self, value: &RESP) -> bool {
+        self.mismatches(value).is_empty()
+    }
+
+    /// Panics listing the mismatches if `value` doesn't match.
+    #[track_caller]
+    pub fn assert(&self, value: &RESP) {
+        let mismatches = self.mismatches(value);
+        if !mismatches.is_empty() {
+            panic!("reply doesn't match\n{}", mismatches.join("\n"));
+        }
+    }
+
+    fn walk_elements(path: &str, expected: &[Expect], elements: &[RESP], out: &mut Vec<String>) {
+        if expected.len() != elements.len() {
+            out.push(format!(
+                "{path}: expected {} elements, found {}",
+                expected.len(),
+                elements.len()
+            ));
+        }
+        for (index, (expected, element)) in expected.iter().zip(elements).enumerate() {
+            expected.walk(&format!("{path}[{index}]"), element, out);
+        }
+    }
+
+    fn walk(&self, path: &str, value: &RESP, out: &mut Vec<String>) {
+        let value = value.inner();
+        let matched = match (&self.0, value) {
+            (Matcher::Array(expected), RESP::Array(elements)) => {
+                Self::walk_elements(path, expected, elements, out);
+                true
+            }
+            #[cfg(feature = "resp3")]
+            (Matcher::Set(expected), RESP::Set(elements))
+            | (Matcher::Push(expected), RESP::Push(elements)) => {
+                Self::walk_elements(path, expected, elements, out);
+                true
+            }
+            #[cfg(feature = "resp3")]
+            (Matcher::Map(expected), RESP::Map(entries)) => {
+                if expected.len() != entries.len() {
+                    out.push(format!(
+                        "{path}: expected {} entries, found {}",
+                        expected.len(),
+                        entries.len()
+                    ));
+                }
+                for (index, ((expected_key, expected), (key, value))) in
+                    expected.iter().zip(entries).enumerate()
+                {
+                    expected_key.walk(&format!("{path}{{#{index} key}}"), key, out);
+                    expected.walk(&format!("{path}{{#{index}}}"), value, out);
+                }
+                true
+            }
+            (Matcher::Any, _) => true,
+            (Matcher::AnyInt, RESP::Integer(_)) => true,
+            (Matcher::AnyString, RESP::SimpleString(_) | RESP::BulkString(_)) => true,
+            #[cfg(feature = "resp3")]
+            (Matcher::AnyString, RESP::VerbatimString { .. }) => true,
+            (Matcher::Int(expected), RESP::Integer(x)) => expected == x,
+            (Matcher::Bulk(expected), RESP::BulkString(x)) => expected == x,
+            (Matcher::Simple(expected), RESP::SimpleString(x)) => expected == x,
+            (Matcher::Error(prefix), RESP::SimpleError(x)) => x.starts_with(prefix.as_str()),
+            #[cfg(feature = "resp3")]
+            (Matcher::Error(prefix), RESP::BulkError(x)) => x.starts_with(prefix.as_str()),
+            #[cfg(feature = "resp3")]
+            (Matcher::Boolean(expected), RESP::Boolean(x)) => expected == x,
+            (Matcher::Null, _) => value.is_null(),
+            (Matcher::Value(expected), _) => expected == value,
+            (Matcher::Predicate(_, predicate), _) => predicate(value),
+            _ => false,
+        };
+        if !matched {
+            out.push(format!(
+                "{path}: expected {}, found {}",
+                self.describe(),
+                value.describe()
+            ));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expect_wildcards() {
+        let reply =
+            RESP::parse("*3\r\n$2\r\nOK\r\n:1712345678\r\n%1\r\n+id\r\n$3\r\nabc\r\n").unwrap();
+        assert!(
+            array([bulk("OK"), any_int(), map([(simple("id"), any_string())])]).matches(&reply)
+        );
+        assert!(array([any(), any(), any()]).matches(&reply));
+        assert!(!array([bulk("OK"), any_int()]).matches(&reply));
+    }
+
+    #[test]
+    fn expect_mismatches() {
+        let reply = RESP::parse("*3\r\n-ERR no such key\r\n:5\r\n_\r\n").unwrap();
+        assert!(array([error("ERR"), int(5), null()]).matches(&reply));
+        assert_eq!(
+            array([error("WRONGTYPE"), matching("even integer", |x| matches!(x, RESP::Integer(x) if x % 2 == 0)), null()])
+                .mismatches(&reply),
+            vec![
+                "$[0]: expected error starting with \"WRONGTYPE\", found simple error \"ERR no such key\"",
+                "$[1]: expected even integer, found integer 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn expect_ignores_attributes() {
+        let reply = RESP::parse("|1\r\n+ttl\r\n:3\r\n:1\r\n").unwrap();
+        int(1).assert(&reply);
+    }
+
+    #[test]
+    #[should_panic(expected = "$: expected boolean true, found integer 1")]
+    fn expect_assert_fails() {
+        boolean(true).assert(&RESP::Integer(1));
+    }
+}
@@ -0,0 +1,159 @@
+//! [`defmt::Format`] implementations, so values and errors can be logged
+//! from embedded targets with interned format strings.
+
+use defmt::{write, Display2Format, Format, Formatter};
+
+use crate::{ConversionError, DecodeError, EncodeError, ParseError, ReadError, RESP};
+
+fn elements(f: Formatter<'_>, open: &str, elements: &[RESP], close: &str) {
+    write!(f, "{=str}", open);
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ");
+        }
+        write!(f, "{}", element);
+    }
+    write!(f, "{=str}", close);
+}
+
+#[cfg(feature = "resp3")]
+fn pairs(f: Formatter<'_>, open: &str, pairs: &[(RESP, RESP)]) {
+    write!(f, "{=str}", open);
+    for (index, (key, value)) in pairs.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ");
+        }
+        write!(f, "{}: {}", key, value);
+    }
+    write!(f, "}}");
+}
+
+impl Format for RESP {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::SimpleString(x) => write!(f, "+{=str}", x.as_str()),
+            Self::SimpleError(x) => write!(f, "-{=str}", x.as_str()),
+            Self::Integer(x) => write!(f, "{=i64}", x),
+            Self::BulkString(x) => write!(f, "{=str:?}", x.as_str()),
+            Self::NullBulkString | Self::NullArray => write!(f, "null"),
+            #[cfg(feature = "resp3")]
+            Self::Null => write!(f, "null"),
+            #[cfg(feature = "resp3")]
+            Self::Boolean(x) => write!(f, "{=bool}", x),
+            #[cfg(feature = "resp3")]
+            Self::Double(x) => write!(f, "{=f64}", x),
+            #[cfg(feature = "rust_decimal")]
+            Self::Decimal(x) => write!(f, "{}", Display2Format(x)),
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(x) => write!(f, "({=str}", x.as_str()),
+            #[cfg(feature = "resp3")]
+            Self::BulkError(x) => write!(f, "!{=str:?}", x.as_str()),
+            #[cfg(feature = "resp3")]
+            Self::VerbatimString { encoding, data } => {
+                write!(f, "{=str}:{=str:?}", encoding.as_str(), data.as_str())
+            }
+            Self::Array(x) => elements(f, "[", x, "]"),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) => elements(f, "~{", x, "}"),
+            #[cfg(feature = "resp3")]
+            Self::Push(x) => elements(f, ">[", x, "]"),
+            #[cfg(feature = "resp3")]
+            Self::Map(x) => pairs(f, "{", x),
+            Self::Inline(x) => {
+                for (index, argument) in x.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ");
+                    }
+                    write!(f, "{=str}", argument.as_str());
+                }
+            }
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { attributes, value } => {
+                pairs(f, "|{", attributes);
+                write!(f, " {}", value.as_ref());
+            }
+        }
+    }
+}
+
+impl Format for ParseError {
+    fn format(&self, f: Formatter<'_>) {
+        write!(
+            f,
+            "expected {=str}, found {=str}",
+            self.expected(),
+            self.found()
+        );
+    }
+}
+
+impl Format for ConversionError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::UnexpectedType { expected, found } => {
+                write!(f, "expected {=str}, found {=str}", expected, found.as_str())
+            }
+            Self::OutOfRange { expected, found } => write!(
+                f,
+                "expected {=str}, found {=str} (out of range)",
+                expected,
+                found.as_str()
+            ),
+            Self::MissingField { expected, found } => write!(
+                f,
+                "expected field {=str:?}, found {=str} without it",
+                expected,
+                found.as_str()
+            ),
+        }
+    }
+}
+
+impl Format for DecodeError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::Parse(err) => write!(f, "invalid frame: {}", err),
+            Self::StreamInProgress => write!(f, "a bulk string is being streamed"),
+            Self::Io(err) => write!(f, "I/O error: {}", Display2Format(err)),
+            Self::Stalled { buffered } => {
+                write!(f, "frame stalled with {=usize} bytes buffered", buffered)
+            }
+        }
+    }
+}
+
+impl Format for ReadError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::Decode(err) => err.format(f),
+            Self::Conversion(err) => err.format(f),
+        }
+    }
+}
+
+impl Format for EncodeError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::Invalid { expected, found } => write!(
+                f,
+                "can't encode {=str}, expected {=str}",
+                found.as_str(),
+                expected
+            ),
+            Self::BufferTooSmall {
+                required,
+                available,
+            } => write!(
+                f,
+                "encoded value takes {=usize} bytes, buffer holds {=usize}",
+                required, available
+            ),
+            Self::ExceedsBudget { required, budget } => write!(
+                f,
+                "encoded element takes {=usize} bytes, pages hold {=usize}",
+                required, budget
+            ),
+            Self::Io(err) => write!(f, "failed to write encoded value: {}", Display2Format(err)),
+        }
+    }
+}
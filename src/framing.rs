@@ -0,0 +1,396 @@
+//! Low-level framing primitives, for protocols built on top of RESP framing
+//! that the parser doesn't know about.
+//!
+//! Positions are byte offsets into the buffer, and `Ok(None)` means more
+//! data is needed.
+//!
+//! ```
+//! use resp_parser_rs::framing;
+//!
+//! let data = b"$5\r\nHello\r\n:1\r\n";
+//! let (payload, next) = framing::read_len_prefixed(data, 1).unwrap().unwrap();
+//! assert_eq!(payload, Some(&b"Hello"[..]));
+//! assert_eq!(framing::read_line(data, next + 1), Some((&b"1"[..], data.len())));
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{preview, ParseError};
+
+pub const CRLF: &[u8] = b"\r\n";
+
+/// Position of the next CRLF at or after `start`.
+pub fn find_crlf(data: &[u8], start: usize) -> Option<usize> {
+    data.get(start..)?
+        .windows(CRLF.len())
+        .position(|x| x == CRLF)
+        .map(|x| x + start)
+}
+
+/// Parses the length header starting at `start`, returning it alongside the
+/// position right after its CRLF.
+pub fn read_length(data: &[u8], start: usize) -> Result<Option<(isize, usize)>, ParseError> {
+    let Some(end) = find_crlf(data, start) else {
+        return Ok(None);
+    };
+    let line = String::from_utf8_lossy(&data[start..end]);
+    let length = line
+        .parse::<isize>()
+        .map_err(|_| ParseError::new("length", preview(&line)))?;
+    Ok(Some((length, end + CRLF.len())))
+}
+
+/// Reads the line starting at `start`, returning it without its CRLF
+/// alongside the position right after it.
+pub fn read_line(data: &[u8], start: usize) -> Option<(&[u8], usize)> {
+    let end = find_crlf(data, start)?;
+    Some((&data[start..end], end + CRLF.len()))
+}
+
+/// Payload read by [`read_len_prefixed`], `None` for the null length, and the
+/// position right after it.
+pub type LenPrefixed<'a> = (Option<&'a [u8]>, usize);
+
+/// Reads a length header starting at `start` and the payload following it,
+/// returning the payload alongside the position right after its CRLF. A
+/// length of -1 gives no payload, like null bulk strings.
+pub fn read_len_prefixed(data: &[u8], start: usize) -> Result<Option<LenPrefixed<'_>>, ParseError> {
+    let Some((length, start)) = read_length(data, start)? else {
+        return Ok(None);
+    };
+    let length = match length {
+        -1 => return Ok(Some((None, start))),
+        0.. => length as usize,
+        _ => return Err(ParseError::new("length of at least -1", length.to_string())),
+    };
+    let end = start
+        .checked_add(length)
+        .ok_or_else(|| too_long(length as isize))?;
+    let Some(terminator) = data.get(end..end + CRLF.len()) else {
+        return Ok(None);
+    };
+    if terminator != CRLF {
+        return Err(ParseError::new(
+            "\"\\r\\n\"",
+            preview(&String::from_utf8_lossy(terminator)),
+        ));
+    }
+    Ok(Some((Some(&data[start..end]), end + CRLF.len())))
+}
+
+/// Writes the header of a length prefixed value of type `kind`, `None`
+/// writing the null length -1.
+pub fn write_len_header<W: Write>(
+    writer: &mut W,
+    kind: u8,
+    length: Option<usize>,
+) -> io::Result<()> {
+    writer.write_all(&[kind])?;
+    match length {
+        Some(length) => write!(writer, "{length}\r\n"),
+        None => writer.write_all(b"-1\r\n"),
+    }
+}
+
+fn too_long(length: isize) -> ParseError {
+    ParseError::new("length that fits in memory", length.to_string())
+}
+
+/// Finds how many bytes the first frame in `data` spans without decoding it,
+/// or `None` if the frame is not complete yet.
+///
+/// Only the framing is checked here, the contents are validated by the parser.
+pub fn frame_len(data: &[u8]) -> Result<Option<usize>, ParseError> {
+    walk_frame(data, |_| {})
+}
+
+/// Like [`frame_len`], passing the type byte of every value in the frame to
+/// `visit` as it goes.
+pub(crate) fn walk_frame<F>(data: &[u8], mut visit: F) -> Result<Option<usize>, ParseError>
+where
+    F: FnMut(u8),
+{
+    let mut position = 0;
+    // Elements still needed to complete the frame, aggregates add their
+    // children here instead of recursing
+    let mut remaining: usize = 1;
+    // What remained around each streamed aggregate being walked, these
+    // having elements until their `.` rather than a count
+    let mut streamed: Vec<usize> = Vec::new();
+
+    while remaining > 0 || !streamed.is_empty() {
+        // Without remaining elements, the next is one of a streamed aggregate
+        let in_stream = remaining == 0;
+        remaining = remaining.saturating_sub(1);
+        let Some(&kind) = data.get(position) else {
+            return Ok(None);
+        };
+        if in_stream && kind == b'.' {
+            let Some((line, end)) = read_line(data, position + 1) else {
+                return Ok(None);
+            };
+            if !line.is_empty() {
+                let line = String::from_utf8_lossy(line);
+                return Err(ParseError::new("end of streamed aggregate", preview(&line)));
+            }
+            position = end;
+            remaining = streamed.pop().unwrap_or_default();
+            continue;
+        }
+        visit(kind);
+        match kind as char {
+            '+' | '-' | ':' | '_' | '#' | ',' | '(' => {
+                let Some(end) = find_crlf(data, position + 1) else {
+                    return Ok(None);
+                };
+                position = end + CRLF.len();
+            }
+            '$' | '!' | '=' => {
+                let Some((length, start)) = read_length(data, position + 1)? else {
+                    return Ok(None);
+                };
+                position = match length {
+                    -1 => start,
+                    0.. => (length as usize)
+                        .checked_add(start + CRLF.len())
+                        .ok_or_else(|| too_long(length))?,
+                    _ => return Err(ParseError::new("length of at least -1", length.to_string())),
+                };
+                if length >= 0 {
+                    // The payload is followed by CRLF, check what arrived of it
+                    let terminator = &data[(position - CRLF.len()).min(data.len())..];
+                    let terminator = &terminator[..terminator.len().min(CRLF.len())];
+                    if !CRLF.starts_with(terminator) {
+                        let found = String::from_utf8_lossy(terminator);
+                        return Err(ParseError::new(format!("{:?}", "\r\n"), preview(&found)));
+                    }
+                }
+                if position > data.len() {
+                    return Ok(None);
+                }
+            }
+            '*' | '~' | '>' | '%' | '|' => {
+                match read_line(data, position + 1) {
+                    None => return Ok(None),
+                    Some((b"?", end)) if kind != b'|' => {
+                        position = end;
+                        streamed.push(remaining);
+                        remaining = 0;
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+                let Some((length, start)) = read_length(data, position + 1)? else {
+                    return Ok(None);
+                };
+                position = start;
+                let children = match (kind, length) {
+                    (_, -1) => Some(0),
+                    (b'%', 0..) => (length as usize).checked_mul(2),
+                    // Attributes are followed by the value they annotate
+                    (b'|', 0..) => (length as usize)
+                        .checked_mul(2)
+                        .and_then(|x| x.checked_add(1)),
+                    (_, 0..) => Some(length as usize),
+                    _ => return Err(ParseError::new("length of at least -1", length.to_string())),
+                };
+                remaining = children
+                    .and_then(|x| x.checked_add(remaining))
+                    .ok_or_else(|| too_long(length))?;
+            }
+            #[cfg(feature = "inline")]
+            _ => {
+                let Some(end) = data[position..].iter().position(|&x| x == b'\n') else {
+                    return Ok(None);
+                };
+                position += end + 1;
+            }
+            #[cfg(not(feature = "inline"))]
+            x => return Err(ParseError::new("type byte", format!("{x:?}"))),
+        }
+    }
+
+    Ok(Some(position))
+}
+
+/// Iterator over the raw bytes of each frame in a buffer, found with
+/// [`frame_len`] so nothing is decoded or copied.
+///
+/// A truncated or malformed frame yields an error and ends the iteration.
+#[derive(Debug, Clone)]
+pub struct RawFrames<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> RawFrames<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bytes not yielded yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for RawFrames<'a> {
+    type Item = Result<&'a [u8], ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        match frame_len(self.data) {
+            Ok(Some(length)) => {
+                let (frame, rest) = self.data.split_at(length);
+                self.data = rest;
+                Some(Ok(frame))
+            }
+            Ok(None) => {
+                self.data = &[];
+                Some(Err(ParseError::end_of_input("rest of the frame")))
+            }
+            Err(err) => {
+                self.data = &[];
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for RawFrames<'_> {}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_len_simple() {
+        assert!(matches!(frame_len(b"+OK\r\n"), Ok(Some(5))));
+        assert!(matches!(frame_len(b"+OK\r\n+OK\r\n"), Ok(Some(5))));
+        assert!(matches!(frame_len(b":-12\r\n"), Ok(Some(6))));
+        assert!(matches!(frame_len(b"_\r\n"), Ok(Some(3))));
+    }
+
+    #[test]
+    fn frame_len_bulk() {
+        assert!(matches!(frame_len(b"$5\r\nHello\r\n"), Ok(Some(11))));
+        assert!(matches!(frame_len(b"$5\r\nHe\r\no\r\n"), Ok(Some(11))));
+        assert!(matches!(frame_len(b"$-1\r\n"), Ok(Some(5))));
+        assert!(matches!(frame_len(b"=9\r\ntxt:Hello\r\n"), Ok(Some(15))));
+    }
+
+    #[test]
+    fn frame_len_bulk_terminator() {
+        let err = frame_len(b"$3\r\nabcXY:1\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"\\r\\n\", found \"XY\"");
+        assert!(matches!(frame_len(b"$3\r\nabc\rX"), Err(_)));
+        assert!(matches!(frame_len(b"!1\r\nxx"), Err(_)));
+        assert!(matches!(frame_len(b"*1\r\n$1\r\nab\r\n"), Err(_)));
+        // A terminator that may still complete is waited for
+        assert!(matches!(frame_len(b"$3\r\nabc"), Ok(None)));
+        assert!(matches!(frame_len(b"$3\r\nabc\r"), Ok(None)));
+    }
+
+    #[test]
+    fn frame_len_aggregate() {
+        assert!(matches!(
+            frame_len(b"*2\r\n+Hello\r\n*1\r\n:1\r\n+tail\r\n"),
+            Ok(Some(20))
+        ));
+        assert!(matches!(
+            frame_len(b"%1\r\n+key\r\n$5\r\nvalue\r\n"),
+            Ok(Some(21))
+        ));
+        assert!(matches!(
+            frame_len(b"|1\r\n+ttl\r\n:1\r\n:2\r\n"),
+            Ok(Some(18))
+        ));
+        assert!(matches!(frame_len(b"*-1\r\n"), Ok(Some(5))));
+        assert!(matches!(frame_len(b"*0\r\n"), Ok(Some(4))));
+    }
+
+    #[test]
+    fn frame_len_streamed() {
+        let data = b"*?\r\n:1\r\n%?\r\n+a\r\n*1\r\n:2\r\n.\r\n.\r\n+next\r\n";
+        assert!(matches!(frame_len(data), Ok(Some(30))));
+        assert!(matches!(frame_len(&data[..29]), Ok(None)));
+        assert!(matches!(frame_len(b"*?\r\n.x\r\n"), Err(_)));
+        assert!(matches!(frame_len(b"|?\r\n"), Err(_)));
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn frame_len_inline() {
+        assert!(matches!(frame_len(b"PING\r\n+OK\r\n"), Ok(Some(6))));
+        assert!(matches!(frame_len(b"PING\n"), Ok(Some(5))));
+        assert!(matches!(frame_len(b"PING"), Ok(None)));
+    }
+
+    #[test]
+    fn frame_len_incomplete() {
+        assert!(matches!(frame_len(b""), Ok(None)));
+        assert!(matches!(frame_len(b"+OK"), Ok(None)));
+        assert!(matches!(frame_len(b"+OK\r"), Ok(None)));
+        assert!(matches!(frame_len(b"$5"), Ok(None)));
+        assert!(matches!(frame_len(b"$5\r\nHel"), Ok(None)));
+        assert!(matches!(frame_len(b"$5\r\nHello\r"), Ok(None)));
+        assert!(matches!(frame_len(b"*2\r\n+Hello\r\n"), Ok(None)));
+    }
+
+    #[test]
+    fn raw_frames() {
+        let mut frames = RawFrames::new(b"+OK\r\n*1\r\n:1\r\n$3\r\nabc\r\n");
+        assert!(matches!(frames.next(), Some(Ok(b"+OK\r\n"))));
+        assert!(matches!(frames.next(), Some(Ok(b"*1\r\n:1\r\n"))));
+        assert!(matches!(frames.next(), Some(Ok(b"$3\r\nabc\r\n"))));
+        assert!(matches!(frames.next(), None));
+    }
+
+    #[test]
+    fn raw_frames_truncated() {
+        let mut frames = RawFrames::new(b"+OK\r\n$3\r\nab");
+        assert!(matches!(frames.next(), Some(Ok(b"+OK\r\n"))));
+        assert_eq!(frames.remaining(), b"$3\r\nab");
+        assert!(matches!(frames.next(), Some(Err(_))));
+        assert!(matches!(frames.next(), None));
+    }
+
+    #[test]
+    fn read_primitives() {
+        assert_eq!(read_line(b"+OK\r\n", 1), Some((&b"OK"[..], 5)));
+        assert_eq!(read_line(b"+OK\r", 1), None);
+        assert_eq!(
+            read_len_prefixed(b"$2\r\nab\r\n", 1),
+            Ok(Some((Some(&b"ab"[..]), 8)))
+        );
+        assert_eq!(read_len_prefixed(b"$-1\r\n", 1), Ok(Some((None, 5))));
+        assert_eq!(read_len_prefixed(b"$2\r\nab\r", 1), Ok(None));
+        assert!(matches!(read_len_prefixed(b"$2\r\nabc\r\n", 1), Err(_)));
+        assert!(matches!(read_len_prefixed(b"$-3\r\n", 1), Err(_)));
+    }
+
+    #[test]
+    fn write_header() {
+        let mut output = Vec::new();
+        write_len_header(&mut output, b'*', Some(3)).unwrap();
+        write_len_header(&mut output, b'$', None).unwrap();
+        assert_eq!(output, b"*3\r\n$-1\r\n");
+    }
+
+    #[test]
+    fn frame_len_invalid() {
+        assert!(matches!(frame_len(b"$x\r\n"), Err(_)));
+        assert!(matches!(frame_len(b"$-2\r\n"), Err(_)));
+        assert!(matches!(frame_len(b"*-2\r\n"), Err(_)));
+        assert!(matches!(
+            frame_len(format!("*{}\r\n", isize::MAX).as_bytes()),
+            Ok(None)
+        ));
+        assert!(matches!(
+            frame_len(format!("%{0}\r\n%{0}\r\n", isize::MAX).as_bytes()),
+            Err(_)
+        ));
+    }
+}
@@ -0,0 +1,429 @@
+//! Deterministic generation of random valid frames, for load generators and
+//! fuzzing corpus seeds, and of corrupted frames for negative testing.
+
+use crate::dump::{tokenize, Part};
+use crate::{Protocol, ShortString, RESP};
+
+/// Characters used for simple strings, which can't hold CR or LF.
+const SIMPLE_ALPHABET: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _-:.";
+#[cfg(feature = "resp3")]
+const ENCODINGS: &[&str] = &["txt", "mkd"];
+
+/// Produces random valid values from a seed, the same seed always giving the
+/// same sequence.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+    protocol: Protocol,
+    max_depth: usize,
+    max_len: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            // Xorshift gets stuck on 0
+            state: seed ^ 0x9e37_79b9_7f4a_7c15,
+            protocol: Protocol::Resp3,
+            max_depth: 4,
+            max_len: 16,
+        }
+    }
+
+    /// Only generates types available in `protocol`, RESP3 by default. Builds
+    /// without RESP3 support always generate RESP2.
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Maximum nesting of aggregates, attributes included, 4 by default.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Maximum elements in an aggregate and bytes in a string, 16 by default.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    #[cfg(feature = "resp3")]
+    fn coin(&mut self) -> bool {
+        self.next_u64() & 1 == 0
+    }
+
+    /// Random number in `0..=max`.
+    fn up_to(&mut self, max: usize) -> usize {
+        (self.next_u64() % (max as u64 + 1)) as usize
+    }
+
+    fn text(&mut self, alphabet: &[u8]) -> String {
+        let length = self.up_to(self.max_len);
+        (0..length)
+            .map(|_| alphabet[self.up_to(alphabet.len() - 1)] as char)
+            .collect()
+    }
+
+    // Only an identity conversion when compact_str is disabled
+    #[allow(clippy::useless_conversion)]
+    fn short(&mut self) -> ShortString {
+        self.text(SIMPLE_ALPHABET).into()
+    }
+
+    fn bulk(&mut self) -> String {
+        // Payloads are read up to their CRLF, so line breaks are left out
+        let length = self.up_to(self.max_len);
+        (0..length)
+            .map(|_| match self.up_to(9) {
+                0 => 'é',
+                _ => SIMPLE_ALPHABET[self.up_to(SIMPLE_ALPHABET.len() - 1)] as char,
+            })
+            .collect()
+    }
+
+    fn integer(&mut self) -> i64 {
+        match self.up_to(3) {
+            0 => self.next_u64() as i64,
+            _ => self.up_to(2000) as i64 - 1000,
+        }
+    }
+
+    fn elements(&mut self, depth: usize) -> Vec<RESP> {
+        let length = self.up_to(self.max_len);
+        (0..length).map(|_| self.internal(depth + 1)).collect()
+    }
+
+    #[cfg(feature = "resp3")]
+    fn pairs(&mut self, depth: usize) -> Vec<(RESP, RESP)> {
+        let length = self.up_to(self.max_len);
+        (0..length)
+            .map(|_| (self.internal(depth + 1), self.internal(depth + 1)))
+            .collect()
+    }
+
+    /// A value nested in another one, where pushes aren't allowed.
+    fn internal(&mut self, depth: usize) -> RESP {
+        self.generate(depth, true)
+    }
+
+    #[cfg_attr(not(feature = "resp3"), allow(unused_variables))]
+    fn generate(&mut self, depth: usize, internal: bool) -> RESP {
+        let nested = depth < self.max_depth;
+        let resp3 = cfg!(feature = "resp3") && self.protocol == Protocol::Resp3;
+        loop {
+            let value = match self.up_to(if resp3 { 17 } else { 6 }) {
+                0 => RESP::SimpleString(self.short()),
+                1 => RESP::SimpleError(self.short()),
+                2 => RESP::Integer(self.integer()),
+                3 => RESP::BulkString(self.bulk()),
+                4 => RESP::NullBulkString,
+                5 if nested => RESP::Array(self.elements(depth)),
+                6 => RESP::NullArray,
+                #[cfg(feature = "resp3")]
+                7 => RESP::Null,
+                #[cfg(feature = "resp3")]
+                8 => RESP::Boolean(self.coin()),
+                #[cfg(feature = "resp3")]
+                9 => RESP::Double(self.integer() as f64 / (self.up_to(999) + 1) as f64),
+                #[cfg(feature = "resp3")]
+                10 => {
+                    let digits = self.text(b"0123456789");
+                    let sign = if self.coin() { "-" } else { "" };
+                    RESP::BigNumber(format!("{sign}1{digits}"))
+                }
+                #[cfg(feature = "resp3")]
+                11 => RESP::BulkError(self.bulk()),
+                #[cfg(feature = "resp3")]
+                12 => RESP::VerbatimString {
+                    encoding: ENCODINGS[self.up_to(ENCODINGS.len() - 1)].into(),
+                    data: self.bulk(),
+                },
+                #[cfg(feature = "resp3")]
+                13 if nested => RESP::Map(self.pairs(depth)),
+                #[cfg(feature = "resp3")]
+                14 if nested => RESP::Set(self.elements(depth)),
+                #[cfg(feature = "resp3")]
+                15 if nested && !internal => RESP::Push(self.elements(depth)),
+                #[cfg(feature = "resp3")]
+                16 if nested => RESP::WithAttributes {
+                    attributes: self.pairs(depth),
+                    value: Box::new(self.generate(depth + 1, true)),
+                },
+                _ => continue,
+            };
+            return value;
+        }
+    }
+
+    /// A random value.
+    pub fn value(&mut self) -> RESP {
+        self.generate(0, false)
+    }
+
+    /// A random value, encoded.
+    pub fn frame(&mut self) -> Vec<u8> {
+        self.value()
+            .encode()
+            .expect("generated values are always valid")
+    }
+}
+
+impl Iterator for Generator {
+    type Item = RESP;
+
+    fn next(&mut self) -> Option<RESP> {
+        Some(self.value())
+    }
+}
+
+/// Tokens worth splicing into fuzzer inputs: type bytes, line endings,
+/// special lengths and the values parsers treat differently.
+pub const DICTIONARY_TOKENS: &[&[u8]] = &[
+    b"+",
+    b"-",
+    b":",
+    b"$",
+    b"*",
+    b"_",
+    b"#",
+    b",",
+    b"(",
+    b"!",
+    b"=",
+    b"%",
+    b"~",
+    b">",
+    b"|",
+    b";",
+    b".",
+    b"\r\n",
+    b"\n",
+    b"$-1\r\n",
+    b"*-1\r\n",
+    b"$?\r\n",
+    b"*?\r\n",
+    b"%?\r\n",
+    b"~?\r\n",
+    b";0\r\n",
+    b".\r\n",
+    b"_\r\n",
+    b"#t\r\n",
+    b"#f\r\n",
+    b",inf\r\n",
+    b",-inf\r\n",
+    b",nan\r\n",
+    b"txt:",
+    b"mkd:",
+    b"0",
+    b"-1",
+    b"-2",
+    b"9223372036854775807",
+    b"-9223372036854775808",
+    b"18446744073709551616",
+    b"1e308",
+    b"HELLO 3\r\n",
+];
+
+/// An AFL/libFuzzer dictionary of [`DICTIONARY_TOKENS`], for the `-dict=`
+/// option of fuzzers exercising servers built on this crate.
+pub fn fuzz_dictionary() -> String {
+    let mut output = String::new();
+    for (index, token) in DICTIONARY_TOKENS.iter().enumerate() {
+        output.push_str(&format!("token_{index}=\""));
+        for &byte in *token {
+            match byte {
+                b'"' | b'\\' => output.push_str(&format!("\\{}", byte as char)),
+                b' '..=b'~' => output.push(byte as char),
+                _ => output.push_str(&format!("\\x{byte:02x}")),
+            }
+        }
+        output.push_str("\"\n");
+    }
+    output
+}
+
+/// How a [`Mutation`] corrupts a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// A length header off by one in either direction.
+    FlippedLength,
+    /// A length header far larger than any real payload.
+    OversizeLength,
+    /// A CRLF missing its LF.
+    TruncatedCrlf,
+    /// A type byte replaced by one that isn't part of the protocol.
+    WrongTypeByte,
+    /// The last byte of the frame dropped.
+    Truncated,
+}
+
+/// A corrupted copy of a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutation {
+    pub corruption: Corruption,
+    /// Position of the corrupted bytes in the original frame.
+    pub offset: usize,
+    pub frame: Vec<u8>,
+}
+
+fn splice(frame: &[u8], start: usize, end: usize, replacement: &[u8]) -> Vec<u8> {
+    [&frame[..start], replacement, &frame[end..]].concat()
+}
+
+/// Systematically corrupted variants of a valid encoded `frame`, applying
+/// every [`Corruption`] at every place it makes sense.
+pub fn mutations(frame: &[u8]) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+    let mut push = |corruption, offset, frame| {
+        mutations.push(Mutation {
+            corruption,
+            offset,
+            frame,
+        })
+    };
+
+    for token in tokenize(frame, 0) {
+        let (start, end) = (token.start, token.end);
+        match token.part {
+            Part::Type => push(
+                Corruption::WrongTypeByte,
+                start,
+                splice(frame, start, end, b"@"),
+            ),
+            Part::Length => {
+                let Some(length) = std::str::from_utf8(&frame[start..end])
+                    .ok()
+                    .and_then(|x| x.parse::<i64>().ok())
+                else {
+                    continue;
+                };
+                for flipped in [length - 1, length + 1] {
+                    let flipped = flipped.to_string();
+                    push(
+                        Corruption::FlippedLength,
+                        start,
+                        splice(frame, start, end, flipped.as_bytes()),
+                    );
+                }
+                let oversize = i64::MAX.to_string();
+                push(
+                    Corruption::OversizeLength,
+                    start,
+                    splice(frame, start, end, oversize.as_bytes()),
+                );
+            }
+            Part::Crlf => push(
+                Corruption::TruncatedCrlf,
+                start,
+                splice(frame, start + 1, end, b""),
+            ),
+            _ => {}
+        }
+    }
+    if let Some(last) = frame.len().checked_sub(1) {
+        push(Corruption::Truncated, last, frame[..last].to_vec());
+    }
+    mutations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseConfig;
+
+    #[test]
+    fn generate_deterministic() {
+        let first = Generator::new(42)
+            .take(20)
+            .map(|x| x.encode().unwrap())
+            .collect::<Vec<_>>();
+        let second = Generator::new(42)
+            .take(20)
+            .map(|x| x.encode().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(first, second);
+        let other = Generator::new(43)
+            .take(20)
+            .map(|x| x.encode().unwrap())
+            .collect::<Vec<_>>();
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn generate_valid_frames() {
+        let config = ParseConfig {
+            max_depth: 3,
+            ..Default::default()
+        };
+        for value in Generator::new(7).max_depth(3).take(2000) {
+            let frame = String::from_utf8(value.encode().unwrap()).unwrap();
+            assert_eq!(RESP::try_parse_with(&frame, &config), Ok(value));
+        }
+    }
+
+    #[test]
+    fn generate_resp2() {
+        for value in Generator::new(1).protocol(Protocol::Resp2).take(500) {
+            let mut stack = vec![&value];
+            while let Some(value) = stack.pop() {
+                match value {
+                    RESP::Array(x) => stack.extend(x),
+                    RESP::SimpleString(_)
+                    | RESP::SimpleError(_)
+                    | RESP::Integer(_)
+                    | RESP::BulkString(_)
+                    | RESP::NullBulkString
+                    | RESP::NullArray => {}
+                    x => panic!("not a RESP2 value: {x:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dictionary_format() {
+        let dictionary = fuzz_dictionary();
+        assert_eq!(dictionary.lines().count(), DICTIONARY_TOKENS.len());
+        assert!(dictionary.contains("=\"$-1\\x0d\\x0a\"\n"));
+        // Shipped alongside the fuzz targets
+        assert_eq!(dictionary, include_str!("../fuzz/resp.dict"));
+    }
+
+    #[test]
+    fn mutations_corrupt_frame() {
+        let frame = b"*2\r\n$5\r\nHello\r\n:1\r\n";
+        let original = RESP::parse_complete(std::str::from_utf8(frame).unwrap()).ok();
+        let mutations = mutations(frame);
+
+        let count = |corruption| {
+            mutations
+                .iter()
+                .filter(|x| x.corruption == corruption)
+                .count()
+        };
+        assert_eq!(count(Corruption::WrongTypeByte), 3);
+        assert_eq!(count(Corruption::FlippedLength), 4);
+        assert_eq!(count(Corruption::OversizeLength), 2);
+        assert_eq!(count(Corruption::TruncatedCrlf), 4);
+        assert_eq!(count(Corruption::Truncated), 1);
+
+        for mutation in &mutations {
+            assert_ne!(mutation.frame, frame);
+            let parsed = std::str::from_utf8(&mutation.frame)
+                .ok()
+                .and_then(|x| RESP::parse_complete(x).ok());
+            assert_ne!(parsed, original, "{mutation:?}");
+        }
+        assert_eq!(mutations[1].frame, b"*1\r\n$5\r\nHello\r\n:1\r\n");
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::{self, Write};
+
+use crate::RESP;
+
+/// Debug view of a [`RESP`] value truncated to about `max_bytes`, returned by
+/// [`RESP::inspect`], the same with both `{}` and `{:?}`.
+#[derive(Clone, Copy)]
+pub struct Inspect<'a> {
+    value: &'a RESP,
+    max_bytes: usize,
+}
+
+impl Inspect<'_> {
+    fn remaining(&self, output: &str) -> usize {
+        self.max_bytes.saturating_sub(output.len())
+    }
+
+    /// Writes `text` quoted, followed by how much of it was cut.
+    fn write_text(&self, output: &mut String, text: &str) {
+        let mut end = self.remaining(output).min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let _ = write!(output, "{:?}", &text[..end]);
+        if end < text.len() {
+            let _ = write!(output, "… (+{} more bytes)", text.len() - end);
+        }
+    }
+
+    fn write_tuple(&self, output: &mut String, name: &str, text: &str) {
+        output.push_str(name);
+        output.push('(');
+        self.write_text(output, text);
+        output.push(')');
+    }
+
+    /// Writes the items with `write`, stopping once the output is too long.
+    fn write_items<T>(
+        &self,
+        output: &mut String,
+        items: &[T],
+        mut write: impl FnMut(&mut String, &T),
+    ) {
+        output.push('[');
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                output.push_str(", ");
+                if self.remaining(output) == 0 {
+                    let _ = write!(output, "… (+{} more)", items.len() - index);
+                    break;
+                }
+            }
+            write(output, item);
+        }
+        output.push(']');
+    }
+
+    #[cfg(feature = "resp3")]
+    fn write_pairs(&self, output: &mut String, pairs: &[(RESP, RESP)]) {
+        self.write_items(output, pairs, |output, (key, value)| {
+            output.push('(');
+            self.write_value(output, key);
+            output.push_str(", ");
+            self.write_value(output, value);
+            output.push(')');
+        });
+    }
+
+    fn write_value(&self, output: &mut String, value: &RESP) {
+        let write_elements = |output: &mut String, name: &str, elements: &[RESP]| {
+            output.push_str(name);
+            output.push('(');
+            self.write_items(output, elements, |output, x| self.write_value(output, x));
+            output.push(')');
+        };
+        match value {
+            RESP::SimpleString(x) => self.write_tuple(output, "SimpleString", x),
+            RESP::SimpleError(x) => self.write_tuple(output, "SimpleError", x),
+            RESP::BulkString(x) => self.write_tuple(output, "BulkString", x),
+            #[cfg(feature = "resp3")]
+            RESP::BulkError(x) => self.write_tuple(output, "BulkError", x),
+            #[cfg(feature = "resp3")]
+            RESP::BigNumber(x) => self.write_tuple(output, "BigNumber", x),
+            #[cfg(feature = "resp3")]
+            RESP::VerbatimString { encoding, data } => {
+                let _ = write!(output, "VerbatimString {{ encoding: {encoding:?}, data: ");
+                self.write_text(output, data);
+                output.push_str(" }");
+            }
+            RESP::Array(x) => write_elements(output, "Array", x),
+            #[cfg(feature = "resp3")]
+            RESP::Set(x) => write_elements(output, "Set", x),
+            #[cfg(feature = "resp3")]
+            RESP::Push(x) => write_elements(output, "Push", x),
+            #[cfg(feature = "resp3")]
+            RESP::Map(x) => {
+                output.push_str("Map(");
+                self.write_pairs(output, x);
+                output.push(')');
+            }
+            RESP::Inline(x) => {
+                output.push_str("Inline(");
+                self.write_items(output, x, |output, x| self.write_text(output, x));
+                output.push(')');
+            }
+            #[cfg(feature = "resp3")]
+            RESP::WithAttributes { attributes, value } => {
+                output.push_str("WithAttributes { attributes: ");
+                self.write_pairs(output, attributes);
+                output.push_str(", value: ");
+                self.write_value(output, value);
+                output.push_str(" }");
+            }
+            x => {
+                let _ = write!(output, "{x:?}");
+            }
+        }
+    }
+}
+
+impl fmt::Display for Inspect<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = String::new();
+        self.write_value(&mut output, self.value);
+        f.write_str(&output)
+    }
+}
+
+impl fmt::Debug for Inspect<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl RESP {
+    /// Formats like [`Debug`](fmt::Debug), cutting strings and aggregates
+    /// once about `max_bytes` were written and noting how much was left out,
+    /// so huge frames can be logged safely.
+    pub fn inspect(&self, max_bytes: usize) -> Inspect<'_> {
+        Inspect {
+            value: self,
+            max_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn inspect_short() {
+        let value = RESP::parse("*3\r\n$3\r\nabc\r\n:1\r\n_\r\n").unwrap();
+        assert_eq!(value.inspect(200).to_string(), format!("{value:?}"));
+        let value = RESP::parse("|1\r\n+a\r\n:1\r\n=7\r\ntxt:abc\r\n").unwrap();
+        assert_eq!(format!("{:?}", value.inspect(200)), format!("{value:?}"));
+    }
+
+    #[test]
+    fn inspect_truncated() {
+        let value = RESP::BulkString("x".repeat(1000));
+        assert_eq!(
+            value.inspect(32).to_string(),
+            format!("BulkString(\"{}\"… (+979 more bytes))", "x".repeat(21))
+        );
+
+        let value = RESP::Array((0..1000).map(RESP::Integer).collect());
+        assert_eq!(
+            value.inspect(20).to_string(),
+            "Array([Integer(0), Integer(1), … (+998 more)])"
+        );
+    }
+
+    #[test]
+    fn inspect_char_boundary() {
+        let value = RESP::BulkString("éé".to_owned());
+        assert_eq!(
+            value.inspect(14).to_string(),
+            "BulkString(\"é\"… (+2 more bytes))"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::LazyLock;
+
+use crate::RESP;
+
+/// Declares a shared value for each reply along with its encoding, written
+/// as is by [`encoded`] instead of going through validation and formatting.
+macro_rules! interned {
+    ($($(#[$doc:meta])* $name:ident => $variant:ident($text:literal) = $encoded:literal;)*) => {
+        impl RESP {
+            $(
+                $(#[$doc])*
+                pub fn $name() -> &'static RESP {
+                    static VALUE: LazyLock<RESP> = LazyLock::new(|| RESP::$variant($text.into()));
+                    &VALUE
+                }
+            )*
+        }
+
+        /// Encoding of values equal to one of the interned ones.
+        pub(crate) fn encoded(value: &RESP) -> Option<&'static [u8]> {
+            match value {
+                $(RESP::$variant(x) if x.as_str() == $text => Some($encoded),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+interned! {
+    /// `+OK`, shared so replying with it doesn't allocate.
+    ok => SimpleString("OK") = b"+OK\r\n";
+    pong => SimpleString("PONG") = b"+PONG\r\n";
+    /// `+QUEUED`, the reply to commands sent inside `MULTI`.
+    queued => SimpleString("QUEUED") = b"+QUEUED\r\n";
+    syntax_error => SimpleError("ERR syntax error") = b"-ERR syntax error\r\n";
+    wrong_type => SimpleError("WRONGTYPE Operation against a key holding the wrong kind of value")
+        = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+    no_auth => SimpleError("NOAUTH Authentication required.")
+        = b"-NOAUTH Authentication required.\r\n";
+}
+
+/// Errors whose text depends on the request, worded like Redis does.
+impl RESP {
+    pub fn unknown_command(name: &str) -> Self {
+        Self::simple_error(format!("ERR unknown command '{}'", sanitize(name)))
+    }
+
+    pub fn wrong_arity(name: &str) -> Self {
+        Self::simple_error(format!(
+            "ERR wrong number of arguments for '{}' command",
+            sanitize(name)
+        ))
+    }
+}
+
+/// `text` with line breaks replaced, as it ends up in a simple error.
+fn sanitize(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_shared() {
+        assert!(std::ptr::eq(RESP::ok(), RESP::ok()));
+        assert_eq!(RESP::queued(), &RESP::SimpleString("QUEUED".into()));
+    }
+
+    #[test]
+    fn interned_encoding() {
+        for value in [
+            RESP::ok(),
+            RESP::pong(),
+            RESP::queued(),
+            RESP::syntax_error(),
+            RESP::wrong_type(),
+            RESP::no_auth(),
+        ] {
+            let mut formatted = Vec::new();
+            value.write_value(&mut formatted).unwrap();
+            assert_eq!(encoded(value), Some(&formatted[..]));
+            assert_eq!(value.encode().unwrap(), formatted);
+        }
+        assert_eq!(encoded(&RESP::SimpleString("OKAY".into())), None);
+    }
+
+    #[test]
+    fn error_templates() {
+        assert_eq!(
+            RESP::unknown_command("FOO\r\n+x").encode().unwrap(),
+            b"-ERR unknown command 'FOO  +x'\r\n"
+        );
+        assert_eq!(
+            RESP::wrong_arity("get"),
+            RESP::SimpleError("ERR wrong number of arguments for 'get' command".into())
+        );
+    }
+}
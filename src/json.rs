@@ -0,0 +1,237 @@
+use std::fmt::Write;
+
+use crate::{ConversionError, RESP};
+
+/// How map keys that aren't strings are turned into JSON, as object keys
+/// have to be strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapKeys {
+    /// Use the JSON text of the key, e.g. `"1"` for an integer.
+    #[default]
+    Stringify,
+    /// Fail the conversion.
+    Error,
+    /// Write such maps as arrays of `[key, value]` pairs instead of objects.
+    Pairs,
+}
+
+pub(crate) fn write_string(output: &mut String, text: &str) {
+    output.push('"');
+    for x in text.chars() {
+        match x {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            x if x.is_control() => {
+                let _ = write!(output, "\\u{:04x}", x as u32);
+            }
+            x => output.push(x),
+        }
+    }
+    output.push('"');
+}
+
+/// Errors become objects, so they can't be mistaken for strings.
+fn write_error(output: &mut String, message: &str) {
+    output.push_str("{\"error\":");
+    write_string(output, message);
+    output.push('}');
+}
+
+/// Text of values that are already strings in JSON.
+fn as_text(value: &RESP) -> Option<&str> {
+    match value {
+        RESP::SimpleString(x) => Some(x),
+        RESP::BulkString(x) => Some(x),
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(x) => Some(x),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { data, .. } => Some(data),
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { value, .. } => as_text(value),
+        _ => None,
+    }
+}
+
+fn write_elements(
+    output: &mut String,
+    elements: &[RESP],
+    keys: MapKeys,
+) -> Result<(), ConversionError> {
+    output.push('[');
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        write_value(output, element, keys)?;
+    }
+    output.push(']');
+    Ok(())
+}
+
+#[cfg(feature = "resp3")]
+fn write_map(
+    output: &mut String,
+    entries: &[(RESP, RESP)],
+    keys: MapKeys,
+) -> Result<(), ConversionError> {
+    let stringly = entries.iter().all(|(key, _)| as_text(key).is_some());
+    if !stringly && keys == MapKeys::Pairs {
+        output.push('[');
+        for (index, (key, value)) in entries.iter().enumerate() {
+            if index > 0 {
+                output.push(',');
+            }
+            output.push('[');
+            write_value(output, key, keys)?;
+            output.push(',');
+            write_value(output, value, keys)?;
+            output.push(']');
+        }
+        output.push(']');
+        return Ok(());
+    }
+
+    output.push('{');
+    for (index, (key, value)) in entries.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        match as_text(key) {
+            Some(text) => write_string(output, text),
+            None if keys == MapKeys::Error => {
+                return Err(ConversionError::unexpected_type("string map key", key));
+            }
+            None => {
+                let mut text = String::new();
+                write_value(&mut text, key, keys)?;
+                write_string(output, &text);
+            }
+        }
+        output.push(':');
+        write_value(output, value, keys)?;
+    }
+    output.push('}');
+    Ok(())
+}
+
+fn write_value(output: &mut String, value: &RESP, keys: MapKeys) -> Result<(), ConversionError> {
+    match value {
+        RESP::SimpleString(_) | RESP::BulkString(_) => {
+            write_string(output, as_text(value).unwrap_or_default())
+        }
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(_) | RESP::VerbatimString { .. } => {
+            write_string(output, as_text(value).unwrap_or_default())
+        }
+        RESP::SimpleError(x) => write_error(output, x),
+        #[cfg(feature = "resp3")]
+        RESP::BulkError(x) => write_error(output, x),
+        RESP::Integer(x) => {
+            let _ = write!(output, "{x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) if x.is_finite() => {
+            let _ = write!(output, "{x}");
+        }
+        // JSON has no infinities or NaN
+        #[cfg(feature = "resp3")]
+        RESP::Double(_) => output.push_str("null"),
+        #[cfg(feature = "rust_decimal")]
+        RESP::Decimal(x) => {
+            let _ = write!(output, "{x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Boolean(x) => {
+            let _ = write!(output, "{x}");
+        }
+        RESP::NullBulkString | RESP::NullArray => output.push_str("null"),
+        #[cfg(feature = "resp3")]
+        RESP::Null => output.push_str("null"),
+        RESP::Array(x) => write_elements(output, x, keys)?,
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) | RESP::Push(x) => write_elements(output, x, keys)?,
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => write_map(output, x, keys)?,
+        RESP::Inline(x) => {
+            output.push('[');
+            for (index, argument) in x.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_string(output, argument);
+            }
+            output.push(']');
+        }
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { value, .. } => write_value(output, value, keys)?,
+    }
+    Ok(())
+}
+
+impl RESP {
+    /// Renders the value as JSON for debugging and logs, with non-string map
+    /// keys stringified. See [`to_json_with`](Self::to_json_with) for the
+    /// mapping.
+    pub fn to_json_string(&self) -> String {
+        self.to_json_with(MapKeys::Stringify)
+            .expect("stringified map keys never fail")
+    }
+
+    /// Renders the value as JSON, dropping attributes. Maps become objects
+    /// with non-string keys handled according to `keys`.
+    pub fn to_json_with(&self, keys: MapKeys) -> Result<String, ConversionError> {
+        let mut output = String::new();
+        write_value(&mut output, self, keys)?;
+        Ok(output)
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    fn map() -> RESP {
+        RESP::parse("%2\r\n+name\r\n$3\r\nbob\r\n:1\r\n#t\r\n").unwrap()
+    }
+
+    #[test]
+    fn json_map_keys() {
+        assert_eq!(
+            map().to_json_with(MapKeys::Stringify),
+            Ok("{\"name\":\"bob\",\"1\":true}".to_owned())
+        );
+        assert_eq!(
+            map().to_json_with(MapKeys::Pairs),
+            Ok("[[\"name\",\"bob\"],[1,true]]".to_owned())
+        );
+        assert!(matches!(
+            map().to_json_with(MapKeys::Error),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+
+        let strings = RESP::parse("%1\r\n+a\r\n:1\r\n").unwrap();
+        assert_eq!(
+            strings.to_json_with(MapKeys::Pairs),
+            Ok("{\"a\":1}".to_owned())
+        );
+    }
+
+    #[test]
+    fn json_values() {
+        let parsed = RESP::parse("*5\r\n$2\r\na\"\r\n_\r\n,1.5\r\n,inf\r\n-ERR x\r\n").unwrap();
+        assert_eq!(
+            parsed.to_json_with(MapKeys::default()),
+            Ok("[\"a\\\"\",null,1.5,null,{\"error\":\"ERR x\"}]".to_owned())
+        );
+    }
+
+    #[test]
+    fn json_string() {
+        assert_eq!(map().to_json_string(), "{\"name\":\"bob\",\"1\":true}");
+        let parsed = RESP::parse("|1\r\n+ttl\r\n:3\r\n*2\r\n$3\r\na\tb\r\n(123\r\n").unwrap();
+        assert_eq!(parsed.to_json_string(), "[\"a\\tb\",\"123\"]");
+    }
+}
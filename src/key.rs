@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+#[cfg(feature = "resp3")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::{ConversionError, RESP};
+
+/// A [`RESP`] value usable as a key of hash maps and sets or for canonical
+/// comparisons.
+///
+/// Values are normalized: simple, bulk and verbatim strings are all
+/// [`String`](Self::String), big numbers fitting an `i64` are
+/// [`Integer`](Self::Integer), and doubles are compared by their total order
+/// with `-0.0` made `0.0` and NaNs made the same NaN.
+#[derive(Debug, Clone)]
+pub enum RespKey {
+    String(String),
+    Integer(i64),
+    /// Digits of a big number outside of the `i64` range.
+    #[cfg(feature = "resp3")]
+    BigNumber(String),
+    #[cfg(feature = "resp3")]
+    Boolean(bool),
+    #[cfg(feature = "resp3")]
+    Double(f64),
+}
+
+impl RespKey {
+    fn rank(&self) -> u8 {
+        match self {
+            Self::String(_) => 0,
+            Self::Integer(_) => 1,
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(_) => 2,
+            #[cfg(feature = "resp3")]
+            Self::Boolean(_) => 3,
+            #[cfg(feature = "resp3")]
+            Self::Double(_) => 4,
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    fn double(value: f64) -> Self {
+        Self::Double(match value {
+            x if x.is_nan() => f64::NAN,
+            // Also matches -0.0
+            0.0 => 0.0,
+            x => x,
+        })
+    }
+}
+
+impl PartialEq for RespKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RespKey {}
+
+impl PartialOrd for RespKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RespKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::String(x), Self::String(y)) => x.cmp(y),
+            #[cfg(feature = "resp3")]
+            (Self::BigNumber(x), Self::BigNumber(y)) => x.cmp(y),
+            (Self::Integer(x), Self::Integer(y)) => x.cmp(y),
+            #[cfg(feature = "resp3")]
+            (Self::Boolean(x), Self::Boolean(y)) => x.cmp(y),
+            #[cfg(feature = "resp3")]
+            (Self::Double(x), Self::Double(y)) => x.total_cmp(y),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for RespKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Self::String(x) => x.hash(state),
+            #[cfg(feature = "resp3")]
+            Self::BigNumber(x) => x.hash(state),
+            Self::Integer(x) => x.hash(state),
+            #[cfg(feature = "resp3")]
+            Self::Boolean(x) => x.hash(state),
+            #[cfg(feature = "resp3")]
+            Self::Double(x) => x.to_bits().hash(state),
+        }
+    }
+}
+
+impl TryFrom<&RESP> for RespKey {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, ConversionError> {
+        Ok(match value {
+            RESP::SimpleString(x) => Self::String(x.to_string()),
+            RESP::BulkString(x) => Self::String(x.clone()),
+            #[cfg(feature = "resp3")]
+            RESP::VerbatimString { data, .. } => Self::String(data.clone()),
+            RESP::Integer(x) => Self::Integer(*x),
+            #[cfg(feature = "resp3")]
+            RESP::BigNumber(x) => match x.parse() {
+                Ok(x) => Self::Integer(x),
+                Err(_) => Self::BigNumber(x.clone()),
+            },
+            #[cfg(feature = "resp3")]
+            RESP::Boolean(x) => Self::Boolean(*x),
+            #[cfg(feature = "resp3")]
+            RESP::Double(x) => Self::double(*x),
+            #[cfg(feature = "resp3")]
+            RESP::WithAttributes { value, .. } => Self::try_from(value.as_ref())?,
+            _ => return Err(ConversionError::unexpected_type("hashable key", value)),
+        })
+    }
+}
+
+impl From<RespKey> for RESP {
+    fn from(value: RespKey) -> Self {
+        match value {
+            RespKey::String(x) => Self::BulkString(x),
+            RespKey::Integer(x) => Self::Integer(x),
+            #[cfg(feature = "resp3")]
+            RespKey::BigNumber(x) => Self::BigNumber(x),
+            #[cfg(feature = "resp3")]
+            RespKey::Boolean(x) => Self::Boolean(x),
+            #[cfg(feature = "resp3")]
+            RespKey::Double(x) => Self::Double(x),
+        }
+    }
+}
+
+impl RESP {
+    /// Converts a map into a [`HashMap`], later duplicates of a key replacing
+    /// earlier ones.
+    #[cfg(feature = "resp3")]
+    pub fn into_hash_map(self) -> Result<HashMap<RespKey, RESP>, ConversionError> {
+        let value = match self {
+            Self::WithAttributes { value, .. } => *value,
+            x => x,
+        };
+        match value {
+            Self::Map(entries) => entries
+                .into_iter()
+                .map(|(key, value)| Ok((RespKey::try_from(&key)?, value)))
+                .collect(),
+            x => Err(ConversionError::unexpected_type("map", &x)),
+        }
+    }
+
+    /// Converts a set, or an array, into a [`HashSet`].
+    pub fn to_hash_set(&self) -> Result<HashSet<RespKey>, ConversionError> {
+        match self {
+            Self::Array(x) => x.iter().map(RespKey::try_from).collect(),
+            #[cfg(feature = "resp3")]
+            Self::Set(x) => x.iter().map(RespKey::try_from).collect(),
+            #[cfg(feature = "resp3")]
+            Self::WithAttributes { value, .. } => value.to_hash_set(),
+            x => Err(ConversionError::unexpected_type("set", x)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_normalized() {
+        let key = |data: &str| RespKey::try_from(&RESP::parse(data).unwrap()).unwrap();
+        assert_eq!(key("+a\r\n"), key("$1\r\na\r\n"));
+        assert_eq!(key("+a\r\n"), key("=5\r\ntxt:a\r\n"));
+        assert_eq!(key(":12\r\n"), key("(12\r\n"));
+        assert_eq!(key(",-0\r\n"), key(",0\r\n"));
+        assert_eq!(key(",nan\r\n"), key(",nan\r\n"));
+        assert_ne!(key(":1\r\n"), key("+1\r\n"));
+        assert_ne!(key(",1\r\n"), key(":1\r\n"));
+        assert!(key(":1\r\n") < key(":2\r\n"));
+        assert!(matches!(
+            RespKey::try_from(&RESP::Array(vec![])),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn key_hash_map() {
+        let map = RESP::parse("%3\r\n+a\r\n:1\r\n:2\r\n:2\r\n$1\r\na\r\n:3\r\n")
+            .unwrap()
+            .into_hash_map()
+            .unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&RespKey::String("a".to_owned())], RESP::Integer(3));
+        assert_eq!(map[&RespKey::Integer(2)], RESP::Integer(2));
+
+        let set = RESP::parse("~3\r\n:1\r\n(1\r\n#t\r\n")
+            .unwrap()
+            .to_hash_set()
+            .unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&RespKey::Boolean(true)));
+    }
+}
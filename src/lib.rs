@@ -1,5 +1,98 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
 use std::str::{Chars, FromStr};
 
+// Derived implementations name this crate by its path
+#[cfg(feature = "derive")]
+extern crate self as resp_parser_rs;
+
+mod access;
+#[cfg(feature = "lru")]
+mod cache;
+pub mod capture;
+#[cfg(feature = "winnow")]
+pub mod combinators;
+#[cfg(feature = "bytes")]
+mod command;
+mod config;
+mod convert;
+#[cfg(feature = "serde")]
+pub mod de;
+mod decoder;
+mod detect;
+#[cfg(feature = "miette")]
+mod diagnostic;
+mod dump;
+pub mod encode;
+mod error;
+pub mod expect;
+#[cfg(feature = "defmt")]
+mod format;
+pub mod framing;
+pub mod generate;
+mod inspect;
+mod interned;
+mod json;
+mod key;
+mod literal;
+#[cfg(feature = "memmap2")]
+mod mmap;
+mod paginate;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "pcap")]
+pub mod pcap;
+mod reader;
+mod redact;
+mod relay;
+pub mod replies;
+#[cfg(feature = "futures")]
+mod sink;
+mod span;
+#[cfg(feature = "metrics")]
+mod telemetry;
+pub mod testing;
+mod tree;
+mod writer;
+mod yaml;
+
+#[cfg(feature = "lru")]
+pub use cache::{FrameCache, DEFAULT_MAX_CACHED_FRAME_LEN};
+#[cfg(feature = "bytes")]
+pub use command::Command;
+pub use config::{
+    DuplicateKeys, ParseConfig, Protocol, Side, DEFAULT_MAX_BIG_NUMBER_DIGITS, DEFAULT_MAX_DEPTH,
+};
+pub use convert::{to_writer, Coercion, FromResp, ToResp};
+#[cfg(feature = "bytes")]
+pub use decoder::Frame;
+pub use decoder::{Decoded, Decoder, MemoryUsage, StallLimits};
+pub use dump::hex_dump;
+use error::preview;
+pub use error::{ConversionError, DecodeError, EncodeError, ParseError, ReadError};
+pub use framing::RawFrames;
+pub use inspect::Inspect;
+pub use json::MapKeys;
+pub use key::RespKey;
+#[cfg(feature = "memmap2")]
+pub use mmap::MappedFile;
+pub use paginate::Paging;
+#[cfg(feature = "parallel")]
+pub use parallel::decode_parallel;
+#[cfg(feature = "futures")]
+pub use reader::{from_async_reader, AsyncFrameReader};
+pub use reader::{from_reader, FrameReader, Timeouts};
+pub use relay::relay;
+#[cfg(feature = "futures")]
+pub use relay::relay_async;
+pub use resp_parser_rs_core::events;
+#[cfg(feature = "derive")]
+pub use resp_parser_rs_derive::{ReplyEnum, ToCommand};
+#[cfg(feature = "futures")]
+pub use sink::{FrameSink, DEFAULT_SINK_CAPACITY};
+pub use span::{Spanned, SpannedNode};
+pub use writer::{FrameWriter, ReplyWriter, DEFAULT_WRITER_CAPACITY};
+
 const SIMPLE_STRING: char = '+';
 const SIMPLE_ERROR: char = '-';
 const INTEGER: char = ':';
@@ -14,253 +107,746 @@ const VERBATIM_STRING: char = '=';
 const MAP: char = '%';
 const SET: char = '~';
 const PUSH: char = '>';
-
-#[derive(Debug)]
+const ATTRIBUTE: char = '|';
+/// Ends streamed aggregates, whose length header is `?`.
+#[cfg(feature = "resp3")]
+const END: char = '.';
+
+/// Count of elements expected by streamed aggregates, which instead end
+/// with [`END`].
+#[cfg(feature = "resp3")]
+const STREAMED: usize = usize::MAX;
+
+/// Smallest possible encoded value, `_\r\n`.
+const MIN_FRAME_LEN: usize = 3;
+/// Upper bound on elements preallocated from a length header.
+const MAX_PREALLOCATION: usize = 4096;
+
+/// String type of short textual values such as simple strings, stored inline
+/// when the `compact_str` feature is enabled.
+#[cfg(not(feature = "compact_str"))]
+pub type ShortString = String;
+#[cfg(feature = "compact_str")]
+pub type ShortString = compact_str::CompactString;
+
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum RESP {
-    SimpleString(String),
-    SimpleError(String),
+    SimpleString(ShortString),
+    SimpleError(ShortString),
     Integer(i64),
     BulkString(String),
     NullBulkString,
     Array(Vec<RESP>),
     NullArray,
+    #[cfg(feature = "resp3")]
     Null,
+    #[cfg(feature = "resp3")]
     Boolean(bool),
+    #[cfg(feature = "resp3")]
     Double(f64),
+    #[cfg(feature = "rust_decimal")]
+    Decimal(rust_decimal::Decimal),
+    #[cfg(feature = "resp3")]
     BigNumber(String),
+    #[cfg(feature = "resp3")]
     BulkError(String),
-    VerbatimString { encoding: String, data: String },
+    #[cfg(feature = "resp3")]
+    VerbatimString {
+        encoding: ShortString,
+        data: String,
+    },
+    #[cfg(feature = "resp3")]
     Map(Vec<(RESP, RESP)>),
+    #[cfg(feature = "resp3")]
     Set(Vec<RESP>),
+    #[cfg(feature = "resp3")]
     Push(Vec<RESP>),
     Inline(Vec<String>),
+    #[cfg(feature = "resp3")]
+    WithAttributes {
+        attributes: Vec<(RESP, RESP)>,
+        value: Box<RESP>,
+    },
+}
+
+/// Aggregate whose elements are still being parsed.
+enum Pending {
+    /// Array, set or push waiting for `expected` elements.
+    Elements {
+        kind: char,
+        expected: usize,
+        items: Vec<RESP>,
+    },
+    /// Map or attribute waiting for `expected` pairs.
+    #[cfg(feature = "resp3")]
+    Pairs {
+        kind: char,
+        expected: usize,
+        items: Vec<(RESP, RESP)>,
+        key: Option<RESP>,
+        internal: bool,
+    },
+    /// Attributes waiting for the value they annotate.
+    #[cfg(feature = "resp3")]
+    Attributed {
+        attributes: Vec<(RESP, RESP)>,
+        internal: bool,
+    },
+}
+
+enum Step {
+    Value(RESP),
+    Open(Pending),
+}
+
+/// Aggregate of `kind` holding `items`.
+fn aggregate(kind: char, items: Vec<RESP>) -> RESP {
+    match kind {
+        #[cfg(feature = "resp3")]
+        SET => RESP::Set(items),
+        #[cfg(feature = "resp3")]
+        PUSH => RESP::Push(items),
+        _ => RESP::Array(items),
+    }
+}
+
+impl Pending {
+    /// Whether this is a streamed aggregate, waiting for [`END`].
+    #[cfg(feature = "resp3")]
+    fn streamed(&self) -> bool {
+        matches!(
+            self,
+            Self::Elements {
+                expected: STREAMED,
+                ..
+            } | Self::Pairs {
+                expected: STREAMED,
+                ..
+            }
+        )
+    }
+
+    /// Finishes a streamed aggregate on reading [`END`].
+    #[cfg(feature = "resp3")]
+    fn finish(self, config: &ParseConfig) -> Result<RESP, ParseError> {
+        match self {
+            Self::Elements { kind, items, .. } => Ok(aggregate(kind, items)),
+            Self::Pairs {
+                items, key: None, ..
+            } => Ok(RESP::Map(RESP::dedup_map(items, config.duplicate_keys)?)),
+            _ => Err(ParseError::new("map value", "end of the streamed map")),
+        }
+    }
+
+    /// Whether values parsed next are inside an aggregate.
+    fn internal(&self) -> bool {
+        match self {
+            #[cfg(feature = "resp3")]
+            Self::Attributed { internal, .. } => *internal,
+            _ => true,
+        }
+    }
+
+    #[cfg_attr(not(feature = "resp3"), allow(unused_variables))]
+    fn add(self, value: RESP, config: &ParseConfig) -> Result<Step, ParseError> {
+        match self {
+            Self::Elements {
+                kind,
+                expected,
+                mut items,
+            } => {
+                items.push(value);
+                if items.len() < expected {
+                    Ok(Step::Open(Self::Elements {
+                        kind,
+                        expected,
+                        items,
+                    }))
+                } else {
+                    Ok(Step::Value(aggregate(kind, items)))
+                }
+            }
+            #[cfg(feature = "resp3")]
+            Self::Pairs {
+                kind,
+                expected,
+                items,
+                key: None,
+                internal,
+            } => Ok(Step::Open(Self::Pairs {
+                kind,
+                expected,
+                items,
+                key: Some(value),
+                internal,
+            })),
+            #[cfg(feature = "resp3")]
+            Self::Pairs {
+                kind,
+                expected,
+                mut items,
+                key: Some(key),
+                internal,
+            } => {
+                items.push((key, value));
+                if items.len() < expected {
+                    Ok(Step::Open(Self::Pairs {
+                        kind,
+                        expected,
+                        items,
+                        key: None,
+                        internal,
+                    }))
+                } else if kind == ATTRIBUTE {
+                    Ok(Step::Open(Self::Attributed {
+                        attributes: RESP::dedup_map(items, config.duplicate_keys)?,
+                        internal,
+                    }))
+                } else {
+                    Ok(Step::Value(RESP::Map(RESP::dedup_map(
+                        items,
+                        config.duplicate_keys,
+                    )?)))
+                }
+            }
+            #[cfg(feature = "resp3")]
+            Self::Attributed { attributes, .. } => Ok(Step::Value(RESP::WithAttributes {
+                attributes,
+                value: Box::new(value),
+            })),
+        }
+    }
 }
 
 impl RESP {
-    fn parse_until(bytes: &mut Chars, stop: &str) -> Option<String> {
+    /// Borrows the rest of the current line, consuming it and its CRLF
+    /// without allocating.
+    fn parse_line<'a>(bytes: &mut Chars<'a>) -> Result<&'a str, ParseError> {
+        let rest = bytes.as_str();
+        let Some(end) = rest.find(['\r', '\n']) else {
+            *bytes = rest[rest.len()..].chars();
+            return Err(ParseError::end_of_input(format!("{:?}", "\r\n")));
+        };
+        *bytes = rest[end..].chars();
+        match (bytes.next(), bytes.next()) {
+            (Some('\r'), Some('\n')) => Ok(&rest[..end]),
+            (Some('\r'), Some(x)) | (Some(x @ '\n'), _) => {
+                Err(ParseError::new(format!("{:?}", "\r\n"), format!("{x:?}")))
+            }
+            _ => Err(ParseError::end_of_input(format!("{:?}", "\r\n"))),
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    fn parse_until_max(bytes: &mut Chars, stop: &str, max: usize) -> Result<String, ParseError> {
         let mut data = String::new();
         while let Some(x) = bytes.next() {
             if !stop.contains(x) {
+                if data.len() >= max {
+                    return Err(ParseError::new(
+                        format!("at most {max} bytes before {stop:?}"),
+                        format!("{}…", preview(&data)),
+                    ));
+                }
                 data.push(x);
             } else {
                 let mut stop_chars = stop.chars();
-                if x == stop_chars.next()? {
+                if Some(x) == stop_chars.next() {
                     for stop_char in stop_chars {
-                        if bytes.next()? == stop_char {
-                            continue;
-                        } else {
-                            return None;
+                        match bytes.next() {
+                            Some(x) if x == stop_char => continue,
+                            Some(x) => {
+                                return Err(ParseError::new(format!("{stop:?}"), format!("{x:?}")))
+                            }
+                            None => return Err(ParseError::end_of_input(format!("{stop:?}"))),
                         }
                     }
-                    return Some(data);
+                    return Ok(data);
                 } else {
-                    return None;
+                    return Err(ParseError::new(format!("{stop:?}"), format!("{x:?}")));
                 }
             }
         }
-        None
+        Err(ParseError::end_of_input(format!("{stop:?}")))
     }
 
-    fn parse_inline(initial: char, bytes: &mut Chars) -> Option<Vec<String>> {
-        let mut data = bytes.collect::<String>();
-        data.insert(0, initial);
-
-        let data = data
+    /// Splits the current line into arguments, consuming it and its line
+    /// break so the frames pipelined after it are left untouched. Inline
+    /// commands may end with a bare LF, or with the input.
+    #[cfg(feature = "inline")]
+    fn parse_inline(initial: char, bytes: &mut Chars) -> Result<Vec<String>, ParseError> {
+        let rest = bytes.as_str();
+        let (line, next) = match rest.find('\n') {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, &rest[rest.len()..]),
+        };
+        *bytes = next.chars();
+
+        let mut data = line
             .split_whitespace()
-            .filter(|x| !x.is_empty())
             .map(ToOwned::to_owned)
             .collect::<Vec<String>>();
+        // The first character was already consumed from the line
+        if !initial.is_whitespace() {
+            if line.starts_with(|x: char| !x.is_whitespace()) {
+                data[0].insert(0, initial);
+            } else {
+                data.insert(0, initial.to_string());
+            }
+        }
 
         if data.is_empty() {
-            None
+            Err(ParseError::new("inline command", "blank line"))
         } else {
-            Some(data)
+            Ok(data)
         }
     }
 
-    fn parse_simple(bytes: &mut Chars) -> Option<String> {
-        Self::parse_until(bytes, "\r\n")
+    fn parse_simple(bytes: &mut Chars) -> Result<String, ParseError> {
+        Self::parse_line(bytes).map(ToOwned::to_owned)
+    }
+
+    // Only an identity conversion when compact_str is disabled
+    #[allow(clippy::useless_conversion)]
+    fn parse_short(bytes: &mut Chars) -> Result<ShortString, ParseError> {
+        Ok(Self::parse_simple(bytes)?.into())
     }
 
-    fn parse_number<T>(bytes: &mut Chars) -> Option<T>
+    fn parse_number<T>(bytes: &mut Chars, expected: &'static str) -> Result<T, ParseError>
     where
         T: FromStr,
     {
-        Self::parse_simple(bytes)?.parse::<T>().ok()
+        let data = Self::parse_line(bytes)?;
+        data.parse::<T>()
+            .map_err(|_| ParseError::new(expected, preview(data)))
     }
 
-    fn parse_big_number(bytes: &mut Chars) -> Option<String> {
-        let data = Self::parse_simple(bytes)?;
-        let mut chars = data.chars();
-        let first = chars.next()?;
-        if !(first == '+' || first == '-' || first.is_ascii_digit())
-            || !chars.all(|c| c.is_ascii_digit())
-        {
-            None
+    /// Capacity worth reserving for `length` elements of at least `min_len`
+    /// bytes each, trusting the header only as far as the remaining input and
+    /// [`MAX_PREALLOCATION`] allow.
+    fn capacity(length: isize, bytes: &Chars, min_len: usize) -> usize {
+        usize::try_from(length)
+            .unwrap_or(0)
+            .min(bytes.as_str().len() / min_len)
+            .min(MAX_PREALLOCATION)
+    }
+
+    fn check_length(length: isize, min: isize) -> Result<usize, ParseError> {
+        if length < min {
+            Err(ParseError::new(
+                format!("length of at least {min}"),
+                length.to_string(),
+            ))
         } else {
-            if let Some(data) = data.strip_prefix("+") {
-                Some(data.to_owned())
-            } else {
-                Some(data)
-            }
+            Ok(length as usize)
         }
     }
 
-    fn parse_array(bytes: &mut Chars) -> Option<(isize, Vec<RESP>)> {
-        let length = Self::parse_number::<isize>(bytes)?;
-        let mut data = Vec::new();
-        for _ in 0..length {
-            data.push(Self::parse_internal(bytes, true)?)
+    #[cfg(feature = "rust_decimal")]
+    fn parse_decimal(bytes: &mut Chars) -> Result<Self, ParseError> {
+        let data = Self::parse_line(bytes)?;
+        let unsigned = data.trim_start_matches(['+', '-']);
+        let invalid = || ParseError::new("decimal", preview(data));
+        if unsigned == "inf" || unsigned == "nan" {
+            Ok(Self::Double(data.parse().map_err(|_| invalid())?))
+        } else if data.contains(['e', 'E']) {
+            Ok(Self::Decimal(
+                rust_decimal::Decimal::from_scientific(data).map_err(|_| invalid())?,
+            ))
+        } else {
+            Ok(Self::Decimal(data.parse().map_err(|_| invalid())?))
         }
-        Some((length, data))
     }
 
-    fn parse_map(bytes: &mut Chars) -> Option<(isize, Vec<(RESP, RESP)>)> {
-        let length = Self::parse_number::<isize>(bytes)?;
-        let mut data = Vec::new();
-        for _ in 0..length {
-            data.push((
-                Self::parse_internal(bytes, true)?,
-                Self::parse_internal(bytes, true)?,
+    #[cfg(feature = "resp3")]
+    fn parse_big_number(bytes: &mut Chars, max_digits: usize) -> Result<String, ParseError> {
+        // Leave room for the sign so the limit only applies to the digits
+        let data = Self::parse_until_max(bytes, "\r\n", max_digits.saturating_add(1))?;
+        let mut chars = data.chars();
+        let valid = match chars.next() {
+            Some(first) => {
+                (first == '+' || first == '-' || first.is_ascii_digit())
+                    && chars.all(|c| c.is_ascii_digit())
+            }
+            None => false,
+        };
+        if !valid {
+            Err(ParseError::new("big number", preview(&data)))
+        } else if data.trim_start_matches(['+', '-']).len() > max_digits {
+            Err(ParseError::new(
+                format!("at most {max_digits} digits"),
+                format!("{}…", preview(&data)),
             ))
+        } else if let Some(data) = data.strip_prefix("+") {
+            Ok(data.to_owned())
+        } else {
+            Ok(data)
         }
-        Some((length, data))
     }
 
-    fn parse_bulk(bytes: &mut Chars) -> Option<(isize, String)> {
-        let length = Self::parse_number::<isize>(bytes)?;
-        if length == -1 {
-            Some((length, String::new()))
-        } else {
-            let data = Self::parse_simple(bytes)?;
-            Some((length, data))
+    #[cfg(feature = "resp3")]
+    fn dedup_map(
+        data: Vec<(RESP, RESP)>,
+        policy: DuplicateKeys,
+    ) -> Result<Vec<(RESP, RESP)>, ParseError> {
+        if policy == DuplicateKeys::KeepAll {
+            return Ok(data);
         }
+        use std::collections::hash_map::{Entry, HashMap};
+
+        // Position of every key in `map`, keys that can't be a `RespKey`
+        // being indexed by their encoding
+        let mut positions: HashMap<Result<RespKey, Vec<u8>>, usize> =
+            HashMap::with_capacity(data.len());
+        let mut map: Vec<(RESP, RESP)> = Vec::with_capacity(data.len());
+        for (key, value) in data {
+            let normalized = RespKey::try_from(&key).map_err(|_| {
+                let mut encoded = Vec::new();
+                let _ = key.write_value(&mut encoded);
+                encoded
+            });
+            match (positions.entry(normalized), policy) {
+                (Entry::Vacant(entry), _) => {
+                    entry.insert(map.len());
+                    map.push((key, value));
+                }
+                (Entry::Occupied(_), DuplicateKeys::FirstWins) => {}
+                (Entry::Occupied(entry), DuplicateKeys::LastWins) => map[*entry.get()].1 = value,
+                (Entry::Occupied(_), _) => {
+                    return Err(ParseError::new(
+                        "unique map keys",
+                        format!("duplicate {}", key.describe()),
+                    ))
+                }
+            }
+        }
+        Ok(map)
     }
 
-    fn parse_internal(bytes: &mut Chars, internal: bool) -> Option<Self> {
-        match bytes.next()? {
-            SIMPLE_STRING => Some(Self::SimpleString(Self::parse_simple(bytes)?)),
-            SIMPLE_ERROR => Some(Self::SimpleError(Self::parse_simple(bytes)?)),
-            INTEGER => Some(Self::Integer(Self::parse_number(bytes)?)),
-            BULK_STRING => {
-                let (length, data) = Self::parse_bulk(bytes)?;
-                if length < -1 {
-                    None
-                } else if length == -1 {
-                    Some(RESP::NullBulkString)
-                } else if length as usize != data.len() {
-                    None
-                } else {
-                    Some(RESP::BulkString(data))
+    fn parse_bulk(bytes: &mut Chars, min: isize) -> Result<(isize, String), ParseError> {
+        let length = Self::parse_number::<isize>(bytes, "length")?;
+        if length == -1 && min == -1 {
+            Ok((length, String::new()))
+        } else {
+            let expected = Self::check_length(length, min)?;
+            // The payload is read by its length, so it may hold line breaks
+            let rest = bytes.as_str();
+            if rest.len() < expected {
+                *bytes = rest[rest.len()..].chars();
+                return Err(ParseError::end_of_input(format!("{expected} bytes")));
+            }
+            if !rest.is_char_boundary(expected) {
+                return Err(ParseError::new(
+                    format!("{expected} bytes"),
+                    "a split UTF-8 character",
+                ));
+            }
+            let (data, tail) = rest.split_at(expected);
+            let crlf = format!("{:?}", "\r\n");
+            match tail.as_bytes() {
+                [b'\r', b'\n', ..] => {
+                    *bytes = tail[2..].chars();
+                    Ok((length, data.to_owned()))
                 }
+                [] | [b'\r'] => {
+                    *bytes = tail[tail.len()..].chars();
+                    Err(ParseError::end_of_input(crlf))
+                }
+                _ => Err(ParseError::new(crlf, preview(tail))),
             }
-            ARRAY => {
-                let (length, data) = Self::parse_array(bytes)?;
-                if length < -1 {
-                    None
-                } else if length == -1 {
-                    Some(RESP::NullArray)
-                } else if length as usize != data.len() {
-                    None
+        }
+    }
+
+    /// Reads an aggregate header, returning the aggregate itself straight
+    /// away when it has no elements to wait for.
+    #[cfg_attr(not(feature = "resp3"), allow(unused_variables))]
+    fn read_aggregate(bytes: &mut Chars, kind: char, internal: bool) -> Result<Step, ParseError> {
+        #[cfg(feature = "resp3")]
+        if let Some(rest) = bytes.as_str().strip_prefix("?\r\n") {
+            *bytes = rest.chars();
+            return match kind {
+                MAP => Ok(Step::Open(Pending::Pairs {
+                    kind,
+                    expected: STREAMED,
+                    items: Vec::new(),
+                    key: None,
+                    internal,
+                })),
+                ARRAY | SET | PUSH => Ok(Step::Open(Pending::Elements {
+                    kind,
+                    expected: STREAMED,
+                    items: Vec::new(),
+                })),
+                _ => Err(ParseError::new("length", "?")),
+            };
+        }
+        let length = Self::parse_number::<isize>(bytes, "length")?;
+        let expected = Self::check_length(length, if kind == ARRAY { -1 } else { 0 })?;
+        if length == -1 {
+            return Ok(Step::Value(RESP::NullArray));
+        }
+        Ok(match (kind, expected) {
+            #[cfg(feature = "resp3")]
+            (ATTRIBUTE, 0) => Step::Open(Pending::Attributed {
+                attributes: Vec::new(),
+                internal,
+            }),
+            #[cfg(feature = "resp3")]
+            (MAP, 0) => Step::Value(RESP::Map(Vec::new())),
+            #[cfg(feature = "resp3")]
+            (SET, 0) => Step::Value(RESP::Set(Vec::new())),
+            #[cfg(feature = "resp3")]
+            (PUSH, 0) => Step::Value(RESP::Push(Vec::new())),
+            (_, 0) => Step::Value(RESP::Array(Vec::new())),
+            #[cfg(feature = "resp3")]
+            (MAP | ATTRIBUTE, _) => Step::Open(Pending::Pairs {
+                kind,
+                expected,
+                items: Vec::with_capacity(Self::capacity(length, bytes, MIN_FRAME_LEN * 2)),
+                key: None,
+                internal,
+            }),
+            _ => Step::Open(Pending::Elements {
+                kind,
+                expected,
+                items: Vec::with_capacity(Self::capacity(length, bytes, MIN_FRAME_LEN)),
+            }),
+        })
+    }
+
+    /// Parses a single value, or the header of an aggregate whose elements
+    /// still have to be parsed.
+    #[cfg_attr(
+        not(any(feature = "resp3", feature = "inline")),
+        allow(unused_variables)
+    )]
+    fn parse_step(
+        bytes: &mut Chars,
+        internal: bool,
+        config: &ParseConfig,
+    ) -> Result<Step, ParseError> {
+        let Some(kind) = bytes.next() else {
+            return Err(ParseError::end_of_input("type byte"));
+        };
+        let value = match kind {
+            SIMPLE_STRING => Self::SimpleString(Self::parse_short(bytes)?),
+            SIMPLE_ERROR => Self::SimpleError(Self::parse_short(bytes)?),
+            INTEGER => Self::Integer(Self::parse_number(bytes, "integer")?),
+            BULK_STRING => {
+                let (length, data) = Self::parse_bulk(bytes, -1)?;
+                if length == -1 {
+                    RESP::NullBulkString
                 } else {
-                    Some(RESP::Array(data))
+                    RESP::BulkString(data)
                 }
             }
+            #[cfg(feature = "resp3")]
             NULL => {
-                let data = Self::parse_simple(bytes)?;
+                let data = Self::parse_line(bytes)?;
                 if data.is_empty() {
-                    Some(RESP::Null)
+                    RESP::Null
                 } else {
-                    None
+                    return Err(ParseError::new("empty null", preview(data)));
                 }
             }
+            #[cfg(feature = "resp3")]
             BOOLEAN => {
-                let data = Self::parse_simple(bytes)?;
-                match data.as_ref() {
-                    "t" => Some(Self::Boolean(true)),
-                    "f" => Some(Self::Boolean(false)),
-                    _ => None,
+                let data = Self::parse_line(bytes)?;
+                match data {
+                    "t" => Self::Boolean(true),
+                    "f" => Self::Boolean(false),
+                    _ => return Err(ParseError::new("t or f", preview(data))),
                 }
             }
-            DOUBLE => Some(Self::Double(Self::parse_number(bytes)?)),
-            BIG_NUMBER => Some(Self::BigNumber(Self::parse_big_number(bytes)?)),
+            #[cfg(feature = "rust_decimal")]
+            DOUBLE if config.decimal_doubles => Self::parse_decimal(bytes)?,
+            #[cfg(feature = "resp3")]
+            DOUBLE => Self::Double(Self::parse_number(bytes, "double")?),
+            #[cfg(feature = "resp3")]
+            BIG_NUMBER => {
+                Self::BigNumber(Self::parse_big_number(bytes, config.max_big_number_digits)?)
+            }
+            #[cfg(feature = "resp3")]
             BULK_ERROR => {
-                let (length, data) = Self::parse_bulk(bytes)?;
-                if length < 0 {
-                    None
-                } else if length as usize != data.len() {
-                    None
-                } else {
-                    Some(RESP::BulkError(data))
-                }
+                let (_, data) = Self::parse_bulk(bytes, 0)?;
+                RESP::BulkError(data)
             }
+            #[cfg(feature = "resp3")]
             VERBATIM_STRING => {
-                let (length, data) = Self::parse_bulk(bytes)?;
-
-                if length < 4 {
-                    None
-                } else if length as usize != data.len() {
-                    None
+                let (_, data) = Self::parse_bulk(bytes, 4)?;
+                let Some((encoding, data)) = data.split_once(":") else {
+                    return Err(ParseError::new("encoding prefix", preview(&data)));
+                };
+                if encoding.len() != 3 {
+                    return Err(ParseError::new("3 byte encoding", preview(encoding)));
+                } else if config.verbatim_as_bulk {
+                    RESP::BulkString(data.to_owned())
                 } else {
-                    let (encoding, data) = data.split_once(":")?;
-                    if encoding.len() != 3 {
-                        None
-                    } else {
-                        Some(RESP::VerbatimString {
-                            data: data.to_owned(),
-                            encoding: encoding.to_owned(),
-                        })
+                    RESP::VerbatimString {
+                        data: data.to_owned(),
+                        encoding: encoding.into(),
                     }
                 }
             }
-            MAP => {
-                let (length, data) = Self::parse_map(bytes)?;
-                if length < 0 {
-                    None
-                } else if length as usize != data.len() {
-                    None
-                } else {
-                    Some(RESP::Map(data))
-                }
+            #[cfg(feature = "resp3")]
+            PUSH if internal => {
+                return Err(ParseError::new("value", "push inside an aggregate"));
             }
-            SET => {
-                let (length, data) = Self::parse_array(bytes)?;
-                if length < 0 {
-                    None
-                } else if length as usize != data.len() {
-                    None
-                } else {
-                    Some(RESP::Set(data))
+            #[cfg(feature = "resp3")]
+            ARRAY | SET | PUSH | MAP | ATTRIBUTE => {
+                return Self::read_aggregate(bytes, kind, internal);
+            }
+            #[cfg(not(feature = "resp3"))]
+            ARRAY => return Self::read_aggregate(bytes, kind, internal),
+            // Rejected rather than read as inline commands
+            #[cfg(not(feature = "resp3"))]
+            NULL | BOOLEAN | DOUBLE | BIG_NUMBER | BULK_ERROR | VERBATIM_STRING | SET | PUSH
+            | MAP | ATTRIBUTE => {
+                return Err(ParseError::new(
+                    "RESP2 type byte",
+                    format!("RESP3 type byte {kind:?}"),
+                ));
+            }
+            #[cfg(feature = "inline")]
+            x if config.inline_commands && config.side != Side::Client => {
+                RESP::Inline(Self::parse_inline(x, bytes)?)
+            }
+            x => return Err(ParseError::new("type byte", format!("{x:?}"))),
+        };
+        Ok(Step::Value(value))
+    }
+
+    /// Parses the end of the streamed aggregate `pending`.
+    #[cfg(feature = "resp3")]
+    fn parse_end(
+        bytes: &mut Chars,
+        pending: Pending,
+        config: &ParseConfig,
+    ) -> Result<Step, ParseError> {
+        bytes.next();
+        match Self::parse_line(bytes)? {
+            "" => pending.finish(config).map(Step::Value),
+            x => Err(ParseError::new("end of streamed aggregate", preview(x))),
+        }
+    }
+
+    /// Whether `value` is a request, as servers receive them.
+    fn is_command(value: &RESP) -> bool {
+        match value {
+            Self::Array(x) => !x.is_empty() && x.iter().all(|x| matches!(x, Self::BulkString(_))),
+            Self::Inline(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Parses a value keeping open aggregates on an explicit stack instead of
+    /// recursing, so deep nesting can't overflow the thread stack.
+    fn parse_internal(bytes: &mut Chars, config: &ParseConfig) -> Result<Self, ParseError> {
+        let len = bytes.as_str().len();
+        let mut stack: Vec<Pending> = Vec::new();
+        loop {
+            let start = len - bytes.as_str().len();
+            // Errors point at the value being parsed, from its type byte to
+            // where parsing stopped
+            let span = |bytes: &Chars| start..len - bytes.as_str().len();
+            if stack.len() > config.max_depth {
+                return Err(ParseError::new(
+                    format!("at most {} levels of nesting", config.max_depth),
+                    "deeper value",
+                )
+                .at(start..(start + 1).min(len)));
+            }
+            let internal = stack.last().is_some_and(Pending::internal);
+            let mut step = match stack.last() {
+                #[cfg(feature = "resp3")]
+                Some(pending) if pending.streamed() && bytes.as_str().starts_with(END) => {
+                    let pending = stack.pop().expect("checked above");
+                    Self::parse_end(bytes, pending, config)
                 }
+                _ => Self::parse_step(bytes, internal, config),
             }
-            PUSH => {
-                let (length, data) = Self::parse_array(bytes)?;
-                if length < 0 || internal {
-                    None
-                } else if length as usize != data.len() {
-                    None
-                } else {
-                    Some(RESP::Push(data))
+            .map_err(|err| err.at(span(bytes)))?;
+            // Hand finished values to their parents until one is still open
+            loop {
+                match step {
+                    Step::Open(pending) => {
+                        stack.push(pending);
+                        break;
+                    }
+                    Step::Value(value) => match stack.pop() {
+                        Some(parent) => {
+                            step = parent
+                                .add(value, config)
+                                .map_err(|err| err.at(span(bytes)))?
+                        }
+                        None if config.side == Side::Server => {
+                            return match Self::is_command(&value) {
+                                true => Ok(value),
+                                false => Err(ParseError::new("command", value.describe())
+                                    .at(0..len - bytes.as_str().len())),
+                            };
+                        }
+                        None => return Ok(value),
+                    },
                 }
             }
-            x => Some(RESP::Inline(Self::parse_inline(x, bytes)?)),
         }
     }
 
+    /// Parses the first frame in `data`, returning `None` if it is invalid.
+    ///
+    /// Parsing never panics, overflows or recurses past
+    /// [`ParseConfig::max_depth`] on any input, which makes it safe to use on
+    /// untrusted data.
     pub fn parse(data: &str) -> Option<Self> {
-        Self::parse_internal(&mut data.chars(), false)
+        Self::parse_with(data, &ParseConfig::default())
+    }
+
+    pub fn parse_with(data: &str, config: &ParseConfig) -> Option<Self> {
+        Self::try_parse_with(data, config).ok()
+    }
+
+    /// Like [`parse`](Self::parse), but reports why the input was rejected.
+    pub fn try_parse(data: &str) -> Result<Self, ParseError> {
+        Self::try_parse_with(data, &ParseConfig::default())
+    }
+
+    pub fn try_parse_with(data: &str, config: &ParseConfig) -> Result<Self, ParseError> {
+        Self::parse_internal(&mut data.chars(), config)
+    }
+
+    /// Parses `data` as exactly one frame, failing if anything follows it.
+    pub fn parse_complete(data: &str) -> Result<Self, ParseError> {
+        Self::parse_complete_with(data, &ParseConfig::default())
+    }
+
+    pub fn parse_complete_with(data: &str, config: &ParseConfig) -> Result<Self, ParseError> {
+        let mut bytes = data.chars();
+        let value = Self::parse_internal(&mut bytes, config)?;
+        let rest = bytes.as_str();
+        if rest.is_empty() {
+            Ok(value)
+        } else {
+            Err(ParseError::new(
+                "end of input",
+                format!("{} trailing bytes {}", rest.len(), preview(rest)),
+            )
+            .at(data.len() - rest.len()..data.len()))
+        }
     }
 }
 
 impl TryFrom<&str> for RESP {
-    type Error = ();
+    type Error = ParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        Self::parse(value).ok_or(())
+        Self::try_parse(value)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
 mod tests {
     use super::*;
 
@@ -273,6 +859,16 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "compact_str")]
+    #[test]
+    fn simple_string_inline() {
+        let parsed = RESP::parse("+OK\r\n");
+        assert!(matches!(parsed, Some(RESP::SimpleString(_))));
+        if let Some(RESP::SimpleString(x)) = parsed {
+            assert!(!x.is_heap_allocated())
+        }
+    }
+
     #[test]
     fn simple_string_none() {
         assert!(matches!(RESP::parse("+He\nllo\r\n"), None));
@@ -358,6 +954,26 @@ mod tests {
         assert!(!matches!(RESP::parse(":-123\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn number_lines() {
+        assert_eq!(
+            RESP::parse(":9223372036854775807\r\n"),
+            Some(RESP::Integer(i64::MAX))
+        );
+        assert!(matches!(RESP::parse(":9223372036854775808\r\n"), None));
+        assert_eq!(RESP::parse(",1.5e3\r\n"), Some(RESP::Double(1500.0)));
+        assert_eq!(
+            RESP::try_parse(":12\r3\r\n").map_err(|x| x.to_string()),
+            Err("expected \"\\r\\n\", found '3'".to_owned())
+        );
+        assert_eq!(
+            RESP::try_parse("*1\r\n:1\r").map_err(|x| x.to_string()),
+            Err("expected \"\\r\\n\", found end of input".to_owned())
+        );
+    }
+
+    #[cfg(feature = "resp3")]
     #[test]
     fn big_number() {
         let parsed = RESP::parse("(+123\r\n");
@@ -367,6 +983,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn big_number_plus() {
         let parsed = RESP::parse("(123\r\n");
@@ -376,6 +993,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn big_number_minus() {
         let parsed = RESP::parse("(-123\r\n");
@@ -385,6 +1003,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn big_number_none() {
         assert!(matches!(RESP::parse("(1\n23\r\n"), None));
@@ -412,6 +1031,42 @@ mod tests {
         assert!(!matches!(RESP::parse("(-123\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn big_number_max_digits() {
+        let config = ParseConfig {
+            max_big_number_digits: 3,
+            ..Default::default()
+        };
+        assert!(!matches!(RESP::parse_with("(123\r\n", &config), None));
+        assert!(!matches!(RESP::parse_with("(+123\r\n", &config), None));
+        assert!(!matches!(RESP::parse_with("(-123\r\n", &config), None));
+        assert!(matches!(RESP::parse_with("(1234\r\n", &config), None));
+        assert!(matches!(RESP::parse_with("(-1234\r\n", &config), None));
+        assert!(matches!(RESP::parse_with("(+1234\r\n", &config), None));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn big_number_max_digits_default() {
+        let digits = "1".repeat(DEFAULT_MAX_BIG_NUMBER_DIGITS);
+        assert!(!matches!(RESP::parse(&format!("({digits}\r\n")), None));
+        assert!(matches!(RESP::parse(&format!("({digits}1\r\n")), None));
+    }
+
+    #[cfg(not(feature = "resp3"))]
+    #[test]
+    fn resp3_rejected() {
+        for data in ["_\r\n", "#t\r\n", "%1\r\n+a\r\n:1\r\n", "*1\r\n,1.5\r\n"] {
+            let err = RESP::try_parse(data).unwrap_err();
+            assert_eq!(err.expected(), "RESP2 type byte");
+        }
+        assert_eq!(
+            RESP::parse("*1\r\n:1\r\n"),
+            Some(RESP::Array(vec![RESP::Integer(1)]))
+        );
+    }
+
     #[test]
     fn array() {
         let parsed = RESP::parse("*3\r\n+Hello\r\n-World\r\n:123\r\n");
@@ -423,7 +1078,7 @@ mod tests {
                     RESP::SimpleString(x) => assert_eq!(x, "Hello"),
                     RESP::SimpleError(x) => assert_eq!(x, "World"),
                     RESP::Integer(x) => assert_eq!(x, 123),
-                    _ => assert!(false),
+                    _ => unreachable!(),
                 }
             }
         }
@@ -459,6 +1114,7 @@ mod tests {
         assert!(!matches!(RESP::parse("*-1\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn push() {
         let parsed = RESP::parse(">3\r\n+Hello\r\n-World\r\n:123\r\n");
@@ -470,12 +1126,13 @@ mod tests {
                     RESP::SimpleString(x) => assert_eq!(x, "Hello"),
                     RESP::SimpleError(x) => assert_eq!(x, "World"),
                     RESP::Integer(x) => assert_eq!(x, 123),
-                    _ => assert!(false),
+                    _ => unreachable!(),
                 }
             }
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn push_empty() {
         let parsed = RESP::parse(">0\r\n");
@@ -492,6 +1149,7 @@ mod tests {
         assert!(matches!(parsed, None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn push_none() {
         assert!(matches!(RESP::parse(">\r\n"), None));
@@ -545,6 +1203,7 @@ mod tests {
         assert!(!matches!(RESP::parse("$-1\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bulk_error() {
         let parsed = RESP::parse("!5\r\nHello\r\n");
@@ -555,6 +1214,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bulk_error_empty() {
         let parsed = RESP::parse("!0\r\n\r\n");
@@ -565,6 +1225,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bulk_error_none() {
         assert!(matches!(RESP::parse("!\r\n"), None));
@@ -577,6 +1238,7 @@ mod tests {
         assert!(!matches!(RESP::parse("!0\r\n\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn verbatim_string() {
         let parsed = RESP::parse("=9\r\ntxt:Hello\r\n");
@@ -587,6 +1249,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn verbatim_string_empty() {
         let parsed = RESP::parse("=4\r\ntxt:\r\n");
@@ -597,6 +1260,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn verbatim_string_none() {
         assert!(matches!(RESP::parse("=\r\n"), None));
@@ -611,12 +1275,32 @@ mod tests {
         assert!(!matches!(RESP::parse("=4\r\ntxt:\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn verbatim_string_as_bulk() {
+        let config = ParseConfig {
+            verbatim_as_bulk: true,
+            ..Default::default()
+        };
+        let parsed = RESP::parse_with("=9\r\ntxt:Hello\r\n", &config);
+        assert!(matches!(parsed, Some(RESP::BulkString(_))));
+        if let Some(RESP::BulkString(data)) = parsed {
+            assert_eq!(data, "Hello".to_owned());
+        }
+        assert!(matches!(
+            RESP::parse_with("=11\r\nhtml:Hello\r\n", &config),
+            None
+        ));
+    }
+
+    #[cfg(feature = "resp3")]
     #[test]
     fn null() {
         let parsed = RESP::parse("_\r\n");
         assert!(matches!(parsed, Some(RESP::Null)));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn null_none() {
         assert!(matches!(RESP::parse("_hello\r\n"), None));
@@ -625,18 +1309,21 @@ mod tests {
         assert!(!matches!(RESP::parse("_\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bool_true() {
         let parsed = RESP::parse("#t\r\n");
         assert!(matches!(parsed, Some(RESP::Boolean(true))));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bool_false() {
         let parsed = RESP::parse("#f\r\n");
         assert!(matches!(parsed, Some(RESP::Boolean(false))));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn bool_none() {
         assert!(matches!(RESP::parse("#\r\n"), None));
@@ -647,6 +1334,7 @@ mod tests {
         assert!(!matches!(RESP::parse("#t\r\n"), None));
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double() {
         let parsed = RESP::parse(",1.23\r\n");
@@ -656,6 +1344,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_min_exponent() {
         let parsed = RESP::parse(",1.23e2\r\n");
@@ -665,6 +1354,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_max_exponent() {
         let parsed = RESP::parse(",1.23E2\r\n");
@@ -674,6 +1364,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_plus() {
         let parsed = RESP::parse(",+1.23\r\n");
@@ -683,6 +1374,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_minus() {
         let parsed = RESP::parse(",-1.23\r\n");
@@ -692,6 +1384,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_inf() {
         let parsed = RESP::parse(",inf\r\n");
@@ -702,6 +1395,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_plus_inf() {
         let parsed = RESP::parse(",+inf\r\n");
@@ -712,6 +1406,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_minus_inf() {
         let parsed = RESP::parse(",-inf\r\n");
@@ -722,6 +1417,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_nan() {
         let parsed = RESP::parse(",nan\r\n");
@@ -731,6 +1427,37 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn double_decimal() {
+        use rust_decimal::Decimal;
+
+        let config = ParseConfig {
+            decimal_doubles: true,
+            ..Default::default()
+        };
+        let parse = |data| RESP::parse_with(data, &config);
+        assert_eq!(parse(",0.1\r\n"), Some(RESP::Decimal(Decimal::new(1, 1))));
+        assert_eq!(
+            parse(",-1.23\r\n"),
+            Some(RESP::Decimal(Decimal::new(-123, 2)))
+        );
+        assert_eq!(
+            parse(",+1.23\r\n"),
+            Some(RESP::Decimal(Decimal::new(123, 2)))
+        );
+        assert_eq!(
+            parse(",1.23e2\r\n"),
+            Some(RESP::Decimal(Decimal::new(123, 0)))
+        );
+        assert_eq!(parse(",inf\r\n"), Some(RESP::Double(f64::INFINITY)));
+        assert_eq!(parse(",-inf\r\n"), Some(RESP::Double(f64::NEG_INFINITY)));
+        assert!(matches!(parse(",nan\r\n"), Some(RESP::Double(x)) if x.is_nan()));
+        assert!(matches!(parse(",1.\r23\r\n"), None));
+        assert!(matches!(parse(",hello\r\n"), None));
+    }
+
+    #[cfg(feature = "resp3")]
     #[test]
     fn double_none() {
         assert!(matches!(RESP::parse(",1.\n23\r\n"), None));
@@ -767,27 +1494,412 @@ mod tests {
         assert!(!matches!(RESP::parse(",-inf\r\n"), None));
     }
 
-    // todo: map and set tests
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn map() {
+        let parsed = RESP::parse("%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n");
+        assert!(matches!(parsed, Some(RESP::Map(_))));
+        if let Some(RESP::Map(data)) = parsed {
+            assert_eq!(
+                data,
+                vec![
+                    (RESP::SimpleString("first".into()), RESP::Integer(1)),
+                    (RESP::SimpleString("second".into()), RESP::Integer(2)),
+                ]
+            );
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn map_duplicate_keys() {
+        let input = "%3\r\n+key\r\n:1\r\n+other\r\n:2\r\n+key\r\n:3\r\n";
+        let parse = |duplicate_keys| {
+            let config = ParseConfig {
+                duplicate_keys,
+                ..Default::default()
+            };
+            match RESP::parse_with(input, &config) {
+                Some(RESP::Map(data)) => {
+                    Some(data.into_iter().map(|(_, value)| value).collect::<Vec<_>>())
+                }
+                _ => None,
+            }
+        };
+
+        assert_eq!(
+            parse(DuplicateKeys::KeepAll),
+            Some(vec![RESP::Integer(1), RESP::Integer(2), RESP::Integer(3)])
+        );
+        assert_eq!(
+            parse(DuplicateKeys::FirstWins),
+            Some(vec![RESP::Integer(1), RESP::Integer(2)])
+        );
+        assert_eq!(
+            parse(DuplicateKeys::LastWins),
+            Some(vec![RESP::Integer(3), RESP::Integer(2)])
+        );
+        assert_eq!(parse(DuplicateKeys::Error), None);
+
+        // Keys are compared normalized, so NaN doubles and strings of any
+        // type are duplicates
+        let config = ParseConfig {
+            duplicate_keys: DuplicateKeys::FirstWins,
+            ..Default::default()
+        };
+        let input = "%4\r\n,nan\r\n:1\r\n,nan\r\n:2\r\n+a\r\n:3\r\n$1\r\na\r\n:4\r\n";
+        let Some(RESP::Map(data)) = RESP::parse_with(input, &config) else {
+            panic!("expected a map");
+        };
+        let values: Vec<_> = data.into_iter().map(|(_, value)| value).collect();
+        assert_eq!(values, [RESP::Integer(1), RESP::Integer(3)]);
+        let input = "%2\r\n*1\r\n:1\r\n:1\r\n*1\r\n:1\r\n:2\r\n";
+        assert_eq!(
+            RESP::parse_with(input, &config),
+            Some(RESP::Map(vec![(
+                RESP::Array(vec![RESP::Integer(1)]),
+                RESP::Integer(1)
+            )]))
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn streamed_aggregates() {
+        assert_eq!(
+            RESP::parse("*?\r\n:1\r\n~?\r\n+a\r\n.\r\n*0\r\n.\r\n"),
+            Some(RESP::Array(vec![
+                RESP::Integer(1),
+                RESP::Set(vec![RESP::SimpleString("a".into())]),
+                RESP::Array(Vec::new()),
+            ]))
+        );
+        assert_eq!(
+            RESP::parse("%?\r\n+a\r\n|1\r\n+ttl\r\n:3\r\n:1\r\n.\r\n"),
+            Some(RESP::Map(vec![(
+                RESP::SimpleString("a".into()),
+                RESP::WithAttributes {
+                    attributes: vec![(RESP::SimpleString("ttl".into()), RESP::Integer(3))],
+                    value: Box::new(RESP::Integer(1)),
+                }
+            )]))
+        );
+        assert_eq!(
+            RESP::try_parse("%?\r\n+a\r\n.\r\n").map_err(|x| x.to_string()),
+            Err("expected map value, found end of the streamed map".to_owned())
+        );
+        assert!(RESP::parse("*?\r\n:1\r\n").is_none());
+        assert!(RESP::parse("*?\r\n.x\r\n").is_none());
+        assert!(RESP::parse("|?\r\n").is_none());
+        // Only streamed aggregates end with a dot
+        assert_ne!(RESP::parse("*1\r\n.\r\n"), Some(RESP::Array(Vec::new())));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn attribute() {
+        let parsed = RESP::parse("|1\r\n+key-popularity\r\n%1\r\n$1\r\na\r\n,0.19\r\n:2039123\r\n");
+        assert!(matches!(parsed, Some(RESP::WithAttributes { .. })));
+        if let Some(RESP::WithAttributes { attributes, value }) = parsed {
+            assert_eq!(
+                attributes,
+                vec![(
+                    RESP::SimpleString("key-popularity".into()),
+                    RESP::Map(vec![(RESP::BulkString("a".to_owned()), RESP::Double(0.19))])
+                )]
+            );
+            assert_eq!(*value, RESP::Integer(2039123));
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn attribute_inside() {
+        let parsed = RESP::parse("*2\r\n:1\r\n|1\r\n+ttl\r\n:3600\r\n:2\r\n");
+        assert!(matches!(parsed, Some(RESP::Array(_))));
+        if let Some(RESP::Array(data)) = parsed {
+            assert_eq!(data.len(), 2);
+            assert!(matches!(data[1], RESP::WithAttributes { .. }));
+            if let RESP::WithAttributes { value, .. } = &data[1] {
+                assert_eq!(**value, RESP::Integer(2));
+            }
+        }
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn attribute_none() {
+        assert!(matches!(RESP::parse("|\r\n"), None));
+        assert!(matches!(RESP::parse("|-1\r\n"), None));
+        assert!(matches!(RESP::parse("|1\r\n+ttl\r\n:3600\r\n"), None));
+        assert!(matches!(RESP::parse("|1\r\n+ttl\r\n"), None));
+        assert!(matches!(
+            RESP::parse("*1\r\n|1\r\n+ttl\r\n:1\r\n>1\r\n+Hello\r\n"),
+            None
+        ));
+        assert!(!matches!(RESP::parse("|0\r\n:1\r\n"), None));
+        assert!(!matches!(
+            RESP::parse("|1\r\n+ttl\r\n:1\r\n>1\r\n+Hello\r\n"),
+            None
+        ));
+    }
+
+    // todo: set tests
 
+    #[cfg(feature = "inline")]
     #[test]
     fn inline_singular() {
         let parsed = RESP::parse("PING");
         assert!(matches!(parsed, Some(RESP::Inline(_))));
         if let Some(RESP::Inline(x)) = parsed {
-            assert_eq!(x.get(0), Some(&"PING".to_owned()))
+            assert_eq!(x.first(), Some(&"PING".to_owned()))
         }
-
-
     }
 
+    #[cfg(feature = "inline")]
     #[test]
     fn inline_multiple() {
         let parsed = RESP::parse("ECHO hello world");
         assert!(matches!(parsed, Some(RESP::Inline(_))));
         if let Some(RESP::Inline(x)) = parsed {
-            assert_eq!(x.get(0), Some(&"ECHO".to_owned()));
+            assert_eq!(x.first(), Some(&"ECHO".to_owned()));
             assert_eq!(x.get(1), Some(&"hello".to_owned()));
             assert_eq!(x.get(2), Some(&"world".to_owned()));
         }
     }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn inline_pipelined() {
+        let mut bytes = "SET a 1\r\n+OK\r\n".chars();
+        assert_eq!(
+            RESP::parse_internal(&mut bytes, &ParseConfig::default()),
+            Ok(RESP::Inline(vec![
+                "SET".to_owned(),
+                "a".to_owned(),
+                "1".to_owned()
+            ]))
+        );
+        assert_eq!(bytes.as_str(), "+OK\r\n");
+        assert_eq!(
+            RESP::parse_complete("P ING\n"),
+            Ok(RESP::Inline(vec!["P".to_owned(), "ING".to_owned()]))
+        );
+        assert!(matches!(RESP::parse(" \r\n"), None));
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn inline_disabled() {
+        let config = ParseConfig {
+            inline_commands: false,
+            ..Default::default()
+        };
+        let err = RESP::try_parse_with("PING\r\n", &config).unwrap_err();
+        assert_eq!((err.expected(), err.found()), ("type byte", "'P'"));
+        assert!(matches!(RESP::parse_with("+OK\r\n", &config), Some(_)));
+    }
+
+    #[test]
+    fn server_side() {
+        let config = ParseConfig {
+            side: Side::Server,
+            ..Default::default()
+        };
+        for data in ["+OK\r\n", "*0\r\n", "*1\r\n:1\r\n", "*1\r\n$-1\r\n"] {
+            let err = RESP::try_parse_with(data, &config).unwrap_err();
+            assert_eq!(err.expected(), "command");
+            assert_eq!(err.span(), Some(0..data.len()));
+        }
+        let request = "*2\r\n$3\r\nGET\r\n$1\r\na\r\n";
+        assert!(matches!(RESP::parse_with(request, &config), Some(_)));
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn client_side() {
+        let config = ParseConfig {
+            side: Side::Client,
+            ..Default::default()
+        };
+        let err = RESP::try_parse_with("PING\r\n", &config).unwrap_err();
+        assert_eq!((err.expected(), err.found()), ("type byte", "'P'"));
+        let server = ParseConfig {
+            side: Side::Server,
+            ..Default::default()
+        };
+        assert!(matches!(RESP::parse_with("PING\r\n", &server), Some(_)));
+    }
+
+    #[cfg(not(feature = "inline"))]
+    #[test]
+    fn inline_compiled_out() {
+        assert!(matches!(RESP::try_parse("PING\r\n"), Err(_)));
+        assert!(matches!(framing::frame_len(b"PING"), Err(_)));
+    }
+
+    #[test]
+    fn parse_complete() {
+        assert!(matches!(
+            RESP::parse_complete("+OK\r\n"),
+            Ok(RESP::SimpleString(_))
+        ));
+        assert!(matches!(
+            RESP::parse_complete("*1\r\n$5\r\nHello\r\n"),
+            Ok(RESP::Array(_))
+        ));
+        assert!(matches!(RESP::parse_complete("+OK\r"), Err(_)));
+        assert!(!matches!(RESP::parse("+OK\r\ngarbage"), None));
+
+        let err = RESP::parse_complete("+OK\r\ngarbage").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected end of input, found 7 trailing bytes \"garbage\""
+        );
+        assert!(matches!(RESP::parse_complete("+OK\r\n+OK\r\n"), Err(_)));
+        assert!(matches!(RESP::parse_complete(":1\r\n\r\n"), Err(_)));
+    }
+
+    #[test]
+    fn max_depth() {
+        let config = ParseConfig {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(!matches!(
+            RESP::parse_with("*1\r\n*1\r\n:1\r\n", &config),
+            None
+        ));
+        assert!(!matches!(
+            RESP::parse_with("*1\r\n*1\r\n*0\r\n", &config),
+            None
+        ));
+        assert!(matches!(
+            RESP::parse_with("*1\r\n*1\r\n*1\r\n:1\r\n", &config),
+            None
+        ));
+        assert!(matches!(
+            RESP::parse_with("%1\r\n*1\r\n*1\r\n:1\r\n:1\r\n", &config),
+            None
+        ));
+        assert!(matches!(
+            RESP::parse_with("|0\r\n|0\r\n|0\r\n:1\r\n", &config),
+            None
+        ));
+    }
+
+    #[test]
+    fn array_capacity() {
+        let parsed = RESP::parse("*3\r\n:1\r\n:2\r\n:3\r\n");
+        assert!(matches!(parsed, Some(RESP::Array(_))));
+        if let Some(RESP::Array(data)) = parsed {
+            assert_eq!(data.capacity(), 3);
+        }
+    }
+
+    #[test]
+    fn capacity_clamped() {
+        let bytes = "+Hello\r\n".chars();
+        assert_eq!(RESP::capacity(-1, &bytes, MIN_FRAME_LEN), 0);
+        assert_eq!(RESP::capacity(1, &bytes, MIN_FRAME_LEN), 1);
+        assert_eq!(RESP::capacity(isize::MAX, &bytes, MIN_FRAME_LEN), 2);
+        assert_eq!(RESP::capacity(isize::MAX, &bytes, MIN_FRAME_LEN * 2), 1);
+
+        let data = "_\r\n".repeat(MAX_PREALLOCATION * 2);
+        assert_eq!(
+            RESP::capacity(isize::MAX, &data.chars(), MIN_FRAME_LEN),
+            MAX_PREALLOCATION
+        );
+    }
+
+    #[test]
+    fn deep_nesting_does_not_overflow() {
+        assert!(matches!(RESP::parse(&"*1\r\n".repeat(100_000)), None));
+        assert!(matches!(RESP::parse(&"|0\r\n".repeat(100_000)), None));
+        assert!(matches!(RESP::parse(&"%1\r\n".repeat(100_000)), None));
+    }
+
+    #[test]
+    fn unlimited_depth_does_not_overflow() {
+        let config = ParseConfig {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        let data = "*1\r\n".repeat(100_000);
+        assert!(matches!(RESP::parse_with(&data, &config), None));
+        let data = "|1\r\n+a\r\n:1\r\n".repeat(100_000);
+        assert!(matches!(RESP::parse_with(&data, &config), None));
+
+        let data = format!("{}:1\r\n", "*1\r\n".repeat(1_000));
+        let mut parsed = RESP::parse_with(&data, &config);
+        for _ in 0..1_000 {
+            assert!(matches!(parsed, Some(RESP::Array(_))));
+            if let Some(RESP::Array(mut data)) = parsed {
+                parsed = data.pop();
+            }
+        }
+        assert!(matches!(parsed, Some(RESP::Integer(1))));
+    }
+
+    /// Small xorshift generator so the fuzz style tests are reproducible
+    /// without extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, max: usize) -> usize {
+            (self.next() % max as u64) as usize
+        }
+    }
+
+    const SEEDS: &[&str] = &[
+        "+Hello\r\n",
+        ":-123\r\n",
+        "$5\r\nHello\r\n",
+        "$-1\r\n",
+        "*3\r\n+Hello\r\n-World\r\n:123\r\n",
+        "%1\r\n+key\r\n(12345678901234567890\r\n",
+        "~2\r\n#t\r\n,1.5e3\r\n",
+        ">2\r\n=9\r\ntxt:Hello\r\n!3\r\nERR\r\n",
+        "|1\r\n+ttl\r\n:1\r\n_\r\n",
+        "ECHO hello world\r\n",
+    ];
+
+    const ALPHABET: &[u8] = b"+-:$*_#,(!=%~>|?.;0123456789-\r\n abcinfte";
+
+    #[test]
+    fn random_input_never_panics() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20_000 {
+            let mut data = SEEDS[rng.below(SEEDS.len())].as_bytes().to_vec();
+            for _ in 0..=rng.below(4) {
+                let byte = ALPHABET[rng.below(ALPHABET.len())];
+                let at = rng.below(data.len() + 1);
+                match rng.below(4) {
+                    0 => data.insert(at, byte),
+                    1 if at < data.len() => data[at] = byte,
+                    2 if at < data.len() => {
+                        data.remove(at);
+                    }
+                    _ => data.truncate(at),
+                }
+            }
+            if rng.below(8) == 0 {
+                data = (0..rng.below(64))
+                    .map(|_| ALPHABET[rng.below(ALPHABET.len())])
+                    .collect();
+            }
+
+            let _ = RESP::try_parse(&String::from_utf8_lossy(&data));
+            let mut decoder = Decoder::new();
+            decoder.feed(&data);
+            while let Ok(Some(_)) = decoder.decode() {}
+        }
+    }
 }
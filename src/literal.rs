@@ -0,0 +1,211 @@
+use std::fmt::Write;
+
+use crate::RESP;
+
+const INDENT: &str = "    ";
+
+fn new_line(output: &mut String, depth: usize) {
+    output.push('\n');
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+}
+
+/// Writes `items` as the body of a `vec![]`, one per line.
+fn write_vec<T>(
+    output: &mut String,
+    items: &[T],
+    depth: usize,
+    mut write: impl FnMut(&mut String, &T, usize),
+) {
+    output.push_str("vec![");
+    if items.is_empty() {
+        output.push(']');
+        return;
+    }
+    for item in items {
+        new_line(output, depth + 1);
+        write(output, item, depth + 1);
+        output.push(',');
+    }
+    new_line(output, depth);
+    output.push(']');
+}
+
+#[cfg(feature = "resp3")]
+fn write_pairs(output: &mut String, pairs: &[(RESP, RESP)], depth: usize) {
+    write_vec(output, pairs, depth, |output, (key, value), depth| {
+        output.push('(');
+        write_value(output, key, depth);
+        output.push_str(", ");
+        write_value(output, value, depth);
+        output.push(')');
+    });
+}
+
+fn write_elements(output: &mut String, name: &str, elements: &[RESP], depth: usize) {
+    let _ = write!(output, "RESP::{name}(");
+    write_vec(output, elements, depth, write_value);
+    output.push(')');
+}
+
+#[cfg(feature = "resp3")]
+fn write_double(output: &mut String, value: f64) {
+    let _ = match value {
+        x if x.is_nan() => write!(output, "f64::NAN"),
+        f64::INFINITY => write!(output, "f64::INFINITY"),
+        f64::NEG_INFINITY => write!(output, "f64::NEG_INFINITY"),
+        // Debug always keeps a fraction or exponent, so it stays a float
+        x => write!(output, "{x:?}"),
+    };
+}
+
+fn write_value(output: &mut String, value: &RESP, depth: usize) {
+    let _ = match value {
+        RESP::SimpleString(x) => write!(output, "RESP::SimpleString({:?}.into())", x.as_str()),
+        RESP::SimpleError(x) => write!(output, "RESP::SimpleError({:?}.into())", x.as_str()),
+        RESP::Integer(x) => write!(output, "RESP::Integer({x})"),
+        RESP::BulkString(x) => write!(output, "RESP::BulkString({x:?}.to_owned())"),
+        RESP::NullBulkString => write!(output, "RESP::NullBulkString"),
+        RESP::NullArray => write!(output, "RESP::NullArray"),
+        #[cfg(feature = "resp3")]
+        RESP::Null => write!(output, "RESP::Null"),
+        #[cfg(feature = "resp3")]
+        RESP::Boolean(x) => write!(output, "RESP::Boolean({x})"),
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) => {
+            output.push_str("RESP::Double(");
+            write_double(output, *x);
+            write!(output, ")")
+        }
+        #[cfg(feature = "rust_decimal")]
+        RESP::Decimal(x) => write!(
+            output,
+            "RESP::Decimal(rust_decimal::Decimal::from_str_exact({:?}).unwrap())",
+            x.to_string()
+        ),
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(x) => write!(output, "RESP::BigNumber({x:?}.to_owned())"),
+        #[cfg(feature = "resp3")]
+        RESP::BulkError(x) => write!(output, "RESP::BulkError({x:?}.to_owned())"),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { encoding, data } => write!(
+            output,
+            "RESP::VerbatimString {{ encoding: {:?}.into(), data: {data:?}.to_owned() }}",
+            encoding.as_str()
+        ),
+        RESP::Array(x) => {
+            write_elements(output, "Array", x, depth);
+            Ok(())
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) => {
+            write_elements(output, "Set", x, depth);
+            Ok(())
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Push(x) => {
+            write_elements(output, "Push", x, depth);
+            Ok(())
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => {
+            output.push_str("RESP::Map(");
+            write_pairs(output, x, depth);
+            write!(output, ")")
+        }
+        RESP::Inline(x) => {
+            output.push_str("RESP::Inline(");
+            write_vec(output, x, depth, |output, x, _| {
+                let _ = write!(output, "{x:?}.to_owned()");
+            });
+            write!(output, ")")
+        }
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { attributes, value } => {
+            output.push_str("RESP::WithAttributes {");
+            new_line(output, depth + 1);
+            output.push_str("attributes: ");
+            write_pairs(output, attributes, depth + 1);
+            output.push(',');
+            new_line(output, depth + 1);
+            output.push_str("value: Box::new(");
+            write_value(output, value, depth + 1);
+            output.push_str("),");
+            new_line(output, depth);
+            write!(output, "}}")
+        }
+    };
+}
+
+impl RESP {
+    /// Renders the Rust expression building this value, to paste captured
+    /// traffic into tests as fixtures. It expects [`RESP`] to be in scope.
+    pub fn to_rust_literal(&self) -> String {
+        let mut output = String::new();
+        write_value(&mut output, self, 0);
+        output
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_nested() {
+        let value = RESP::parse(
+            "*4\r\n$5\r\nHe\"lo\r\n%1\r\n+k\r\n,1\r\n*0\r\n|1\r\n+ttl\r\n:3\r\n,inf\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_rust_literal(),
+            r#"RESP::Array(vec![
+    RESP::BulkString("He\"lo".to_owned()),
+    RESP::Map(vec![
+        (RESP::SimpleString("k".into()), RESP::Double(1.0)),
+    ]),
+    RESP::Array(vec![]),
+    RESP::WithAttributes {
+        attributes: vec![
+            (RESP::SimpleString("ttl".into()), RESP::Integer(3)),
+        ],
+        value: Box::new(RESP::Double(f64::INFINITY)),
+    },
+])"#
+        );
+
+        // The output above, as code
+        let built = RESP::Array(vec![
+            RESP::BulkString("He\"lo".to_owned()),
+            RESP::Map(vec![(RESP::SimpleString("k".into()), RESP::Double(1.0))]),
+            RESP::Array(vec![]),
+            RESP::WithAttributes {
+                attributes: vec![(RESP::SimpleString("ttl".into()), RESP::Integer(3))],
+                value: Box::new(RESP::Double(f64::INFINITY)),
+            },
+        ]);
+        assert_eq!(built, value);
+    }
+
+    #[test]
+    fn literal_scalars() {
+        assert_eq!(
+            RESP::parse("=7\r\ntxt:a\tb\r\n").unwrap().to_rust_literal(),
+            r#"RESP::VerbatimString { encoding: "txt".into(), data: "a\tb".to_owned() }"#
+        );
+        assert_eq!(
+            RESP::NullBulkString.to_rust_literal(),
+            "RESP::NullBulkString"
+        );
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn literal_inline() {
+        assert_eq!(
+            RESP::parse("PING x\r\n").unwrap().to_rust_literal(),
+            "RESP::Inline(vec![\n    \"PING\".to_owned(),\n    \"x\".to_owned(),\n])"
+        );
+    }
+}
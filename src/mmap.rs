@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{ParseConfig, ParseError, RawFrames, RESP};
+
+/// A capture or AOF file mapped into memory, so frames can be walked without
+/// reading the whole file into RAM.
+#[derive(Debug)]
+pub struct MappedFile {
+    mmap: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at `path`.
+    ///
+    /// The file must not be truncated or modified while it is mapped, as that
+    /// is undefined behaviour for memory maps.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller is told not to modify the file while it's mapped,
+        // which is the invariant memmap2 can't check
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self { mmap })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Raw bytes of every frame, borrowed straight from the mapping.
+    pub fn frames(&self) -> RawFrames<'_> {
+        RawFrames::new(&self.mmap)
+    }
+
+    /// Every frame decoded lazily, one at a time.
+    pub fn values<'a>(
+        &'a self,
+        config: &'a ParseConfig,
+    ) -> impl Iterator<Item = Result<RESP, ParseError>> + 'a {
+        self.frames().map(move |frame| {
+            let frame = std::str::from_utf8(frame?)
+                .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence"))?;
+            RESP::try_parse_with(frame, config)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn mapped_frames() {
+        let path = std::env::temp_dir().join(format!("resp-mmap-{}.aof", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(
+            b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+        )
+        .unwrap();
+        drop(file);
+
+        let mapped = MappedFile::open(&path).unwrap();
+        assert_eq!(mapped.frames().count(), 2);
+        let config = ParseConfig::default();
+        let values = mapped
+            .values(&config)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(matches!(values[1], RESP::Array(ref x) if x.len() == 3));
+
+        drop(mapped);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{EncodeError, RESP};
+
+/// How [`RESP::paginate`] splits an aggregate too large for a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Paging {
+    /// Every page is a complete aggregate holding some of the elements, to
+    /// be concatenated by the receiver.
+    #[default]
+    Batches,
+    /// The pages are parts of a single RESP3 streamed aggregate (`*?` … `.`),
+    /// each holding whole elements, so only RESP3 clients can read them.
+    Streamed,
+}
+
+/// Encoded elements of an aggregate along with its type byte, entries of
+/// maps being a single element holding both the key and value.
+fn encoded_elements(value: &RESP) -> Option<(u8, Vec<Vec<u8>>)> {
+    let encode = |x: &RESP| {
+        let mut data = Vec::new();
+        let _ = x.write_value(&mut data);
+        data
+    };
+    match value {
+        RESP::Array(x) => Some((b'*', x.iter().map(encode).collect())),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) => Some((b'~', x.iter().map(encode).collect())),
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => Some((
+            b'%',
+            x.iter()
+                .map(|(key, value)| [encode(key), encode(value)].concat())
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn header(kind: u8, count: usize) -> Vec<u8> {
+    format!("{}{count}\r\n", kind as char).into_bytes()
+}
+
+fn batches(kind: u8, elements: Vec<Vec<u8>>, budget: usize) -> Result<Vec<Vec<u8>>, EncodeError> {
+    let mut pages = Vec::new();
+    let mut batch: Vec<Vec<u8>> = Vec::new();
+    let mut size = 0;
+    for element in elements {
+        let required = header(kind, 1).len() + element.len();
+        if required > budget {
+            return Err(EncodeError::ExceedsBudget { required, budget });
+        }
+        if header(kind, batch.len() + 1).len() + size + element.len() > budget {
+            pages.push([header(kind, batch.len()), batch.concat()].concat());
+            batch.clear();
+            size = 0;
+        }
+        size += element.len();
+        batch.push(element);
+    }
+    pages.push([header(kind, batch.len()), batch.concat()].concat());
+    Ok(pages)
+}
+
+fn streamed(kind: u8, elements: Vec<Vec<u8>>, budget: usize) -> Result<Vec<Vec<u8>>, EncodeError> {
+    let mut pages = Vec::new();
+    let mut page = vec![kind, b'?', b'\r', b'\n'];
+    for element in elements.into_iter().chain([b".\r\n".to_vec()]) {
+        if element.len() > budget {
+            return Err(EncodeError::ExceedsBudget {
+                required: element.len(),
+                budget,
+            });
+        }
+        if page.len() + element.len() > budget {
+            pages.push(std::mem::take(&mut page));
+        }
+        page.extend_from_slice(&element);
+    }
+    pages.push(page);
+    Ok(pages)
+}
+
+impl RESP {
+    /// Encodes the value into pages of at most `budget` bytes each, for
+    /// transports capping the size of messages. A value that fits is a
+    /// single page holding its usual encoding, otherwise arrays, sets and
+    /// maps are split between elements as `paging` says.
+    ///
+    /// Fails if the value is invalid, if it's too large and can't be split,
+    /// or if one of its elements doesn't fit in a page on its own.
+    ///
+    /// ```
+    /// use resp_parser_rs::{Paging, RESP};
+    ///
+    /// let reply = RESP::Array((1..=4).map(RESP::Integer).collect());
+    /// let pages = reply.paginate(12, Paging::Batches).unwrap();
+    /// assert_eq!(pages, [&b"*2\r\n:1\r\n:2\r\n"[..], b"*2\r\n:3\r\n:4\r\n"]);
+    /// ```
+    pub fn paginate(&self, budget: usize, paging: Paging) -> Result<Vec<Vec<u8>>, EncodeError> {
+        let encoded = self.encode()?;
+        if encoded.len() <= budget {
+            return Ok(vec![encoded]);
+        }
+        let Some((kind, elements)) = encoded_elements(self) else {
+            return Err(EncodeError::ExceedsBudget {
+                required: encoded.len(),
+                budget,
+            });
+        };
+        match paging {
+            Paging::Batches => batches(kind, elements, budget),
+            Paging::Streamed => streamed(kind, elements, budget),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    fn reply() -> RESP {
+        RESP::Array(
+            ["a", "bb", "ccc", "dddd"]
+                .into_iter()
+                .map(|x| RESP::BulkString(x.to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn paginate_fits() {
+        let pages = reply().paginate(1024, Paging::Streamed).unwrap();
+        assert_eq!(pages, [reply().encode().unwrap()]);
+    }
+
+    #[test]
+    fn paginate_batches() {
+        let pages = reply().paginate(20, Paging::Batches).unwrap();
+        assert_eq!(
+            pages,
+            [
+                &b"*2\r\n$1\r\na\r\n$2\r\nbb\r\n"[..],
+                b"*1\r\n$3\r\nccc\r\n",
+                b"*1\r\n$4\r\ndddd\r\n",
+            ]
+        );
+        assert!(pages.iter().all(|x| x.len() <= 20));
+    }
+
+    #[test]
+    fn paginate_streamed() {
+        let pages = reply().paginate(20, Paging::Streamed).unwrap();
+        assert_eq!(
+            pages,
+            [
+                &b"*?\r\n$1\r\na\r\n$2\r\nbb\r\n"[..],
+                b"$3\r\nccc\r\n$4\r\ndddd\r\n",
+                b".\r\n",
+            ]
+        );
+        #[cfg(feature = "resp3")]
+        assert_eq!(
+            RESP::parse(std::str::from_utf8(&pages.concat()).unwrap()),
+            Some(reply())
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn paginate_map() {
+        let map = RESP::Map(vec![
+            (RESP::Integer(1), RESP::Integer(2)),
+            (RESP::Integer(3), RESP::Integer(4)),
+        ]);
+        assert_eq!(
+            map.paginate(13, Paging::Batches).unwrap(),
+            [&b"%1\r\n:1\r\n:2\r\n"[..], b"%1\r\n:3\r\n:4\r\n"]
+        );
+    }
+
+    #[test]
+    fn paginate_too_large() {
+        assert!(matches!(
+            reply().paginate(10, Paging::Batches),
+            Err(EncodeError::ExceedsBudget {
+                required: 11,
+                budget: 10
+            })
+        ));
+        assert!(matches!(
+            RESP::BulkString("x".repeat(20)).paginate(10, Paging::Streamed),
+            Err(EncodeError::ExceedsBudget { .. })
+        ));
+        assert!(matches!(
+            RESP::SimpleString("a\r\n".into()).paginate(10, Paging::Batches),
+            Err(EncodeError::Invalid { .. })
+        ));
+    }
+}
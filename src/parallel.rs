@@ -0,0 +1,55 @@
+use rayon::prelude::*;
+
+use crate::{ParseConfig, ParseError, RawFrames, RESP};
+
+/// Decodes every frame of `data` across the rayon thread pool, after finding
+/// the frame boundaries with [`RawFrames`] in a single cheap pass.
+///
+/// Meant for offline analysis of large captures, where decoding dominates.
+/// Fails with the error of the first invalid frame, in input order.
+pub fn decode_parallel(data: &[u8], config: &ParseConfig) -> Result<Vec<RESP>, ParseError> {
+    let mut frames = Vec::new();
+    for frame in RawFrames::new(data) {
+        frames.push(frame?);
+    }
+    frames
+        .into_par_iter()
+        .map(|frame| {
+            let text = std::str::from_utf8(frame)
+                .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence"))?;
+            RESP::parse_complete_with(text, config)
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::Generator;
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let config = ParseConfig::default();
+        let data = Generator::new(3)
+            .take(500)
+            .flat_map(|x| x.encode().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_parallel(&data, &config),
+            Ok(Generator::new(3).take(500).collect())
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn parallel_first_error() {
+        let config = ParseConfig::default();
+        assert_eq!(
+            decode_parallel(b"+OK\r\n#x\r\n_1\r\n", &config).map_err(|x| x.to_string()),
+            Err("expected t or f, found \"x\"".to_owned())
+        );
+        assert!(decode_parallel(b"+OK\r\n$5\r\nab", &config).is_err());
+    }
+}
@@ -0,0 +1,624 @@
+//! Decoding of Redis traffic from pcap and pcapng captures, for looking into
+//! incidents with the output of tcpdump or Wireshark.
+//!
+//! TCP segments to or from the server port are reassembled per connection
+//! and decoded as they become contiguous. Ethernet, Linux cooked, loopback
+//! and raw IP links are supported, over IPv4 or IPv6.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use crate::capture::Direction;
+use crate::{DecodeError, Decoder, RESP};
+
+const PCAPNG_SECTION: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+/// Packets kept per connection while waiting for the data before them.
+const MAX_OUT_OF_ORDER: usize = 256;
+
+/// A frame decoded from a capture.
+#[derive(Debug)]
+pub struct PcapFrame {
+    /// Capture time of the packet completing the frame.
+    pub timestamp: Duration,
+    /// [`Outbound`](Direction::Outbound) for frames sent to the server,
+    /// [`Inbound`](Direction::Inbound) for its replies.
+    pub direction: Direction,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    /// The frame, or why the rest of that direction of the connection can't
+    /// be decoded, e.g. because the capture started in the middle of a frame.
+    pub value: Result<RESP, DecodeError>,
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn u16_at(data: &[u8], offset: usize, little: bool) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if little {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    })
+}
+
+fn u32_at(data: &[u8], offset: usize, little: bool) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        little: bool,
+        nanos: bool,
+        link: u32,
+    },
+    Pcapng {
+        little: bool,
+        interfaces: Vec<(u32, u64)>,
+    },
+}
+
+/// A captured packet, before decoding its headers.
+struct Packet {
+    timestamp: Duration,
+    link: u32,
+    data: Vec<u8>,
+}
+
+/// Reads `length` bytes, `None` meaning the input ended right away.
+fn read_block<R: Read>(reader: &mut R, length: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    reader.take(length as u64).read_to_end(&mut data)?;
+    match data.len() {
+        0 if length > 0 => Ok(None),
+        x if x < length => Err(io::ErrorKind::UnexpectedEof.into()),
+        _ => Ok(Some(data)),
+    }
+}
+
+/// Timestamp of `units` since the epoch, `per_second` of them making a
+/// second.
+fn timestamp(units: u64, per_second: u64) -> Duration {
+    let nanos = (units % per_second) as u128 * 1_000_000_000 / per_second as u128;
+    Duration::new(units / per_second, nanos as u32)
+}
+
+/// Units per second of the `if_tsresol` option of an interface block.
+fn resolution(options: &[u8], little: bool) -> u64 {
+    let mut offset = 0;
+    while let (Some(code), Some(length)) = (
+        u16_at(options, offset, little),
+        u16_at(options, offset + 2, little),
+    ) {
+        let length = length as usize;
+        if code == 9 && length == 1 {
+            if let Some(&value) = options.get(offset + 4) {
+                let exponent = (value & 0x7f) as u32;
+                let base: u64 = if value & 0x80 == 0 { 10 } else { 2 };
+                return base.checked_pow(exponent).unwrap_or(1_000_000).max(1);
+            }
+        }
+        if code == 0 {
+            break;
+        }
+        offset += 4 + length.next_multiple_of(4);
+    }
+    1_000_000
+}
+
+impl Format {
+    fn read_header<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        let little = match magic {
+            [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => true,
+            [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => false,
+            [0x0a, 0x0d, 0x0d, 0x0a] => {
+                let mut header = [0; 8];
+                reader.read_exact(&mut header)?;
+                let little = match header[4..] {
+                    [0x4d, 0x3c, 0x2b, 0x1a] => true,
+                    [0x1a, 0x2b, 0x3c, 0x4d] => false,
+                    _ => return Err(invalid_data("invalid pcapng byte order magic")),
+                };
+                let length = u32_at(&header, 0, little).unwrap_or_default() as usize;
+                // Rest of the section header block, which holds nothing needed
+                read_block(reader, length.saturating_sub(12))?
+                    .ok_or(io::ErrorKind::UnexpectedEof)?;
+                return Ok(Self::Pcapng {
+                    little,
+                    interfaces: Vec::new(),
+                });
+            }
+            _ => return Err(invalid_data("not a pcap or pcapng capture")),
+        };
+        let nanos = matches!(magic, [0x4d, 0x3c, ..] | [.., 0x3c, 0x4d]);
+        let mut header = [0; 20];
+        reader.read_exact(&mut header)?;
+        Ok(Self::Pcap {
+            little,
+            nanos,
+            link: u32_at(&header, 16, little).unwrap_or_default() & 0xffff,
+        })
+    }
+
+    fn read_packet<R: Read>(&mut self, reader: &mut R) -> io::Result<Option<Packet>> {
+        match self {
+            Self::Pcap {
+                little,
+                nanos,
+                link,
+            } => {
+                let Some(header) = read_block(reader, 16)? else {
+                    return Ok(None);
+                };
+                let seconds = u32_at(&header, 0, *little).unwrap_or_default() as u64;
+                let fraction = u32_at(&header, 4, *little).unwrap_or_default() as u64;
+                let length = u32_at(&header, 8, *little).unwrap_or_default() as usize;
+                let data = read_block(reader, length)?.unwrap_or_default();
+                let timestamp = if *nanos {
+                    Duration::from_secs(seconds) + Duration::from_nanos(fraction)
+                } else {
+                    Duration::from_secs(seconds) + Duration::from_micros(fraction)
+                };
+                Ok(Some(Packet {
+                    timestamp,
+                    link: *link,
+                    data,
+                }))
+            }
+            Self::Pcapng { little, interfaces } => loop {
+                let Some(header) = read_block(reader, 8)? else {
+                    return Ok(None);
+                };
+                let kind = u32_at(&header, 0, *little).unwrap_or_default();
+                let length = u32_at(&header, 4, *little).unwrap_or_default() as usize;
+                if kind == PCAPNG_SECTION {
+                    return Err(invalid_data("multiple pcapng sections aren't supported"));
+                }
+                if length < 12 || !length.is_multiple_of(4) {
+                    return Err(invalid_data(format!(
+                        "invalid pcapng block length {length}"
+                    )));
+                }
+                let body = read_block(reader, length - 8)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+                let body = &body[..body.len() - 4];
+                match kind {
+                    PCAPNG_INTERFACE => {
+                        let link = u16_at(body, 0, *little).unwrap_or_default() as u32;
+                        let options = body.get(8..).unwrap_or_default();
+                        interfaces.push((link, resolution(options, *little)));
+                    }
+                    PCAPNG_ENHANCED_PACKET => {
+                        let field = |offset| u32_at(body, offset, *little).unwrap_or_default();
+                        let Some(&(link, per_second)) = interfaces.get(field(0) as usize) else {
+                            return Err(invalid_data("packet of an undeclared interface"));
+                        };
+                        let units = (field(4) as u64) << 32 | field(8) as u64;
+                        let captured = field(12) as usize;
+                        let data = body.get(20..20 + captured).unwrap_or_default();
+                        return Ok(Some(Packet {
+                            timestamp: timestamp(units, per_second),
+                            link,
+                            data: data.to_vec(),
+                        }));
+                    }
+                    PCAPNG_SIMPLE_PACKET => {
+                        let Some(&(link, _)) = interfaces.first() else {
+                            return Err(invalid_data("packet of an undeclared interface"));
+                        };
+                        return Ok(Some(Packet {
+                            timestamp: Duration::ZERO,
+                            link,
+                            data: body.get(4..).unwrap_or_default().to_vec(),
+                        }));
+                    }
+                    _ => {}
+                }
+            },
+        }
+    }
+}
+
+/// The TCP segment carried by a packet.
+struct Segment<'a> {
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence: u32,
+    syn: bool,
+    payload: &'a [u8],
+}
+
+/// IP packet carried by a frame of the given link type.
+fn ip_packet(link: u32, data: &[u8]) -> Option<&[u8]> {
+    let (ethertype, offset) = match link {
+        // BSD loopback, the address family is in host byte order
+        0 => return data.get(4..),
+        1 => {
+            let mut offset = 12;
+            // VLAN tags
+            while matches!(u16_at(data, offset, false)?, 0x8100 | 0x88a8) {
+                offset += 4;
+            }
+            (u16_at(data, offset, false)?, offset + 2)
+        }
+        101 => return Some(data),
+        113 => (u16_at(data, 14, false)?, 16),
+        276 => (u16_at(data, 0, false)?, 20),
+        _ => return None,
+    };
+    match ethertype {
+        0x0800 | 0x86dd => data.get(offset..),
+        _ => None,
+    }
+}
+
+fn tcp_segment(packet: &[u8]) -> Option<Segment<'_>> {
+    let (source, destination, tcp) = match packet.first()? >> 4 {
+        4 => {
+            let header = (packet.first()? & 0x0f) as usize * 4;
+            let total = (u16_at(packet, 2, false)? as usize).min(packet.len());
+            if *packet.get(9)? != 6 || header > total {
+                return None;
+            }
+            let address = |offset| {
+                let bytes: [u8; 4] = packet.get(offset..offset + 4)?.try_into().ok()?;
+                Some(IpAddr::V4(Ipv4Addr::from(bytes)))
+            };
+            (address(12)?, address(16)?, &packet[header..total])
+        }
+        6 => {
+            let total = (40 + u16_at(packet, 4, false)? as usize).min(packet.len());
+            if *packet.get(6)? != 6 || total < 40 {
+                return None;
+            }
+            let address = |offset| {
+                let bytes: [u8; 16] = packet.get(offset..offset + 16)?.try_into().ok()?;
+                Some(IpAddr::V6(Ipv6Addr::from(bytes)))
+            };
+            (address(8)?, address(24)?, &packet[40..total])
+        }
+        _ => return None,
+    };
+    let offset = (tcp.get(12)? >> 4) as usize * 4;
+    Some(Segment {
+        source: SocketAddr::new(source, u16_at(tcp, 0, false)?),
+        destination: SocketAddr::new(destination, u16_at(tcp, 2, false)?),
+        sequence: u32_at(tcp, 4, false)?,
+        syn: tcp.get(13)? & 0x02 != 0,
+        payload: tcp.get(offset..)?,
+    })
+}
+
+/// One direction of a connection.
+#[derive(Debug, Default)]
+struct Flow {
+    next: Option<u32>,
+    out_of_order: Vec<(u32, Vec<u8>)>,
+    decoder: Decoder,
+    failed: bool,
+}
+
+impl Flow {
+    /// Feeds the part of `payload` starting at `sequence` not seen yet,
+    /// returning whether it was ahead of the data received so far.
+    fn accept(&mut self, sequence: u32, payload: &[u8]) -> bool {
+        let next = *self.next.get_or_insert(sequence);
+        // Relative to the expected position, with wrapping
+        let ahead = sequence.wrapping_sub(next) as i32;
+        if ahead > 0 {
+            return false;
+        }
+        let seen = ahead.unsigned_abs() as usize;
+        if seen < payload.len() {
+            self.decoder.feed(&payload[seen..]);
+            self.next = Some(next.wrapping_add((payload.len() - seen) as u32));
+        }
+        true
+    }
+
+    fn push(&mut self, sequence: u32, payload: &[u8]) {
+        if !self.accept(sequence, payload) {
+            if self.out_of_order.len() < MAX_OUT_OF_ORDER {
+                self.out_of_order.push((sequence, payload.to_vec()));
+            }
+            return;
+        }
+        while let Some(index) = self.out_of_order.iter().position(|(sequence, _)| {
+            self.next
+                .is_some_and(|x| sequence.wrapping_sub(x) as i32 <= 0)
+        }) {
+            let (sequence, payload) = self.out_of_order.swap_remove(index);
+            self.accept(sequence, &payload);
+        }
+    }
+}
+
+/// Reads the frames exchanged with a Redis server out of a pcap or pcapng
+/// capture, in the order the packets completing them were captured.
+///
+/// Packets of other protocols or ports are skipped. Connections whose start
+/// wasn't captured are decoded from their first packet, which may fail if it
+/// starts in the middle of a frame.
+#[derive(Debug)]
+pub struct PcapReader<R> {
+    reader: R,
+    format: Format,
+    port: u16,
+    flows: HashMap<(SocketAddr, SocketAddr), Flow>,
+    frames: VecDeque<PcapFrame>,
+    done: bool,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Opens a capture of traffic to and from a server listening on `port`.
+    pub fn new(mut reader: R, port: u16) -> io::Result<Self> {
+        Ok(Self {
+            format: Format::read_header(&mut reader)?,
+            reader,
+            port,
+            flows: HashMap::new(),
+            frames: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    fn handle(&mut self, packet: &Packet) {
+        let Some(segment) = ip_packet(packet.link, &packet.data).and_then(tcp_segment) else {
+            return;
+        };
+        let (direction, client, server) = if segment.destination.port() == self.port {
+            (Direction::Outbound, segment.source, segment.destination)
+        } else if segment.source.port() == self.port {
+            (Direction::Inbound, segment.destination, segment.source)
+        } else {
+            return;
+        };
+
+        let flow = self
+            .flows
+            .entry((segment.source, segment.destination))
+            .or_default();
+        if segment.syn {
+            *flow = Flow {
+                next: Some(segment.sequence.wrapping_add(1)),
+                ..Default::default()
+            };
+            return;
+        }
+        if flow.failed || segment.payload.is_empty() {
+            return;
+        }
+        flow.push(segment.sequence, segment.payload);
+
+        loop {
+            let value = match flow.decoder.decode() {
+                Ok(Some(value)) => Ok(value),
+                Ok(None) => break,
+                Err(err) => {
+                    flow.failed = true;
+                    Err(err)
+                }
+            };
+            self.frames.push_back(PcapFrame {
+                timestamp: packet.timestamp,
+                direction,
+                client,
+                server,
+                value,
+            });
+            if flow.failed {
+                break;
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = io::Result<PcapFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.frames.is_empty() && !self.done {
+            match self.format.read_packet(&mut self.reader) {
+                Ok(Some(packet)) => self.handle(&packet),
+                Ok(None) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.frames.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    /// Ethernet frame of an IPv4 TCP segment.
+    fn packet(from_client: bool, sequence: u32, syn: bool, payload: &[u8]) -> Vec<u8> {
+        let (source, destination, ports) = match from_client {
+            true => (CLIENT, SERVER, [50000u16, 6379]),
+            false => (SERVER, CLIENT, [6379, 50000]),
+        };
+        let mut data = vec![0; 12];
+        data.extend([0x08, 0x00]);
+        data.extend([0x45, 0]);
+        data.extend((40 + payload.len() as u16).to_be_bytes());
+        data.extend([0, 0, 0, 0, 64, 6, 0, 0]);
+        data.extend(source);
+        data.extend(destination);
+        data.extend(ports[0].to_be_bytes());
+        data.extend(ports[1].to_be_bytes());
+        data.extend(sequence.to_be_bytes());
+        data.extend([
+            0,
+            0,
+            0,
+            0,
+            0x50,
+            if syn { 0x02 } else { 0x18 },
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]);
+        data.extend(payload);
+        data
+    }
+
+    fn pcap(packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0];
+        data.extend([0; 8]);
+        data.extend(65535u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        for (index, packet) in packets.iter().enumerate() {
+            data.extend((index as u32).to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+            data.extend((packet.len() as u32).to_le_bytes());
+            data.extend((packet.len() as u32).to_le_bytes());
+            data.extend(packet);
+        }
+        data
+    }
+
+    fn pcapng(packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut block = |kind: u32, body: &[u8]| {
+            let mut body = body.to_vec();
+            body.resize(body.len().next_multiple_of(4), 0);
+            let length = (body.len() + 12) as u32;
+            data.extend(kind.to_le_bytes());
+            data.extend(length.to_le_bytes());
+            data.extend(&body);
+            data.extend(length.to_le_bytes());
+        };
+        block(
+            PCAPNG_SECTION,
+            &[
+                0x4d, 0x3c, 0x2b, 0x1a, 1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            ],
+        );
+        // Millisecond resolution
+        block(
+            PCAPNG_INTERFACE,
+            &[1, 0, 0, 0, 0, 0, 0, 0, 9, 0, 1, 0, 3, 0, 0, 0, 0, 0, 0, 0],
+        );
+        for (index, packet) in packets.iter().enumerate() {
+            let mut body = vec![0; 4];
+            body.extend(0u32.to_le_bytes());
+            body.extend((1500 * index as u32).to_le_bytes());
+            body.extend((packet.len() as u32).to_le_bytes());
+            body.extend((packet.len() as u32).to_le_bytes());
+            body.extend(packet);
+            block(PCAPNG_ENHANCED_PACKET, &body);
+        }
+        data
+    }
+
+    fn conversation() -> Vec<Vec<u8>> {
+        vec![
+            packet(true, 99, true, b""),
+            packet(false, 499, true, b""),
+            packet(true, 100, false, b"*2\r\n$3\r\nGET\r\n"),
+            packet(true, 113, false, b"$1\r\nk\r\n"),
+            // Out of order then retransmitted
+            packet(false, 507, false, b"lo\r\n"),
+            packet(false, 500, false, b"$5\r\nHel"),
+            packet(false, 500, false, b"$5\r\nHel"),
+        ]
+    }
+
+    fn check(frames: Vec<PcapFrame>) {
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].direction, Direction::Outbound);
+        assert_eq!(frames[0].client, "10.0.0.1:50000".parse().unwrap());
+        assert_eq!(frames[0].server, "10.0.0.2:6379".parse().unwrap());
+        assert_eq!(
+            frames[0].value.as_ref().unwrap(),
+            &RESP::Array(vec![
+                RESP::BulkString("GET".to_owned()),
+                RESP::BulkString("k".to_owned())
+            ])
+        );
+        assert_eq!(frames[1].direction, Direction::Inbound);
+        assert_eq!(
+            frames[1].value.as_ref().unwrap(),
+            &RESP::BulkString("Hello".to_owned())
+        );
+    }
+
+    #[test]
+    fn pcap_reassembly() {
+        let data = pcap(&conversation());
+        let frames = PcapReader::new(&data[..], 6379)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(frames[1].timestamp, Duration::from_secs(5));
+        check(frames);
+    }
+
+    #[test]
+    fn pcapng_reassembly() {
+        let data = pcapng(&conversation());
+        let frames = PcapReader::new(&data[..], 6379)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(frames[1].timestamp, Duration::from_millis(7500));
+        check(frames);
+    }
+
+    #[test]
+    fn pcap_other_ports() {
+        let data = pcap(&conversation());
+        let mut reader = PcapReader::new(&data[..], 6380).unwrap();
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "inline")]
+    #[test]
+    fn pcap_midstream() {
+        // Tail of a bulk string read as an inline command, then a payload
+        // that isn't valid RESP, after which nothing is decoded
+        let data = pcap(&[
+            packet(false, 7, false, b"llo\r\n$-7\r\n+OK\r\n"),
+            packet(false, 22, false, b":1\r\n"),
+        ]);
+        let frames = PcapReader::new(&data[..], 6379)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(frames[0].value, Ok(RESP::Inline(_))));
+        assert!(matches!(frames[1].value, Err(DecodeError::Parse(_))));
+    }
+
+    #[test]
+    fn pcap_invalid() {
+        assert!(PcapReader::new(&b"RESPCAP\x01"[..], 6379).is_err());
+        let mut data = pcap(&conversation());
+        data.truncate(data.len() - 3);
+        let frames = PcapReader::new(&data[..], 6379).unwrap();
+        assert!(frames.last().unwrap().is_err());
+    }
+}
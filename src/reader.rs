@@ -0,0 +1,554 @@
+use std::io::{self, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(feature = "futures")]
+use futures::io::{AsyncRead, AsyncReadExt};
+
+use crate::framing::read_length;
+use crate::{DecodeError, Decoder, FromResp, ParseConfig, ParseError, ReadError, RESP};
+
+/// Size of the chunks read from the underlying reader.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Socket timeouts applied by [`FrameReader::connect_tcp`] and
+/// [`FrameReader::connect_unix`], `None` meaning no timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Timeouts {
+    /// Only used for TCP, Unix sockets connect immediately.
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+    pub write: Option<Duration>,
+}
+
+/// Reads whole frames from a blocking reader, such as a socket.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    decoder: Decoder,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_decoder(reader, Decoder::new())
+    }
+
+    /// Reads frames through `decoder`, to keep its configuration.
+    pub fn with_decoder(reader: R, decoder: Decoder) -> Self {
+        Self { reader, decoder }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Returns the inner reader, losing any input read but not decoded yet.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next frame, returning `None` once the input ends between
+    /// frames.
+    pub fn read_frame(&mut self) -> Result<Option<RESP>, DecodeError> {
+        let mut chunk = [0; READ_CHUNK];
+        loop {
+            if let Some(value) = self.decoder.decode()? {
+                return Ok(Some(value));
+            }
+            // Blocking readers have no one to yield to
+            if self.decoder.budget_exhausted() {
+                self.decoder.reset_budget();
+                continue;
+            }
+            let read = match self.reader.read(&mut chunk) {
+                Ok(x) => x,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if read == 0 {
+                return end_of_input(&self.decoder);
+            }
+            self.decoder.feed(&chunk[..read]);
+        }
+    }
+}
+
+fn end_of_input(decoder: &Decoder) -> Result<Option<RESP>, DecodeError> {
+    match decoder.buffered() {
+        0 => Ok(None),
+        _ => Err(ParseError::end_of_input("rest of the frame").into()),
+    }
+}
+
+/// Future returning pending once, giving other tasks a chance to run.
+#[cfg(feature = "futures")]
+struct YieldNow(bool);
+
+#[cfg(feature = "futures")]
+impl std::future::Future for YieldNow {
+    type Output = ();
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        if self.0 {
+            return std::task::Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    }
+}
+
+/// Reads whole frames from an [`AsyncRead`].
+///
+/// [`read_frame`](Self::read_frame) is cancellation safe: bytes are handed to
+/// the decoder as soon as they are read, so dropping the future, e.g. when
+/// another branch of a `select!` completes, loses nothing and the next call
+/// picks up where it left off.
+#[cfg(feature = "futures")]
+#[derive(Debug)]
+pub struct AsyncFrameReader<R> {
+    reader: R,
+    decoder: Decoder,
+}
+
+#[cfg(feature = "futures")]
+impl<R: AsyncRead + Unpin> AsyncFrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_decoder(reader, Decoder::new())
+    }
+
+    /// Reads frames through `decoder`, to keep its configuration.
+    pub fn with_decoder(reader: R, decoder: Decoder) -> Self {
+        Self { reader, decoder }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Returns the inner reader, losing any input read but not decoded yet.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next frame, returning `None` once the input ends between
+    /// frames. Yields to the executor whenever the decoder's
+    /// [frame budget](Decoder::set_frame_budget) runs out.
+    pub async fn read_frame(&mut self) -> Result<Option<RESP>, DecodeError> {
+        let mut chunk = [0; READ_CHUNK];
+        loop {
+            if let Some(value) = self.decoder.decode()? {
+                return Ok(Some(value));
+            }
+            if self.decoder.budget_exhausted() {
+                self.decoder.reset_budget();
+                YieldNow(false).await;
+                continue;
+            }
+            // Nothing is consumed while the read is pending
+            let read = self.reader.read(&mut chunk).await?;
+            if read == 0 {
+                return end_of_input(&self.decoder);
+            }
+            self.decoder.feed(&chunk[..read]);
+        }
+    }
+}
+
+/// Tracks the elements of a single frame left to read, so that
+/// [`from_reader`] stops right after it.
+struct FrameScan {
+    buffer: Vec<u8>,
+    remaining: usize,
+}
+
+impl FrameScan {
+    fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            remaining: 1,
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Accounts for the line just read from `start`, returning how many
+    /// payload bytes follow it.
+    fn line_read(&mut self, start: usize) -> Result<usize, ParseError> {
+        self.remaining -= 1;
+        let kind = self.buffer[start];
+        if !matches!(kind, b'$' | b'!' | b'=' | b'*' | b'~' | b'>' | b'%' | b'|') {
+            return Ok(0);
+        }
+        let Some((length, _)) = read_length(&self.buffer, start + 1)? else {
+            // No CRLF, the parser reports it
+            self.remaining = 0;
+            return Ok(0);
+        };
+        let too_long = || ParseError::new("length that fits in memory", length.to_string());
+        let count = match length {
+            -1 => return Ok(0),
+            0.. => length as usize,
+            _ => return Err(ParseError::new("length of at least -1", length.to_string())),
+        };
+        let children = match kind {
+            b'$' | b'!' | b'=' => return count.checked_add(2).ok_or_else(too_long),
+            b'%' => count.checked_mul(2),
+            // Attributes are followed by the value they annotate
+            b'|' => count.checked_mul(2).and_then(|x| x.checked_add(1)),
+            _ => Some(count),
+        };
+        self.remaining = children
+            .and_then(|x| x.checked_add(self.remaining))
+            .ok_or_else(too_long)?;
+        Ok(0)
+    }
+
+    fn finish<T: FromResp>(self) -> Result<T, ReadError> {
+        let text = String::from_utf8(self.buffer)
+            .map_err(|err| ParseError::new("UTF-8 text", format!("{err}")))?;
+        let value = RESP::try_parse_with(&text, &ParseConfig::default())?;
+        Ok(T::from_resp(value)?)
+    }
+}
+
+fn unexpected_eof(err: io::Error) -> ReadError {
+    match err.kind() {
+        io::ErrorKind::UnexpectedEof => ParseError::end_of_input("rest of the frame").into(),
+        _ => err.into(),
+    }
+}
+
+/// Reads exactly one frame from `reader` and converts it to `T`, leaving
+/// anything after the frame unread.
+///
+/// The frame is read a byte at a time, so unbuffered readers such as sockets
+/// should be wrapped in a [`BufReader`](std::io::BufReader), which then keeps
+/// what it read ahead.
+///
+/// ```
+/// let mut input = &b":42\r\n+OK\r\n"[..];
+/// let value: i64 = resp_parser_rs::from_reader(&mut input).unwrap();
+/// assert_eq!(value, 42);
+/// assert_eq!(input, b"+OK\r\n");
+/// ```
+pub fn from_reader<R: Read, T: FromResp>(mut reader: R) -> Result<T, ReadError> {
+    let mut scan = FrameScan::new();
+    while !scan.done() {
+        let start = scan.buffer.len();
+        let mut byte = [0];
+        loop {
+            reader.read_exact(&mut byte).map_err(unexpected_eof)?;
+            scan.buffer.push(byte[0]);
+            if byte[0] == b'\n' {
+                break;
+            }
+        }
+        let payload = scan.line_read(start)?;
+        if payload > 0 {
+            let read = reader
+                .by_ref()
+                .take(payload as u64)
+                .read_to_end(&mut scan.buffer)?;
+            if read < payload {
+                return Err(ParseError::end_of_input("rest of the frame").into());
+            }
+        }
+    }
+    scan.finish()
+}
+
+/// Async version of [`from_reader`].
+#[cfg(feature = "futures")]
+pub async fn from_async_reader<R, T>(mut reader: R) -> Result<T, ReadError>
+where
+    R: AsyncRead + Unpin,
+    T: FromResp,
+{
+    let mut scan = FrameScan::new();
+    while !scan.done() {
+        let start = scan.buffer.len();
+        let mut byte = [0];
+        loop {
+            reader.read_exact(&mut byte).await.map_err(unexpected_eof)?;
+            scan.buffer.push(byte[0]);
+            if byte[0] == b'\n' {
+                break;
+            }
+        }
+        let payload = scan.line_read(start)?;
+        if payload > 0 {
+            let read = (&mut reader)
+                .take(payload as u64)
+                .read_to_end(&mut scan.buffer)
+                .await?;
+            if read < payload {
+                return Err(ParseError::end_of_input("rest of the frame").into());
+            }
+        }
+    }
+    scan.finish()
+}
+
+impl FrameReader<TcpStream> {
+    /// Connects to `addr` with the given timeouts and Nagle's algorithm
+    /// disabled, as requests are usually small.
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A, timeouts: Timeouts) -> io::Result<Self> {
+        let stream = match timeouts.connect {
+            Some(timeout) => {
+                let mut last = None;
+                let mut stream = None;
+                for addr in addr.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&addr, timeout) {
+                        Ok(x) => {
+                            stream = Some(x);
+                            break;
+                        }
+                        Err(err) => last = Some(err),
+                    }
+                }
+                match (stream, last) {
+                    (Some(stream), _) => stream,
+                    (None, Some(err)) => return Err(err),
+                    (None, None) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "no addresses to connect to",
+                        ))
+                    }
+                }
+            }
+            None => TcpStream::connect(addr)?,
+        };
+        stream.set_read_timeout(timeouts.read)?;
+        stream.set_write_timeout(timeouts.write)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+}
+
+#[cfg(unix)]
+impl FrameReader<UnixStream> {
+    /// Connects to the socket at `path` with the given read and write
+    /// timeouts.
+    pub fn connect_unix<P: AsRef<Path>>(path: P, timeouts: Timeouts) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(timeouts.read)?;
+        stream.set_write_timeout(timeouts.write)?;
+        Ok(Self::new(stream))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    #[test]
+    fn read_frames() {
+        let mut reader = FrameReader::new(&b"+OK\r\n*1\r\n:1\r\n"[..]);
+        assert!(matches!(
+            reader.read_frame(),
+            Ok(Some(RESP::SimpleString(_)))
+        ));
+        assert!(matches!(reader.read_frame(), Ok(Some(RESP::Array(_)))));
+        assert!(matches!(reader.read_frame(), Ok(None)));
+
+        let mut reader = FrameReader::new(&b"+OK\r\n$3\r\na"[..]);
+        assert!(matches!(reader.read_frame(), Ok(Some(_))));
+        assert!(matches!(reader.read_frame(), Err(DecodeError::Parse(_))));
+    }
+
+    /// Reader yielding one byte at a time, pending before each of them.
+    #[cfg(feature = "futures")]
+    struct Trickle {
+        data: &'static [u8],
+        ready: bool,
+    }
+
+    #[cfg(feature = "futures")]
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if self.ready {
+                cx.waker().wake_by_ref();
+                return std::task::Poll::Pending;
+            }
+            let length = self.data.len().min(buf.len()).min(1);
+            buf[..length].copy_from_slice(&self.data[..length]);
+            self.data = &self.data[length..];
+            std::task::Poll::Ready(Ok(length))
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn async_read_cancelled() {
+        use futures::FutureExt;
+
+        let data = b"*2\r\n$5\r\nHello\r\n:1\r\n+OK\r\n";
+        let mut reader = AsyncFrameReader::new(Trickle { data, ready: false });
+        let mut frames = Vec::new();
+        let mut polls = 0;
+        // Every poll is followed by dropping the future, cancelling it at
+        // whichever await point it reached
+        loop {
+            polls += 1;
+            match reader.read_frame().now_or_never() {
+                Some(Ok(Some(value))) => frames.push(value),
+                Some(Ok(None)) => break,
+                Some(Err(err)) => panic!("{err}"),
+                None => {}
+            }
+        }
+        assert!(polls > data.len());
+        assert_eq!(
+            frames,
+            vec![
+                RESP::Array(vec![RESP::BulkString("Hello".to_owned()), RESP::Integer(1)]),
+                RESP::SimpleString("OK".into()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn async_read_truncated() {
+        let mut reader = AsyncFrameReader::new(&b"+OK\r\n:1"[..]);
+        futures::executor::block_on(async {
+            assert!(matches!(reader.read_frame().await, Ok(Some(_))));
+            assert!(matches!(
+                reader.read_frame().await,
+                Err(DecodeError::Parse(_))
+            ));
+        });
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn async_read_budget() {
+        use futures::FutureExt;
+
+        let mut decoder = Decoder::new();
+        decoder.set_frame_budget(Some(1));
+        let mut reader = AsyncFrameReader::with_decoder(&b":1\r\n:2\r\n"[..], decoder);
+        assert!(matches!(
+            reader.read_frame().now_or_never(),
+            Some(Ok(Some(RESP::Integer(1))))
+        ));
+        assert!(matches!(reader.read_frame().now_or_never(), None));
+        assert!(matches!(
+            reader.read_frame().now_or_never(),
+            Some(Ok(Some(RESP::Integer(2))))
+        ));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn from_reader_one_frame() {
+        let mut input = &b"*2\r\n$5\r\nHello\r\n:1\r\n+OK\r\n"[..];
+        let value: RESP = from_reader(&mut input).unwrap();
+        assert_eq!(
+            value,
+            RESP::Array(vec![RESP::BulkString("Hello".to_owned()), RESP::Integer(1)])
+        );
+        assert_eq!(input, b"+OK\r\n");
+
+        let mut input = &b"|1\r\n+ttl\r\n:3\r\n%1\r\n+a\r\n*-1\r\n:7\r\n"[..];
+        assert!(matches!(
+            from_reader::<_, RESP>(&mut input),
+            Ok(RESP::WithAttributes { .. })
+        ));
+        assert_eq!(from_reader::<_, u64>(&mut input).unwrap(), 7);
+
+        let mut input = &b"$7\r\nid=1\r\n\n\r\n"[..];
+        let value: String = from_reader(&mut input).unwrap();
+        assert_eq!(value, "id=1\r\n\n");
+    }
+
+    #[test]
+    fn from_reader_errors() {
+        assert!(matches!(
+            from_reader::<_, i64>(&b"+OK\r\n"[..]),
+            Err(ReadError::Conversion(_))
+        ));
+        assert!(matches!(
+            from_reader::<_, String>(&b"$5\r\nab"[..]),
+            Err(ReadError::Decode(DecodeError::Parse(_)))
+        ));
+        assert!(matches!(
+            from_reader::<_, RESP>(&b""[..]),
+            Err(ReadError::Decode(DecodeError::Parse(_)))
+        ));
+        assert!(matches!(
+            from_reader::<_, RESP>(&b"*-2\r\n"[..]),
+            Err(ReadError::Decode(DecodeError::Parse(_)))
+        ));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[cfg(feature = "futures")]
+    #[test]
+    fn from_async_reader_one_frame() {
+        let data = b"%1\r\n+a\r\n$3\r\nbob\r\n:1\r\n";
+        let mut input = Trickle { data, ready: false };
+        let value: RESP = futures::executor::block_on(from_async_reader(&mut input)).unwrap();
+        assert!(matches!(value, RESP::Map(_)));
+        assert_eq!(input.data, b":1\r\n");
+    }
+
+    #[test]
+    fn connect_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"+PONG\r\n").unwrap();
+        });
+
+        let timeouts = Timeouts {
+            connect: Some(Duration::from_secs(5)),
+            read: Some(Duration::from_secs(5)),
+            write: None,
+        };
+        let mut reader = FrameReader::connect_tcp(addr, timeouts).unwrap();
+        assert!(reader.get_ref().nodelay().unwrap());
+        assert_eq!(
+            reader.get_ref().read_timeout().unwrap(),
+            Some(Duration::from_secs(5))
+        );
+        assert!(matches!(
+            reader.read_frame(),
+            Ok(Some(RESP::SimpleString(_)))
+        ));
+        server.join().unwrap();
+        assert!(matches!(reader.read_frame(), Ok(None)));
+    }
+}
@@ -0,0 +1,478 @@
+use crate::RESP;
+
+/// Text replacing sensitive arguments.
+const REDACTED: &str = "<redacted>";
+
+/// `CONFIG SET` parameters holding secrets.
+const SECRET_PARAMETERS: &[&str] = &[
+    "requirepass",
+    "masterauth",
+    "tls-key-file-pass",
+    "tls-client-key-file-pass",
+];
+
+/// Copies `value`, replacing the nodes `replace` returns a value for instead
+/// of descending into them.
+pub(crate) fn copy_with<F>(value: &RESP, replace: &mut F) -> RESP
+where
+    F: FnMut(&RESP) -> Option<RESP>,
+{
+    if let Some(replaced) = replace(value) {
+        return replaced;
+    }
+    #[cfg(feature = "resp3")]
+    let mut copy_pairs = |pairs: &[(RESP, RESP)]| -> Vec<(RESP, RESP)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (copy_with(key, replace), copy_with(value, replace)))
+            .collect()
+    };
+    match value {
+        RESP::SimpleString(x) => RESP::SimpleString(x.clone()),
+        RESP::SimpleError(x) => RESP::SimpleError(x.clone()),
+        RESP::Integer(x) => RESP::Integer(*x),
+        RESP::BulkString(x) => RESP::BulkString(x.clone()),
+        RESP::NullBulkString => RESP::NullBulkString,
+        RESP::Array(x) => RESP::Array(x.iter().map(|x| copy_with(x, replace)).collect()),
+        RESP::NullArray => RESP::NullArray,
+        #[cfg(feature = "resp3")]
+        RESP::Null => RESP::Null,
+        #[cfg(feature = "resp3")]
+        RESP::Boolean(x) => RESP::Boolean(*x),
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) => RESP::Double(*x),
+        #[cfg(feature = "rust_decimal")]
+        RESP::Decimal(x) => RESP::Decimal(*x),
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(x) => RESP::BigNumber(x.clone()),
+        #[cfg(feature = "resp3")]
+        RESP::BulkError(x) => RESP::BulkError(x.clone()),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { encoding, data } => RESP::VerbatimString {
+            encoding: encoding.clone(),
+            data: data.clone(),
+        },
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => RESP::Map(copy_pairs(x)),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) => RESP::Set(x.iter().map(|x| copy_with(x, replace)).collect()),
+        #[cfg(feature = "resp3")]
+        RESP::Push(x) => RESP::Push(x.iter().map(|x| copy_with(x, replace)).collect()),
+        RESP::Inline(x) => RESP::Inline(x.clone()),
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { attributes, value } => RESP::WithAttributes {
+            attributes: copy_pairs(attributes),
+            value: Box::new(copy_with(value, replace)),
+        },
+    }
+}
+
+/// Indexes of the arguments of a command holding secrets, the command name
+/// being at index 0.
+pub(crate) fn sensitive_arguments(arguments: &[&[u8]]) -> Vec<usize> {
+    let is = |index: usize, name: &str| {
+        arguments
+            .get(index)
+            .is_some_and(|x| x.eq_ignore_ascii_case(name.as_bytes()))
+    };
+    let after = |token: &str, offset: usize| {
+        (1..arguments.len())
+            .filter(|&index| is(index, token))
+            .map(|index| index + offset)
+            .filter(|&index| index < arguments.len())
+            .collect::<Vec<_>>()
+    };
+    if is(0, "AUTH") {
+        // The password is last, after an optional username
+        vec![arguments.len() - 1]
+            .into_iter()
+            .filter(|&x| x > 0)
+            .collect()
+    } else if is(0, "HELLO") {
+        after("AUTH", 2)
+    } else if is(0, "MIGRATE") {
+        [after("AUTH", 1), after("AUTH2", 2)].concat()
+    } else if is(0, "CONFIG") && is(1, "SET") {
+        (2..arguments.len())
+            .step_by(2)
+            .filter(|&index| SECRET_PARAMETERS.iter().any(|x| is(index, x)))
+            .map(|index| index + 1)
+            .filter(|&index| index < arguments.len())
+            .collect()
+    } else if is(0, "ACL") && is(1, "SETUSER") {
+        // Rules adding or removing passwords (> and <) and their hashes (#
+        // and !)
+        (3..arguments.len())
+            .filter(|&index| matches!(arguments[index].first(), Some(b'>' | b'<' | b'#' | b'!')))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Cuts `text` to about `max_bytes`, noting its original size.
+fn shorten(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_owned();
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes)", &text[..end], text.len())
+}
+
+/// Keeps the leading elements fitting in `max_bytes` once encoded, at least
+/// one, followed by a note of how many were left out.
+fn keep_leading<T>(
+    items: &[T],
+    max_bytes: usize,
+    mut truncate: impl FnMut(&T) -> (T, usize),
+    note: impl FnOnce(String) -> T,
+) -> Vec<T> {
+    let mut kept = Vec::new();
+    let mut size = 0;
+    for (index, item) in items.iter().enumerate() {
+        let (item, len) = truncate(item);
+        size += len;
+        if index > 0 && size > max_bytes {
+            kept.push(note(format!(
+                "… (+{} more of {} elements)",
+                items.len() - index,
+                items.len()
+            )));
+            break;
+        }
+        kept.push(item);
+    }
+    kept
+}
+
+fn truncate_node(value: &RESP, max_bytes: usize) -> Option<RESP> {
+    let element = |x: &RESP| {
+        let x = x.truncate_for_logging(max_bytes);
+        let len = x.encoded_len();
+        (x, len)
+    };
+    let elements = |x: &[RESP]| keep_leading(x, max_bytes, element, RESP::BulkString);
+    match value {
+        RESP::BulkString(x) => Some(RESP::BulkString(shorten(x, max_bytes))),
+        #[cfg(feature = "resp3")]
+        RESP::BulkError(x) => Some(RESP::BulkError(shorten(x, max_bytes))),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { encoding, data } => Some(RESP::VerbatimString {
+            encoding: encoding.clone(),
+            data: shorten(data, max_bytes),
+        }),
+        RESP::Array(x) => Some(RESP::Array(elements(x))),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) => Some(RESP::Set(elements(x))),
+        #[cfg(feature = "resp3")]
+        RESP::Push(x) => Some(RESP::Push(elements(x))),
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => Some(RESP::Map(keep_leading(
+            x,
+            max_bytes,
+            |(key, value)| {
+                let (key, key_len) = element(key);
+                let (value, value_len) = element(value);
+                ((key, value), key_len + value_len)
+            },
+            |note| (RESP::BulkString(note), RESP::NullBulkString),
+        ))),
+        RESP::Inline(x) => {
+            let kept = keep_leading(
+                x,
+                max_bytes,
+                |x| {
+                    let x = shorten(x, max_bytes);
+                    let len = x.len() + 1;
+                    (x, len)
+                },
+                |note| note,
+            );
+            // Notes hold spaces, which inline arguments can't
+            Some(match kept == *x {
+                true => RESP::Inline(kept),
+                false => RESP::Array(kept.into_iter().map(RESP::BulkString).collect()),
+            })
+        }
+        _ => None,
+    }
+}
+
+impl RESP {
+    /// Copies the value with bulk strings longer than `max_bytes` cut and
+    /// aggregates keeping only the elements fitting in `max_bytes` once
+    /// encoded, both noting their original size, so the copy can be logged
+    /// as structured data. Unlike [`RESP::inspect`], the result is still a
+    /// value, and like it, it's only bounded for bounded nesting.
+    ///
+    /// Truncated inline commands become arrays of bulk strings, since inline
+    /// arguments can't hold the notes.
+    ///
+    /// ```
+    /// use resp_parser_rs::RESP;
+    ///
+    /// let reply = RESP::BulkString("x".repeat(1000));
+    /// assert_eq!(
+    ///     reply.truncate_for_logging(4),
+    ///     RESP::BulkString("xxxx… (1000 bytes)".to_owned())
+    /// );
+    /// ```
+    pub fn truncate_for_logging(&self, max_bytes: usize) -> RESP {
+        copy_with(self, &mut |x| truncate_node(x, max_bytes))
+    }
+
+    /// Copies the value with the secrets of commands replaced by
+    /// `<redacted>`, so it can be logged or captured: passwords given to
+    /// `AUTH`, `HELLO`, `MIGRATE` and `ACL SETUSER`, and the values of
+    /// password parameters set with `CONFIG SET`. Other values are copied
+    /// as they are.
+    ///
+    /// ```
+    /// use resp_parser_rs::RESP;
+    ///
+    /// let request = RESP::parse("*3\r\n$4\r\nAUTH\r\n$3\r\nbob\r\n$6\r\nsecret\r\n").unwrap();
+    /// assert_eq!(
+    ///     request.redacted().encode().unwrap(),
+    ///     b"*3\r\n$4\r\nAUTH\r\n$3\r\nbob\r\n$10\r\n<redacted>\r\n"
+    /// );
+    /// ```
+    pub fn redacted(&self) -> RESP {
+        match self {
+            Self::Array(elements) => {
+                let arguments = elements
+                    .iter()
+                    .map(|x| match x {
+                        Self::BulkString(x) => Some(x.as_bytes()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .unwrap_or_default();
+                let sensitive = sensitive_arguments(&arguments);
+                Self::Array(
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(index, x)| match sensitive.contains(&index) {
+                            true => Self::BulkString(REDACTED.to_owned()),
+                            false => copy_with(x, &mut |_| None),
+                        })
+                        .collect(),
+                )
+            }
+            Self::Inline(arguments) => {
+                let bytes = arguments.iter().map(|x| x.as_bytes()).collect::<Vec<_>>();
+                let sensitive = sensitive_arguments(&bytes);
+                Self::Inline(
+                    arguments
+                        .iter()
+                        .enumerate()
+                        .map(|(index, x)| match sensitive.contains(&index) {
+                            true => REDACTED.to_owned(),
+                            false => x.clone(),
+                        })
+                        .collect(),
+                )
+            }
+            x => copy_with(x, &mut |_| None),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl crate::Command {
+    /// Copies the command with its secrets replaced, like
+    /// [`RESP::redacted`].
+    pub fn redacted(&self) -> Self {
+        let mut arguments = vec![&self.name[..]];
+        arguments.extend(self.args.iter().map(|x| &x[..]));
+        let sensitive = sensitive_arguments(&arguments);
+        let args = self
+            .args
+            .iter()
+            .enumerate()
+            .map(|(index, x)| match sensitive.contains(&(index + 1)) {
+                true => bytes::Bytes::from_static(REDACTED.as_bytes()),
+                false => x.clone(),
+            })
+            .collect();
+        Self {
+            name: self.name.clone(),
+            args,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(arguments: &[&str]) -> RESP {
+        RESP::Array(
+            arguments
+                .iter()
+                .map(|x| RESP::BulkString((*x).to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn redact_commands() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["auth", "pw"], &["auth", REDACTED]),
+            (
+                &["HELLO", "3", "AUTH", "bob", "pw", "SETNAME", "x"],
+                &["HELLO", "3", "AUTH", "bob", REDACTED, "SETNAME", "x"],
+            ),
+            (
+                &["CONFIG", "SET", "maxmemory", "1gb", "requirepass", "pw"],
+                &["CONFIG", "SET", "maxmemory", "1gb", "requirepass", REDACTED],
+            ),
+            (
+                &[
+                    "MIGRATE", "h", "6379", "", "0", "5000", "AUTH2", "bob", "pw", "KEYS", "a",
+                ],
+                &[
+                    "MIGRATE", "h", "6379", "", "0", "5000", "AUTH2", "bob", REDACTED, "KEYS", "a",
+                ],
+            ),
+            (
+                &["ACL", "SETUSER", "bob", "on", ">pw", "~*"],
+                &["ACL", "SETUSER", "bob", "on", REDACTED, "~*"],
+            ),
+            (
+                &[
+                    "ACL",
+                    "SETUSER",
+                    "bob",
+                    "<old",
+                    "!5e88",
+                    "#5e88",
+                    "resetpass",
+                ],
+                &[
+                    "ACL",
+                    "SETUSER",
+                    "bob",
+                    REDACTED,
+                    REDACTED,
+                    REDACTED,
+                    "resetpass",
+                ],
+            ),
+            (&["GET", "pw"], &["GET", "pw"]),
+            (&["AUTH"], &["AUTH"]),
+        ];
+        for (request, redacted) in cases {
+            assert_eq!(command(request).redacted(), command(redacted));
+        }
+    }
+
+    #[test]
+    fn redact_inline() {
+        let request = RESP::Inline(vec!["AUTH".into(), "pw".into()]);
+        assert_eq!(
+            request.redacted(),
+            RESP::Inline(vec!["AUTH".into(), REDACTED.into()])
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn redact_other_values() {
+        let reply = RESP::parse("%1\r\n+auth\r\n*1\r\n$2\r\npw\r\n").unwrap();
+        assert_eq!(
+            reply.redacted(),
+            RESP::parse("%1\r\n+auth\r\n*1\r\n$2\r\npw\r\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn truncate_strings() {
+        let value = RESP::Array(vec![
+            RESP::BulkString("é".repeat(3)),
+            RESP::BulkString("short".to_owned()),
+        ]);
+        assert_eq!(
+            value.truncate_for_logging(5),
+            RESP::Array(vec![
+                RESP::BulkString("éé… (6 bytes)".to_owned()),
+                RESP::BulkString("… (+1 more of 2 elements)".to_owned()),
+            ])
+        );
+        assert_eq!(
+            value.truncate_for_logging(100),
+            RESP::Array(vec![
+                RESP::BulkString("é".repeat(3)),
+                RESP::BulkString("short".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn truncate_aggregates() {
+        let value = RESP::Array((1..=100).map(RESP::Integer).collect());
+        let mut expected: Vec<RESP> = (1..=3).map(RESP::Integer).collect();
+        expected.push(RESP::BulkString("… (+97 more of 100 elements)".to_owned()));
+        assert_eq!(value.truncate_for_logging(12), RESP::Array(expected));
+
+        let bulk = |x: &str| RESP::BulkString(x.to_owned());
+        let request = RESP::Inline(vec!["SET".into(), "key".into(), "v".repeat(50)]);
+        let truncated = request.truncate_for_logging(8);
+        assert_eq!(
+            truncated,
+            RESP::Array(vec![
+                bulk("SET"),
+                bulk("key"),
+                bulk("… (+1 more of 3 elements)")
+            ])
+        );
+        assert!(truncated.encode().is_ok());
+
+        let request = RESP::Inline(vec!["v".repeat(50)]);
+        let truncated = request.truncate_for_logging(4);
+        assert_eq!(truncated, RESP::Array(vec![bulk("vvvv… (50 bytes)")]));
+        assert!(truncated.encode().is_ok());
+
+        let request = RESP::Inline(vec!["GET".into(), "key".into()]);
+        assert_eq!(request.truncate_for_logging(20), request);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn truncate_nested() {
+        let value = RESP::WithAttributes {
+            attributes: vec![(RESP::Integer(0), RESP::BulkString("a".repeat(10)))],
+            value: Box::new(RESP::Map(vec![
+                (RESP::Integer(1), RESP::Integer(2)),
+                (RESP::Integer(3), RESP::Integer(4)),
+            ])),
+        };
+        let expected = RESP::WithAttributes {
+            attributes: vec![(
+                RESP::Integer(0),
+                RESP::BulkString("aaaaaaaa… (10 bytes)".to_owned()),
+            )],
+            value: Box::new(RESP::Map(vec![
+                (RESP::Integer(1), RESP::Integer(2)),
+                (
+                    RESP::BulkString("… (+1 more of 2 elements)".to_owned()),
+                    RESP::NullBulkString,
+                ),
+            ])),
+        };
+        assert_eq!(value.truncate_for_logging(8), expected);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn redact_command_type() {
+        use crate::{Command, FromResp};
+
+        let request = command(&["HELLO", "3", "auth", "bob", "pw"]);
+        let redacted = Command::from_resp(request).unwrap().redacted();
+        assert_eq!(redacted.args, ["3", "auth", "bob", REDACTED]);
+    }
+}
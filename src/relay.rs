@@ -0,0 +1,192 @@
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "futures")]
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::framing::frame_len;
+use crate::{DecodeError, ParseError};
+
+const RELAY_CHUNK: usize = 8 * 1024;
+
+/// Frames read from the source but not written yet.
+#[derive(Default)]
+struct Pending {
+    input: Vec<u8>,
+    output: Vec<u8>,
+    forwarded: u64,
+}
+
+impl Pending {
+    /// Moves the complete frames at the start of the input to the output,
+    /// leaving out those `hook` rejects.
+    fn split<F>(&mut self, hook: &mut F) -> Result<(), ParseError>
+    where
+        F: FnMut(&[u8]) -> bool,
+    {
+        let mut position = 0;
+        while let Some(length) = frame_len(&self.input[position..])? {
+            let frame = &self.input[position..position + length];
+            if hook(frame) {
+                self.output.extend_from_slice(frame);
+                self.forwarded += 1;
+            }
+            position += length;
+        }
+        self.input.drain(..position);
+        Ok(())
+    }
+
+    /// Ends the relay once the source is exhausted.
+    fn finish(self) -> Result<u64, DecodeError> {
+        match self.input.len() {
+            0 => Ok(self.forwarded),
+            _ => Err(ParseError::end_of_input("rest of the frame").into()),
+        }
+    }
+}
+
+/// Copies whole frames from `source` to `destination` until the source ends,
+/// returning how many were forwarded. Frames are only delimited, never
+/// decoded, so they are written back byte for byte.
+///
+/// `hook` sees the raw bytes of every frame and returns whether to forward
+/// it, pass `|_| true` to forward everything. A frame is held until it is
+/// complete, so a truncated one is never forwarded.
+///
+/// ```
+/// use resp_parser_rs::relay;
+///
+/// let mut output = Vec::new();
+/// let forwarded = relay(&b"+OK\r\n:1\r\n"[..], &mut output, |frame| frame[0] == b'+').unwrap();
+/// assert_eq!((forwarded, &output[..]), (1, &b"+OK\r\n"[..]));
+/// ```
+pub fn relay<R, W, F>(mut source: R, mut destination: W, mut hook: F) -> Result<u64, DecodeError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&[u8]) -> bool,
+{
+    let mut pending = Pending::default();
+    let mut chunk = [0; RELAY_CHUNK];
+    loop {
+        let read = match source.read(&mut chunk) {
+            Ok(x) => x,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if read == 0 {
+            return pending.finish();
+        }
+        pending.input.extend_from_slice(&chunk[..read]);
+        pending.split(&mut hook)?;
+        if !pending.output.is_empty() {
+            destination.write_all(&pending.output)?;
+            destination.flush()?;
+            pending.output.clear();
+        }
+    }
+}
+
+/// Like [`relay`], between an [`AsyncRead`] and an [`AsyncWrite`].
+#[cfg(feature = "futures")]
+pub async fn relay_async<R, W, F>(
+    mut source: R,
+    mut destination: W,
+    mut hook: F,
+) -> Result<u64, DecodeError>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&[u8]) -> bool,
+{
+    let mut pending = Pending::default();
+    let mut chunk = [0; RELAY_CHUNK];
+    loop {
+        let read = source.read(&mut chunk).await?;
+        if read == 0 {
+            return pending.finish();
+        }
+        pending.input.extend_from_slice(&chunk[..read]);
+        pending.split(&mut hook)?;
+        if !pending.output.is_empty() {
+            destination.write_all(&pending.output).await?;
+            destination.flush().await?;
+            pending.output.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    /// Reader handing out its data a few bytes at a time.
+    struct Chunked(&'static [u8]);
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let length = self.0.len().min(buf.len()).min(3);
+            buf[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn relay_verbatim() {
+        let input = b"*2\r\n$3\r\nGET\r\n$1\r\na\r\n:007\r\n$-1\r\n";
+        let mut output = Vec::new();
+        let mut seen = 0;
+        let forwarded = relay(Chunked(input), &mut output, |_| {
+            seen += 1;
+            true
+        });
+        assert!(matches!(forwarded, Ok(3)));
+        assert_eq!(seen, 3);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn relay_filtered() {
+        let mut output = Vec::new();
+        let forwarded = relay(Chunked(b":1\r\n-ERR x\r\n:2\r\n"), &mut output, |frame| {
+            !frame.starts_with(b"-")
+        });
+        assert!(matches!(forwarded, Ok(2)));
+        assert_eq!(output, b":1\r\n:2\r\n");
+    }
+
+    #[test]
+    fn relay_truncated() {
+        let mut output = Vec::new();
+        let forwarded = relay(Chunked(b"+OK\r\n$5\r\nHel"), &mut output, |_| true);
+        assert!(matches!(forwarded, Err(DecodeError::Parse(_))));
+        assert_eq!(output, b"+OK\r\n");
+
+        let forwarded = relay(Chunked(b"$x\r\n"), &mut output, |_| true);
+        assert!(matches!(forwarded, Err(DecodeError::Parse(_))));
+    }
+
+    #[test]
+    fn relay_bad_terminator() {
+        let mut output = Vec::new();
+        let forwarded = relay(Chunked(b":1\r\n$3\r\nabcXY+OK\r\n"), &mut output, |_| true);
+        assert!(matches!(forwarded, Err(DecodeError::Parse(_))));
+        assert_eq!(output, b":1\r\n");
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn relay_async_verbatim() {
+        let input = b"+OK\r\n*1\r\n:1\r\n";
+        let mut output = Vec::new();
+        let forwarded = futures::executor::block_on(relay_async(
+            &input[..],
+            futures::io::Cursor::new(&mut output),
+            |_| true,
+        ));
+        assert!(matches!(forwarded, Ok(2)));
+        assert_eq!(output, input);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, integer, is_null, optional_text, strings, text, Fields};
+
+/// How the first key of a [`KeySpec`] is found among the arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeginSearch {
+    /// At a fixed argument index.
+    Index(i64),
+    /// After a keyword, searched from `start_from`, negative values
+    /// searching backwards from the end.
+    Keyword { keyword: String, start_from: i64 },
+    /// Any other way, including those added by later server versions.
+    Unknown,
+}
+
+/// Which arguments of a [`KeySpec`] are keys, once the first one is found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FindKeys {
+    /// Keys up to `last_key`, relative to the first one and negative
+    /// counting from the end, every `key_step` arguments.
+    Range {
+        last_key: i64,
+        key_step: i64,
+        limit: i64,
+    },
+    /// Keys counted by the argument at `keynum_index`.
+    Keynum {
+        keynum_index: i64,
+        first_key: i64,
+        key_step: i64,
+    },
+    /// Any other way, including those added by later server versions.
+    Unknown,
+}
+
+/// Where a command takes keys, from `COMMAND INFO`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySpec {
+    pub notes: Option<String>,
+    /// Access flags, e.g. `RW` or `INSERT`.
+    pub flags: Vec<String>,
+    pub begin_search: BeginSearch,
+    pub find_keys: FindKeys,
+}
+
+/// An entry of `COMMAND INFO` or `COMMAND`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandInfo {
+    pub name: String,
+    /// Amount of arguments including the command name, negative when it's a
+    /// minimum.
+    pub arity: i64,
+    pub flags: Vec<String>,
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    /// Only sent by Redis 6 and later.
+    pub acl_categories: Vec<String>,
+    /// Only sent by Redis 7 and later, as are the fields below.
+    pub tips: Vec<String>,
+    pub key_specs: Vec<KeySpec>,
+    pub subcommands: Vec<CommandInfo>,
+}
+
+/// An argument of a command, from `COMMAND DOCS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandArgument {
+    pub name: String,
+    /// Type of the argument, e.g. `key`, `integer` or `oneof`.
+    pub kind: String,
+    pub token: Option<String>,
+    pub summary: Option<String>,
+    pub since: Option<String>,
+    /// E.g. `optional` or `multiple`.
+    pub flags: Vec<String>,
+    /// Nested arguments of `oneof` and `block` arguments.
+    pub arguments: Vec<CommandArgument>,
+}
+
+/// Documentation of a command, from `COMMAND DOCS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandDocs {
+    pub summary: Option<String>,
+    pub since: Option<String>,
+    pub group: Option<String>,
+    pub complexity: Option<String>,
+    pub doc_flags: Vec<String>,
+    pub deprecated_since: Option<String>,
+    pub replaced_by: Option<String>,
+    /// Versions and the changes they brought.
+    pub history: Vec<(String, String)>,
+    pub arguments: Vec<CommandArgument>,
+    pub subcommands: Vec<(String, CommandDocs)>,
+}
+
+fn optional_strings(fields: &Fields, name: &'static str) -> Result<Vec<String>, ConversionError> {
+    fields
+        .get(name)
+        .map(|x| strings(x, name))
+        .unwrap_or(Ok(Vec::new()))
+}
+
+fn optional_list<T>(value: Option<&RESP>, expected: &'static str) -> Result<Vec<T>, ConversionError>
+where
+    T: for<'a> TryFrom<&'a RESP, Error = ConversionError>,
+{
+    match value {
+        Some(x) => elements(x, expected)?.iter().map(T::try_from).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Entries of a map, or of a flat array of keys and values, by name.
+fn named<T>(value: &RESP, expected: &'static str) -> Result<Vec<(String, T)>, ConversionError>
+where
+    T: for<'a> TryFrom<&'a RESP, Error = ConversionError>,
+{
+    Fields::new(value, expected)?
+        .entries
+        .into_iter()
+        .map(|(name, value)| Ok((text(name, "command name")?.to_owned(), T::try_from(value)?)))
+        .collect()
+}
+
+impl TryFrom<&RESP> for BeginSearch {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "begin_search")?;
+        let spec = Fields::new(fields.required("spec")?, "begin_search spec")?;
+        Ok(match text(fields.required("type")?, "begin_search type")? {
+            "index" => Self::Index(integer(spec.required("index")?, "index")?),
+            "keyword" => Self::Keyword {
+                keyword: text(spec.required("keyword")?, "keyword")?.to_owned(),
+                start_from: integer(spec.required("startfrom")?, "startfrom")?,
+            },
+            _ => Self::Unknown,
+        })
+    }
+}
+
+impl TryFrom<&RESP> for FindKeys {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "find_keys")?;
+        let spec = Fields::new(fields.required("spec")?, "find_keys spec")?;
+        Ok(match text(fields.required("type")?, "find_keys type")? {
+            "range" => Self::Range {
+                last_key: integer(spec.required("lastkey")?, "lastkey")?,
+                key_step: integer(spec.required("keystep")?, "keystep")?,
+                limit: integer(spec.required("limit")?, "limit")?,
+            },
+            "keynum" => Self::Keynum {
+                keynum_index: integer(spec.required("keynumidx")?, "keynumidx")?,
+                first_key: integer(spec.required("firstkey")?, "firstkey")?,
+                key_step: integer(spec.required("keystep")?, "keystep")?,
+            },
+            _ => Self::Unknown,
+        })
+    }
+}
+
+impl TryFrom<&RESP> for KeySpec {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "key spec")?;
+        Ok(Self {
+            notes: optional_text(&fields, "notes")?,
+            flags: optional_strings(&fields, "flags")?,
+            begin_search: BeginSearch::try_from(fields.required("begin_search")?)?,
+            find_keys: FindKeys::try_from(fields.required("find_keys")?)?,
+        })
+    }
+}
+
+impl CommandInfo {
+    /// Decodes the reply of `COMMAND INFO` or `COMMAND`, with `None` for
+    /// commands that don't exist.
+    pub fn from_command_info(value: &RESP) -> Result<Vec<Option<Self>>, ConversionError> {
+        elements(value, "COMMAND INFO reply")?
+            .iter()
+            .map(|x| {
+                if is_null(x) {
+                    Ok(None)
+                } else {
+                    Self::try_from(x).map(Some)
+                }
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for CommandInfo {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = elements(value, "command info")?;
+        let [name, arity, flags, first_key, last_key, step, rest @ ..] = fields else {
+            return Err(ConversionError::unexpected_type("command info", value));
+        };
+        Ok(Self {
+            name: text(name, "command name")?.to_owned(),
+            arity: integer(arity, "arity")?,
+            flags: strings(flags, "command flags")?,
+            first_key: integer(first_key, "first key")?,
+            last_key: integer(last_key, "last key")?,
+            step: integer(step, "step")?,
+            acl_categories: rest
+                .first()
+                .map(|x| strings(x, "ACL categories"))
+                .unwrap_or(Ok(Vec::new()))?,
+            tips: rest
+                .get(1)
+                .map(|x| strings(x, "tips"))
+                .unwrap_or(Ok(Vec::new()))?,
+            key_specs: optional_list(rest.get(2), "key specs")?,
+            subcommands: optional_list(rest.get(3), "subcommands")?,
+        })
+    }
+}
+
+impl TryFrom<&RESP> for CommandArgument {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "command argument")?;
+        Ok(Self {
+            name: text(fields.required("name")?, "argument name")?.to_owned(),
+            kind: text(fields.required("type")?, "argument type")?.to_owned(),
+            token: optional_text(&fields, "token")?,
+            summary: optional_text(&fields, "summary")?,
+            since: optional_text(&fields, "since")?,
+            flags: optional_strings(&fields, "flags")?,
+            arguments: optional_list(fields.get("arguments"), "arguments")?,
+        })
+    }
+}
+
+impl CommandDocs {
+    /// Decodes the reply of `COMMAND DOCS`, by command name.
+    pub fn from_command_docs(value: &RESP) -> Result<Vec<(String, Self)>, ConversionError> {
+        named(value, "COMMAND DOCS reply")
+    }
+}
+
+impl TryFrom<&RESP> for CommandDocs {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "command docs")?;
+        let history = match fields.get("history") {
+            Some(x) => elements(x, "history")?
+                .iter()
+                .map(|x| match elements(x, "history entry")? {
+                    [version, change] => Ok((
+                        text(version, "version")?.to_owned(),
+                        text(change, "change")?.to_owned(),
+                    )),
+                    _ => Err(ConversionError::unexpected_type("history entry", x)),
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            summary: optional_text(&fields, "summary")?,
+            since: optional_text(&fields, "since")?,
+            group: optional_text(&fields, "group")?,
+            complexity: optional_text(&fields, "complexity")?,
+            doc_flags: optional_strings(&fields, "doc_flags")?,
+            deprecated_since: optional_text(&fields, "deprecated_since")?,
+            replaced_by: optional_text(&fields, "replaced_by")?,
+            history,
+            arguments: optional_list(fields.get("arguments"), "arguments")?,
+            subcommands: match fields.get("subcommands") {
+                Some(x) => named(x, "subcommands")?,
+                None => Vec::new(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn command_info() {
+        let reply = RESP::parse(
+            "*2\r\n\
+             *10\r\n$3\r\nget\r\n:2\r\n~2\r\n+readonly\r\n+fast\r\n:1\r\n:1\r\n:1\r\n\
+             ~2\r\n+@read\r\n+@string\r\n*0\r\n\
+             *1\r\n%3\r\n+flags\r\n~2\r\n+RO\r\n+access\r\n\
+             +begin_search\r\n%2\r\n+type\r\n+index\r\n+spec\r\n%1\r\n+index\r\n:1\r\n\
+             +find_keys\r\n%2\r\n+type\r\n+range\r\n+spec\r\n%3\r\n+lastkey\r\n:0\r\n+keystep\r\n:1\r\n+limit\r\n:0\r\n\
+             *0\r\n\
+             _\r\n",
+        )
+        .unwrap();
+        let commands = CommandInfo::from_command_info(&reply).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(commands[1].is_none());
+        let get = commands[0].as_ref().unwrap();
+        assert_eq!(get.name, "get");
+        assert_eq!(get.arity, 2);
+        assert_eq!(get.flags, vec!["readonly", "fast"]);
+        assert_eq!(get.acl_categories, vec!["@read", "@string"]);
+        assert_eq!(
+            get.key_specs,
+            vec![KeySpec {
+                notes: None,
+                flags: vec!["RO".to_owned(), "access".to_owned()],
+                begin_search: BeginSearch::Index(1),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                },
+            }]
+        );
+
+        // Redis 5 and earlier
+        let reply =
+            RESP::parse("*1\r\n*6\r\n$4\r\nping\r\n:-1\r\n*1\r\n+stale\r\n:0\r\n:0\r\n:0\r\n")
+                .unwrap();
+        let commands = CommandInfo::from_command_info(&reply).unwrap();
+        let ping = commands[0].as_ref().unwrap();
+        assert_eq!(ping.arity, -1);
+        assert!(ping.key_specs.is_empty());
+    }
+
+    #[test]
+    fn command_docs() {
+        // RESP2 flat arrays
+        let reply = RESP::parse(
+            "*2\r\n$3\r\nget\r\n\
+             *10\r\n$7\r\nsummary\r\n$14\r\nGet the value.\r\n$5\r\nsince\r\n$5\r\n1.0.0\r\n\
+             $5\r\ngroup\r\n$6\r\nstring\r\n\
+             $7\r\nhistory\r\n*1\r\n*2\r\n$5\r\n2.0.0\r\n$7\r\nChanged\r\n\
+             $9\r\narguments\r\n*1\r\n*4\r\n$4\r\nname\r\n$3\r\nkey\r\n$4\r\ntype\r\n$3\r\nkey\r\n",
+        )
+        .unwrap();
+        let docs = CommandDocs::from_command_docs(&reply).unwrap();
+        assert_eq!(docs[0].0, "get");
+        let get = &docs[0].1;
+        assert_eq!(get.summary.as_deref(), Some("Get the value."));
+        assert_eq!(get.group.as_deref(), Some("string"));
+        assert_eq!(get.complexity, None);
+        assert_eq!(
+            get.history,
+            vec![("2.0.0".to_owned(), "Changed".to_owned())]
+        );
+        assert_eq!(get.arguments[0].name, "key");
+        assert_eq!(get.arguments[0].kind, "key");
+        assert!(get.subcommands.is_empty());
+    }
+
+    #[test]
+    fn command_invalid() {
+        let reply = RESP::parse("*1\r\n*2\r\n$3\r\nget\r\n:2\r\n").unwrap();
+        assert!(matches!(
+            CommandInfo::from_command_info(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+}
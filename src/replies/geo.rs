@@ -0,0 +1,161 @@
+use crate::{ConversionError, RESP};
+
+use super::{double, elements, integer, strip, text};
+
+/// Position of a member of a geospatial index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+impl Coordinates {
+    /// Decodes a `GEOPOS` reply, with `None` for members that don't exist.
+    pub fn from_geopos(value: &RESP) -> Result<Vec<Option<Self>>, ConversionError> {
+        elements(value, "GEOPOS reply")?
+            .iter()
+            .map(|x| {
+                if x.is_null() {
+                    Ok(None)
+                } else {
+                    Self::try_from(x).map(Some)
+                }
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for Coordinates {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        match elements(value, "coordinates")? {
+            [longitude, latitude] => Ok(Self {
+                longitude: double(longitude, "longitude")?,
+                latitude: double(latitude, "latitude")?,
+            }),
+            _ => Err(ConversionError::unexpected_type("coordinates", value)),
+        }
+    }
+}
+
+/// A member found by `GEOSEARCH` or `GEORADIUS`, with whatever the `WITH*`
+/// flags of the command asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMember {
+    pub member: String,
+    /// Present with `WITHDIST`, in the unit of the query.
+    pub distance: Option<f64>,
+    /// Present with `WITHHASH`.
+    pub hash: Option<i64>,
+    /// Present with `WITHCOORD`.
+    pub coordinates: Option<Coordinates>,
+}
+
+impl GeoMember {
+    /// Decodes the reply of `GEOSEARCH` or `GEORADIUS`.
+    pub fn from_search(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "GEOSEARCH reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for GeoMember {
+    type Error = ConversionError;
+
+    /// Accepts both a bare member name and the array sent when `WITH*`
+    /// flags are used, telling the fields apart by their type as they always
+    /// come in the same order.
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let RESP::Array(fields) = strip(value) else {
+            return Ok(Self {
+                member: text(value, "GEOSEARCH member")?.to_owned(),
+                distance: None,
+                hash: None,
+                coordinates: None,
+            });
+        };
+        let Some((member, fields)) = fields.split_first() else {
+            return Err(ConversionError::unexpected_type("GEOSEARCH member", value));
+        };
+        let mut result = Self {
+            member: text(member, "member name")?.to_owned(),
+            distance: None,
+            hash: None,
+            coordinates: None,
+        };
+        for field in fields {
+            match strip(field) {
+                RESP::Integer(_) => result.hash = Some(integer(field, "geohash")?),
+                RESP::Array(_) => result.coordinates = Some(Coordinates::try_from(field)?),
+                _ => result.distance = Some(double(field, "distance")?),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geopos() {
+        let reply = RESP::parse(
+            "*2\r\n*2\r\n$18\r\n13.361389338970184\r\n$16\r\n38.1155563954963\r\n*-1\r\n",
+        )
+        .unwrap();
+        let positions = Coordinates::from_geopos(&reply).unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(
+            positions[0],
+            Some(Coordinates {
+                longitude: 13.361389338970184,
+                latitude: 38.1155563954963,
+            })
+        );
+        assert_eq!(positions[1], None);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn geosearch() {
+        let reply = RESP::parse("*2\r\n$7\r\nPalermo\r\n$7\r\nCatania\r\n").unwrap();
+        let members = GeoMember::from_search(&reply).unwrap();
+        assert_eq!(members[1].member, "Catania");
+        assert_eq!(members[1].distance, None);
+
+        // WITHDIST WITHHASH WITHCOORD, RESP3
+        let reply =
+            RESP::parse("*1\r\n*4\r\n$7\r\nPalermo\r\n,190.4424\r\n:3479099956230698\r\n*2\r\n,13.36\r\n,38.11\r\n")
+                .unwrap();
+        let members = GeoMember::from_search(&reply).unwrap();
+        assert_eq!(members[0].member, "Palermo");
+        assert_eq!(members[0].distance, Some(190.4424));
+        assert_eq!(members[0].hash, Some(3479099956230698));
+        assert_eq!(members[0].coordinates.map(|x| x.latitude), Some(38.11));
+
+        // WITHCOORD only, RESP2
+        let reply =
+            RESP::parse("*1\r\n*2\r\n$7\r\nPalermo\r\n*2\r\n$2\r\n13\r\n$2\r\n38\r\n").unwrap();
+        let members = GeoMember::from_search(&reply).unwrap();
+        assert_eq!(members[0].distance, None);
+        assert_eq!(members[0].coordinates.map(|x| x.longitude), Some(13.0));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn geo_invalid() {
+        let reply = RESP::parse("*1\r\n*3\r\n,1\r\n,2\r\n,3\r\n").unwrap();
+        assert!(matches!(
+            Coordinates::from_geopos(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert!(matches!(
+            GeoMember::from_search(&RESP::Integer(1)),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{ConversionError, FromResp, RESP};
+
+/// Type of the value stored at a key, as replied by `TYPE` or listed by
+/// `SCAN ... TYPE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    /// The key doesn't exist.
+    None,
+    String,
+    List,
+    Set,
+    SortedSet,
+    Hash,
+    Stream,
+    /// Any other type, like those of modules.
+    Unknown(String),
+}
+
+impl KeyType {
+    /// The string replied for this type.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::None => "none",
+            Self::String => "string",
+            Self::List => "list",
+            Self::Set => "set",
+            Self::SortedSet => "zset",
+            Self::Hash => "hash",
+            Self::Stream => "stream",
+            Self::Unknown(x) => x,
+        }
+    }
+}
+
+impl FromResp for KeyType {
+    fn from_resp(value: RESP) -> Result<Self, ConversionError> {
+        let text = String::from_resp(value)?;
+        Ok(match text.to_ascii_lowercase().as_str() {
+            "none" => Self::None,
+            "string" => Self::String,
+            "list" => Self::List,
+            "set" => Self::Set,
+            "zset" => Self::SortedSet,
+            "hash" => Self::Hash,
+            "stream" => Self::Stream,
+            _ => Self::Unknown(text),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FromResp, RESP};
+
+    #[test]
+    fn key_type() {
+        let reply = RESP::parse("+zset\r\n").unwrap();
+        assert_eq!(KeyType::from_resp(reply), Ok(KeyType::SortedSet));
+        assert_eq!(
+            KeyType::from_resp(RESP::SimpleString("ReJSON-RL".into())),
+            Ok(KeyType::Unknown("ReJSON-RL".to_owned()))
+        );
+        assert_eq!(
+            KeyType::Unknown("vectorset".to_owned()).as_str(),
+            "vectorset"
+        );
+        assert!(KeyType::from_resp(RESP::Integer(1)).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{ConversionError, RESP};
+
+use super::Message;
+
+/// A keyspace notification, from either a `__keyspace@<db>__:<key>` or a
+/// `__keyevent@<db>__:<event>` channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyspaceEvent {
+    pub db: u32,
+    pub key: String,
+    /// Name of the event, e.g. `set`, `del` or `expired`.
+    pub event: String,
+}
+
+/// Database and the rest of a channel starting with `prefix`.
+fn split_channel<'a>(channel: &'a str, prefix: &str) -> Option<(u32, &'a str)> {
+    let (db, rest) = channel.strip_prefix(prefix)?.split_once("__:")?;
+    Some((db.parse().ok()?, rest))
+}
+
+impl KeyspaceEvent {
+    /// Recognizes a notification from the channel and payload of a message,
+    /// `None` for other channels.
+    pub fn parse(channel: &str, payload: &str) -> Option<Self> {
+        let (db, key, event) = if let Some((db, key)) = split_channel(channel, "__keyspace@") {
+            (db, key, payload)
+        } else {
+            let (db, event) = split_channel(channel, "__keyevent@")?;
+            (db, payload, event)
+        };
+        Some(Self {
+            db,
+            key: key.to_owned(),
+            event: event.to_owned(),
+        })
+    }
+
+    pub fn from_message(message: &Message) -> Option<Self> {
+        Self::parse(&message.channel, &message.payload)
+    }
+}
+
+impl TryFrom<&RESP> for KeyspaceEvent {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        Self::from_message(&Message::try_from(value)?)
+            .ok_or_else(|| ConversionError::unexpected_type("keyspace notification", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyspace_channels() {
+        assert_eq!(
+            KeyspaceEvent::parse("__keyspace@0__:user:1", "expired"),
+            Some(KeyspaceEvent {
+                db: 0,
+                key: "user:1".to_owned(),
+                event: "expired".to_owned(),
+            })
+        );
+        assert_eq!(
+            KeyspaceEvent::parse("__keyevent@12__:del", "a__:b"),
+            Some(KeyspaceEvent {
+                db: 12,
+                key: "a__:b".to_owned(),
+                event: "del".to_owned(),
+            })
+        );
+        assert_eq!(KeyspaceEvent::parse("news", "hi"), None);
+        assert_eq!(KeyspaceEvent::parse("__keyspace@x__:key", "set"), None);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn keyspace_pmessage() {
+        let reply = RESP::parse(
+            ">4\r\n$8\r\npmessage\r\n$11\r\n__keyspace*\r\n$17\r\n__keyspace@3__:k1\r\n$3\r\nset\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            KeyspaceEvent::try_from(&reply),
+            Ok(KeyspaceEvent {
+                db: 3,
+                key: "k1".to_owned(),
+                event: "set".to_owned(),
+            })
+        );
+    }
+}
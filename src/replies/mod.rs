@@ -0,0 +1,143 @@
+//! Typed decoding of the replies of specific commands, whose nested and
+//! optional shapes are fiddly to unpack by hand.
+//!
+//! Both the RESP2 and RESP3 forms of each reply are accepted, e.g. maps sent
+//! as flat arrays of keys and values, or doubles sent as bulk strings.
+
+mod command;
+mod geo;
+mod key_type;
+mod keyspace;
+mod pubsub;
+mod role;
+mod sentinel;
+mod slowlog;
+mod tracking;
+mod transaction;
+mod xinfo;
+
+pub use command::{BeginSearch, CommandArgument, CommandDocs, CommandInfo, FindKeys, KeySpec};
+pub use geo::{Coordinates, GeoMember};
+pub use key_type::KeyType;
+pub use keyspace::KeyspaceEvent;
+pub use pubsub::{Message, SubscriptionKind, SubscriptionTracker, SubscriptionUpdate};
+pub use role::{ConnectedReplica, Role};
+pub use sentinel::{
+    MasterAddress, SentinelEvent, SentinelInstance, SentinelMaster, SentinelReplica,
+};
+pub use slowlog::SlowlogEntry;
+pub use tracking::Invalidation;
+pub use transaction::{CommandReply, ExecOutcome, Transaction};
+pub use xinfo::{ConsumerInfo, GroupInfo, StreamEntry, StreamInfo};
+
+use crate::{ConversionError, RESP};
+
+/// The value itself, without its attributes.
+fn strip(value: &RESP) -> &RESP {
+    value.inner()
+}
+
+fn elements<'a>(value: &'a RESP, expected: &'static str) -> Result<&'a [RESP], ConversionError> {
+    match strip(value) {
+        RESP::Array(x) => Ok(x),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) | RESP::Push(x) => Ok(x),
+        _ => Err(ConversionError::unexpected_type(expected, value)),
+    }
+}
+
+fn text<'a>(value: &'a RESP, expected: &'static str) -> Result<&'a str, ConversionError> {
+    match strip(value) {
+        RESP::SimpleString(x) => Ok(x),
+        RESP::BulkString(x) => Ok(x),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { data, .. } => Ok(data),
+        _ => Err(ConversionError::unexpected_type(expected, value)),
+    }
+}
+
+fn strings(value: &RESP, expected: &'static str) -> Result<Vec<String>, ConversionError> {
+    elements(value, expected)?
+        .iter()
+        .map(|x| text(x, expected).map(str::to_owned))
+        .collect()
+}
+
+fn integer(value: &RESP, expected: &'static str) -> Result<i64, ConversionError> {
+    match strip(value) {
+        RESP::Integer(x) => Ok(*x),
+        _ => text(value, expected)?
+            .parse()
+            .map_err(|_| ConversionError::unexpected_type(expected, value)),
+    }
+}
+
+fn double(value: &RESP, expected: &'static str) -> Result<f64, ConversionError> {
+    match strip(value) {
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) => Ok(*x),
+        RESP::Integer(x) => Ok(*x as f64),
+        _ => text(value, expected)?
+            .parse()
+            .map_err(|_| ConversionError::unexpected_type(expected, value)),
+    }
+}
+
+fn unsigned(value: &RESP, expected: &'static str) -> Result<u64, ConversionError> {
+    u64::try_from(integer(value, expected)?)
+        .map_err(|_| ConversionError::out_of_range(expected, value))
+}
+
+fn port_number(value: &RESP) -> Result<u16, ConversionError> {
+    u16::try_from(integer(value, "port")?).map_err(|_| ConversionError::out_of_range("port", value))
+}
+
+/// Named fields of a map, or of a flat array of names and values.
+struct Fields<'a> {
+    value: &'a RESP,
+    entries: Vec<(&'a RESP, &'a RESP)>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a RESP, expected: &'static str) -> Result<Self, ConversionError> {
+        let entries = match strip(value) {
+            #[cfg(feature = "resp3")]
+            RESP::Map(x) => x.iter().map(|(k, v)| (k, v)).collect(),
+            RESP::Array(x) if x.len().is_multiple_of(2) => {
+                x.chunks_exact(2).map(|x| (&x[0], &x[1])).collect()
+            }
+            _ => return Err(ConversionError::unexpected_type(expected, value)),
+        };
+        Ok(Self { value, entries })
+    }
+
+    fn get(&self, name: &str) -> Option<&'a RESP> {
+        self.entries
+            .iter()
+            .find(|(key, _)| text(key, "field name").is_ok_and(|x| x == name))
+            .map(|(_, value)| *value)
+    }
+
+    fn required(&self, name: &'static str) -> Result<&'a RESP, ConversionError> {
+        self.get(name)
+            .ok_or_else(|| ConversionError::missing_field(name, self.value))
+    }
+}
+
+fn is_null(value: &RESP) -> bool {
+    value.is_null()
+}
+
+fn optional_text(fields: &Fields, name: &'static str) -> Result<Option<String>, ConversionError> {
+    fields
+        .get(name)
+        .map(|x| text(x, name).map(str::to_owned))
+        .transpose()
+}
+
+fn optional_unsigned(fields: &Fields, name: &'static str) -> Result<Option<u64>, ConversionError> {
+    match fields.get(name) {
+        Some(x) if !is_null(x) => unsigned(x, name).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeSet;
+
+use crate::{ConversionError, RESP};
+
+use super::{elements, is_null, text, unsigned};
+
+/// A message received on a subscribed channel, sent as a push in RESP3 and
+/// as an array in RESP2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// The matching pattern, for messages received through `PSUBSCRIBE`.
+    pub pattern: Option<String>,
+    pub channel: String,
+    pub payload: String,
+}
+
+impl TryFrom<&RESP> for Message {
+    type Error = ConversionError;
+
+    /// Accepts `message`, `smessage` and `pmessage` frames.
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = elements(value, "pub/sub message")?;
+        let kind = fields
+            .first()
+            .map(|x| text(x, "message kind"))
+            .transpose()?;
+        let (pattern, channel, payload) = match (kind, fields) {
+            (Some("message" | "smessage"), [_, channel, payload]) => (None, channel, payload),
+            (Some("pmessage"), [_, pattern, channel, payload]) => {
+                (Some(text(pattern, "pattern")?.to_owned()), channel, payload)
+            }
+            _ => return Err(ConversionError::unexpected_type("pub/sub message", value)),
+        };
+        Ok(Self {
+            pattern,
+            channel: text(channel, "channel")?.to_owned(),
+            payload: text(payload, "payload")?.to_owned(),
+        })
+    }
+}
+
+/// What a subscription is made on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// `SUBSCRIBE`
+    Channel,
+    /// `PSUBSCRIBE`
+    Pattern,
+    /// `SSUBSCRIBE`
+    Shard,
+}
+
+/// A pub/sub frame, as handled by a [`SubscriptionTracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionUpdate {
+    Subscribed {
+        kind: SubscriptionKind,
+        name: String,
+        /// Subscriptions of the connection according to the server.
+        count: u64,
+    },
+    Unsubscribed {
+        kind: SubscriptionKind,
+        /// `None` when unsubscribing from everything while nothing was
+        /// subscribed.
+        name: Option<String>,
+        count: u64,
+        /// Whether no subscriptions are left, the connection being back to
+        /// accepting regular commands in RESP2.
+        last: bool,
+    },
+    Message(Message),
+}
+
+/// Keeps track of the channels and patterns a connection is subscribed to,
+/// from the confirmations and messages the server sends.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionTracker {
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    shard_channels: BTreeSet<String>,
+}
+
+impl SubscriptionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, kind: SubscriptionKind) -> &mut BTreeSet<String> {
+        match kind {
+            SubscriptionKind::Channel => &mut self.channels,
+            SubscriptionKind::Pattern => &mut self.patterns,
+            SubscriptionKind::Shard => &mut self.shard_channels,
+        }
+    }
+
+    /// Updates the state from `frame`, `Ok(None)` meaning it isn't a pub/sub
+    /// frame, like the reply of a regular command sent over RESP3.
+    pub fn handle(&mut self, frame: &RESP) -> Result<Option<SubscriptionUpdate>, ConversionError> {
+        let Ok(fields) = elements(frame, "pub/sub frame") else {
+            return Ok(None);
+        };
+        let Some(kind) = fields.first().and_then(|x| text(x, "pub/sub kind").ok()) else {
+            return Ok(None);
+        };
+        let (subscribe, kind) = match kind {
+            "message" | "pmessage" | "smessage" => {
+                return Message::try_from(frame).map(|x| Some(SubscriptionUpdate::Message(x)));
+            }
+            "subscribe" => (true, SubscriptionKind::Channel),
+            "psubscribe" => (true, SubscriptionKind::Pattern),
+            "ssubscribe" => (true, SubscriptionKind::Shard),
+            "unsubscribe" => (false, SubscriptionKind::Channel),
+            "punsubscribe" => (false, SubscriptionKind::Pattern),
+            "sunsubscribe" => (false, SubscriptionKind::Shard),
+            _ => return Ok(None),
+        };
+        let [_, name, count] = fields else {
+            return Err(ConversionError::unexpected_type(
+                "subscription confirmation",
+                frame,
+            ));
+        };
+        let count = unsigned(count, "subscription count")?;
+        if subscribe {
+            let name = text(name, "channel")?.to_owned();
+            self.set(kind).insert(name.clone());
+            return Ok(Some(SubscriptionUpdate::Subscribed { kind, name, count }));
+        }
+
+        let name = if is_null(name) {
+            self.set(kind).clear();
+            None
+        } else {
+            let name = text(name, "channel")?.to_owned();
+            self.set(kind).remove(&name);
+            Some(name)
+        };
+        Ok(Some(SubscriptionUpdate::Unsubscribed {
+            kind,
+            name,
+            count,
+            last: !self.is_subscribed(),
+        }))
+    }
+
+    pub fn channels(&self) -> &BTreeSet<String> {
+        &self.channels
+    }
+
+    pub fn patterns(&self) -> &BTreeSet<String> {
+        &self.patterns
+    }
+
+    pub fn shard_channels(&self) -> &BTreeSet<String> {
+        &self.shard_channels
+    }
+
+    /// Amount of subscriptions of every kind.
+    pub fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len() + self.shard_channels.len()
+    }
+
+    pub fn is_subscribed(&self) -> bool {
+        self.count() > 0
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pubsub_message() {
+        let reply = RESP::parse(">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n").unwrap();
+        assert_eq!(
+            Message::try_from(&reply),
+            Ok(Message {
+                pattern: None,
+                channel: "news".to_owned(),
+                payload: "hi".to_owned(),
+            })
+        );
+
+        let reply =
+            RESP::parse("*4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$2\r\nhi\r\n").unwrap();
+        assert_eq!(
+            Message::try_from(&reply).map(|x| x.pattern),
+            Ok(Some("n*".to_owned()))
+        );
+
+        let reply = RESP::parse(">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n").unwrap();
+        assert!(matches!(
+            Message::try_from(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn subscription_tracker() {
+        let mut tracker = SubscriptionTracker::new();
+        let mut handle = |frame: &str| tracker.handle(&RESP::parse(frame).unwrap());
+        assert_eq!(
+            handle(">3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n"),
+            Ok(Some(SubscriptionUpdate::Subscribed {
+                kind: SubscriptionKind::Channel,
+                name: "a".to_owned(),
+                count: 1,
+            }))
+        );
+        handle(">3\r\n$10\r\npsubscribe\r\n$2\r\nb*\r\n:2\r\n").unwrap();
+        assert!(matches!(
+            handle(">3\r\n$7\r\nmessage\r\n$1\r\na\r\n$2\r\nhi\r\n"),
+            Ok(Some(SubscriptionUpdate::Message(_)))
+        ));
+        assert_eq!(handle("+PONG\r\n"), Ok(None));
+        assert!(matches!(
+            handle(">2\r\n$9\r\nsubscribe\r\n$1\r\na\r\n"),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        assert_eq!(
+            handle("*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n"),
+            Ok(Some(SubscriptionUpdate::Unsubscribed {
+                kind: SubscriptionKind::Channel,
+                name: Some("a".to_owned()),
+                count: 1,
+                last: false,
+            }))
+        );
+        assert!(matches!(
+            handle("*3\r\n$12\r\npunsubscribe\r\n$2\r\nb*\r\n:0\r\n"),
+            Ok(Some(SubscriptionUpdate::Unsubscribed { last: true, .. }))
+        ));
+        assert!(matches!(
+            handle("*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n"),
+            Ok(Some(SubscriptionUpdate::Unsubscribed {
+                name: None,
+                last: true,
+                ..
+            }))
+        ));
+        assert!(!tracker.is_subscribed());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, integer, port_number, strings, text, unsigned};
+
+/// A replica connected to a master, as listed by its `ROLE` reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedReplica {
+    pub host: String,
+    pub port: u16,
+    /// Replication offset acknowledged by the replica.
+    pub offset: u64,
+}
+
+/// Reply of `ROLE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    Master {
+        offset: u64,
+        replicas: Vec<ConnectedReplica>,
+    },
+    Replica {
+        master_host: String,
+        master_port: u16,
+        /// Link state with the master, e.g. `connected` or `sync`.
+        state: String,
+        /// Data received from the master so far, -1 while not connected.
+        offset: i64,
+    },
+    Sentinel {
+        /// Names of the monitored masters.
+        masters: Vec<String>,
+    },
+}
+
+impl TryFrom<&RESP> for ConnectedReplica {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        match elements(value, "replica")? {
+            [host, port, offset] => Ok(Self {
+                host: text(host, "host")?.to_owned(),
+                port: port_number(port)?,
+                offset: unsigned(offset, "offset")?,
+            }),
+            _ => Err(ConversionError::unexpected_type("replica", value)),
+        }
+    }
+}
+
+impl TryFrom<&RESP> for Role {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = elements(value, "ROLE reply")?;
+        let role = fields.first().map(|x| text(x, "role")).transpose()?;
+        match (role, fields) {
+            (Some("master"), [_, offset, replicas]) => Ok(Self::Master {
+                offset: unsigned(offset, "offset")?,
+                replicas: elements(replicas, "replicas")?
+                    .iter()
+                    .map(ConnectedReplica::try_from)
+                    .collect::<Result<_, _>>()?,
+            }),
+            (Some("slave"), [_, host, port, state, offset]) => Ok(Self::Replica {
+                master_host: text(host, "host")?.to_owned(),
+                master_port: port_number(port)?,
+                state: text(state, "replication state")?.to_owned(),
+                offset: integer(offset, "offset")?,
+            }),
+            (Some("sentinel"), [_, masters]) => Ok(Self::Sentinel {
+                masters: strings(masters, "master names")?,
+            }),
+            _ => Err(ConversionError::unexpected_type("ROLE reply", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_master() {
+        let reply = RESP::parse(
+            "*3\r\n$6\r\nmaster\r\n:3129659\r\n*1\r\n*3\r\n$9\r\n127.0.0.1\r\n$4\r\n9001\r\n$7\r\n3129242\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Role::try_from(&reply),
+            Ok(Role::Master {
+                offset: 3129659,
+                replicas: vec![ConnectedReplica {
+                    host: "127.0.0.1".to_owned(),
+                    port: 9001,
+                    offset: 3129242,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn role_replica_and_sentinel() {
+        let reply = RESP::parse(
+            "*5\r\n$5\r\nslave\r\n$9\r\n127.0.0.1\r\n:9000\r\n$10\r\nconnecting\r\n:-1\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Role::try_from(&reply),
+            Ok(Role::Replica {
+                master_host: "127.0.0.1".to_owned(),
+                master_port: 9000,
+                state: "connecting".to_owned(),
+                offset: -1,
+            })
+        );
+
+        let reply =
+            RESP::parse("*2\r\n$8\r\nsentinel\r\n*2\r\n$8\r\nmaster_a\r\n$8\r\nmaster_b\r\n")
+                .unwrap();
+        assert_eq!(
+            Role::try_from(&reply),
+            Ok(Role::Sentinel {
+                masters: vec!["master_a".to_owned(), "master_b".to_owned()],
+            })
+        );
+    }
+
+    #[test]
+    fn role_invalid() {
+        let reply = RESP::parse("*2\r\n$6\r\nmaster\r\n:1\r\n").unwrap();
+        assert!(matches!(
+            Role::try_from(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+        let reply =
+            RESP::parse("*5\r\n$5\r\nslave\r\n$1\r\nh\r\n:70000\r\n$1\r\ns\r\n:0\r\n").unwrap();
+        assert!(matches!(
+            Role::try_from(&reply),
+            Err(ConversionError::OutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+use crate::{ConversionError, RESP};
+
+use super::{
+    elements, is_null, optional_text, optional_unsigned, port_number, text, unsigned, Fields,
+    Message,
+};
+
+/// Address of a master, from `SENTINEL GET-MASTER-ADDR-BY-NAME`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MasterAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl MasterAddress {
+    /// Decodes the reply of `SENTINEL GET-MASTER-ADDR-BY-NAME`, `None` when
+    /// the master isn't monitored.
+    pub fn from_reply(value: &RESP) -> Result<Option<Self>, ConversionError> {
+        if is_null(value) {
+            return Ok(None);
+        }
+        Self::try_from(value).map(Some)
+    }
+}
+
+impl TryFrom<&RESP> for MasterAddress {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        match elements(value, "master address")? {
+            [host, port] => Ok(Self {
+                host: text(host, "host")?.to_owned(),
+                port: port_number(port)?,
+            }),
+            _ => Err(ConversionError::unexpected_type("master address", value)),
+        }
+    }
+}
+
+/// Flags of an instance, e.g. `master` or `s_down`, sent comma separated.
+fn flags(fields: &Fields) -> Result<Vec<String>, ConversionError> {
+    Ok(text(fields.required("flags")?, "flags")?
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// A master monitored by a sentinel, from `SENTINEL MASTERS` or
+/// `SENTINEL MASTER`. Fields that older sentinels don't send are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelMaster {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub run_id: Option<String>,
+    pub flags: Vec<String>,
+    pub quorum: u64,
+    pub num_replicas: Option<u64>,
+    pub num_other_sentinels: Option<u64>,
+    pub config_epoch: Option<u64>,
+    pub down_after_ms: Option<u64>,
+    pub failover_timeout_ms: Option<u64>,
+    pub parallel_syncs: Option<u64>,
+}
+
+impl SentinelMaster {
+    /// Decodes the reply of `SENTINEL MASTERS`.
+    pub fn from_masters(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "SENTINEL MASTERS reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+
+    /// Whether the sentinel considers the master down, subjectively or
+    /// objectively.
+    pub fn is_down(&self) -> bool {
+        self.flags.iter().any(|x| x == "s_down" || x == "o_down")
+    }
+}
+
+impl TryFrom<&RESP> for SentinelMaster {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "sentinel master")?;
+        Ok(Self {
+            name: text(fields.required("name")?, "master name")?.to_owned(),
+            host: text(fields.required("ip")?, "host")?.to_owned(),
+            port: port_number(fields.required("port")?)?,
+            run_id: optional_text(&fields, "runid")?.filter(|x| !x.is_empty()),
+            flags: flags(&fields)?,
+            quorum: unsigned(fields.required("quorum")?, "quorum")?,
+            num_replicas: optional_unsigned(&fields, "num-slaves")?,
+            num_other_sentinels: optional_unsigned(&fields, "num-other-sentinels")?,
+            config_epoch: optional_unsigned(&fields, "config-epoch")?,
+            down_after_ms: optional_unsigned(&fields, "down-after-milliseconds")?,
+            failover_timeout_ms: optional_unsigned(&fields, "failover-timeout")?,
+            parallel_syncs: optional_unsigned(&fields, "parallel-syncs")?,
+        })
+    }
+}
+
+/// A replica of a monitored master, from `SENTINEL REPLICAS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelReplica {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub run_id: Option<String>,
+    pub flags: Vec<String>,
+    /// `ok` or `err`.
+    pub master_link_status: Option<String>,
+    pub master_host: Option<String>,
+    pub master_port: Option<u16>,
+    pub priority: Option<u64>,
+    pub repl_offset: Option<u64>,
+}
+
+impl SentinelReplica {
+    /// Decodes the reply of `SENTINEL REPLICAS` or `SENTINEL SLAVES`.
+    pub fn from_replicas(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "SENTINEL REPLICAS reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.flags.iter().any(|x| x == "s_down" || x == "o_down")
+    }
+}
+
+impl TryFrom<&RESP> for SentinelReplica {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "sentinel replica")?;
+        Ok(Self {
+            name: text(fields.required("name")?, "replica name")?.to_owned(),
+            host: text(fields.required("ip")?, "host")?.to_owned(),
+            port: port_number(fields.required("port")?)?,
+            run_id: optional_text(&fields, "runid")?.filter(|x| !x.is_empty()),
+            flags: flags(&fields)?,
+            master_link_status: optional_text(&fields, "master-link-status")?,
+            master_host: optional_text(&fields, "master-host")?,
+            master_port: fields.get("master-port").map(port_number).transpose()?,
+            priority: optional_unsigned(&fields, "slave-priority")?,
+            repl_offset: optional_unsigned(&fields, "slave-repl-offset")?,
+        })
+    }
+}
+
+/// An instance named in a Sentinel event, e.g. the replica that went down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelInstance {
+    /// `master`, `slave` or `sentinel`.
+    pub kind: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    /// Name and address of the master of replicas and sentinels.
+    pub master: Option<(String, MasterAddress)>,
+}
+
+impl SentinelInstance {
+    /// Parses `<kind> <name> <ip> <port> [@ <master> <ip> <port>]`.
+    fn parse(payload: &str) -> Option<Self> {
+        let words = payload.split(' ').collect::<Vec<_>>();
+        let (instance, master) = match words.as_slice() {
+            [kind, name, host, port] => ([kind, name, host, port], None),
+            [kind, name, host, port, "@", master, master_host, master_port] => (
+                [kind, name, host, port],
+                Some(((*master).to_owned(), address(master_host, master_port)?)),
+            ),
+            _ => return None,
+        };
+        let [kind, name, host, port] = instance;
+        if !matches!(*kind, "master" | "slave" | "sentinel") {
+            return None;
+        }
+        Some(Self {
+            kind: (*kind).to_owned(),
+            name: (*name).to_owned(),
+            host: (*host).to_owned(),
+            port: port.parse().ok()?,
+            master,
+        })
+    }
+}
+
+fn address(host: &str, port: &str) -> Option<MasterAddress> {
+    Some(MasterAddress {
+        host: host.to_owned(),
+        port: port.parse().ok()?,
+    })
+}
+
+/// A message published by a sentinel on one of its event channels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SentinelEvent {
+    /// `+switch-master`, sent once a failover completed.
+    SwitchMaster {
+        master: String,
+        old: MasterAddress,
+        new: MasterAddress,
+    },
+    /// Events about an instance, like `+sdown`, `-odown` or `+slave`.
+    Instance {
+        /// The channel, e.g. `+sdown`.
+        event: String,
+        instance: SentinelInstance,
+    },
+    /// Events with other payloads, like `+new-epoch` or `+tilt`.
+    Other { event: String, payload: String },
+}
+
+impl SentinelEvent {
+    /// Parses the message published on `channel`, `None` when it isn't a
+    /// Sentinel event channel or a `+switch-master` payload is invalid.
+    pub fn parse(channel: &str, payload: &str) -> Option<Self> {
+        if !channel.starts_with(['+', '-']) {
+            return None;
+        }
+        if channel == "+switch-master" {
+            let [master, old_host, old_port, new_host, new_port] =
+                payload.split(' ').collect::<Vec<_>>()[..]
+            else {
+                return None;
+            };
+            return Some(Self::SwitchMaster {
+                master: master.to_owned(),
+                old: address(old_host, old_port)?,
+                new: address(new_host, new_port)?,
+            });
+        }
+        Some(match SentinelInstance::parse(payload) {
+            Some(instance) => Self::Instance {
+                event: channel.to_owned(),
+                instance,
+            },
+            None => Self::Other {
+                event: channel.to_owned(),
+                payload: payload.to_owned(),
+            },
+        })
+    }
+
+    pub fn from_message(message: &Message) -> Option<Self> {
+        Self::parse(&message.channel, &message.payload)
+    }
+
+    /// The channel the event was published on.
+    pub fn event(&self) -> &str {
+        match self {
+            Self::SwitchMaster { .. } => "+switch-master",
+            Self::Instance { event, .. } | Self::Other { event, .. } => event,
+        }
+    }
+}
+
+impl TryFrom<&RESP> for SentinelEvent {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        Self::from_message(&Message::try_from(value)?)
+            .ok_or_else(|| ConversionError::unexpected_type("sentinel event", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat(fields: &[(&str, &str)]) -> RESP {
+        RESP::Array(
+            fields
+                .iter()
+                .flat_map(|(k, v)| [k, v])
+                .map(|x| RESP::BulkString((*x).to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn sentinel_masters() {
+        let reply = RESP::Array(vec![flat(&[
+            ("name", "mymaster"),
+            ("ip", "127.0.0.1"),
+            ("port", "6379"),
+            ("runid", "953ae6a589449c13ddefaee3538d356d287f509b"),
+            ("flags", "master,s_down"),
+            ("num-slaves", "1"),
+            ("quorum", "2"),
+        ])]);
+        let masters = SentinelMaster::from_masters(&reply).unwrap();
+        assert_eq!(masters[0].name, "mymaster");
+        assert_eq!(masters[0].port, 6379);
+        assert_eq!(masters[0].flags, vec!["master", "s_down"]);
+        assert_eq!(masters[0].quorum, 2);
+        assert_eq!(masters[0].num_replicas, Some(1));
+        assert_eq!(masters[0].config_epoch, None);
+        assert!(masters[0].is_down());
+
+        let reply = RESP::Array(vec![flat(&[("name", "mymaster")])]);
+        assert_eq!(
+            SentinelMaster::from_masters(&reply).map_err(|x| x.to_string()),
+            Err("expected field \"ip\", found array of 2 elements without it".to_owned())
+        );
+    }
+
+    #[test]
+    fn sentinel_replicas() {
+        let reply = RESP::Array(vec![flat(&[
+            ("name", "127.0.0.1:6380"),
+            ("ip", "127.0.0.1"),
+            ("port", "6380"),
+            ("runid", ""),
+            ("flags", "slave"),
+            ("master-link-status", "ok"),
+            ("master-host", "127.0.0.1"),
+            ("master-port", "6379"),
+            ("slave-repl-offset", "5843"),
+        ])]);
+        let replicas = SentinelReplica::from_replicas(&reply).unwrap();
+        assert_eq!(replicas[0].run_id, None);
+        assert_eq!(replicas[0].master_port, Some(6379));
+        assert_eq!(replicas[0].repl_offset, Some(5843));
+        assert!(!replicas[0].is_down());
+    }
+
+    #[test]
+    fn sentinel_master_address() {
+        let reply = RESP::parse("*2\r\n$9\r\n127.0.0.1\r\n$4\r\n6379\r\n").unwrap();
+        assert_eq!(
+            MasterAddress::from_reply(&reply),
+            Ok(Some(MasterAddress {
+                host: "127.0.0.1".to_owned(),
+                port: 6379,
+            }))
+        );
+        assert_eq!(MasterAddress::from_reply(&RESP::NullArray), Ok(None));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn sentinel_switch_master() {
+        let reply = RESP::parse(
+            ">3\r\n$7\r\nmessage\r\n$14\r\n+switch-master\r\n\
+             $38\r\nmymaster 127.0.0.1 6379 127.0.0.1 6380\r\n",
+        )
+        .unwrap();
+        let event = SentinelEvent::try_from(&reply).unwrap();
+        assert_eq!(event.event(), "+switch-master");
+        assert_eq!(
+            event,
+            SentinelEvent::SwitchMaster {
+                master: "mymaster".to_owned(),
+                old: MasterAddress {
+                    host: "127.0.0.1".to_owned(),
+                    port: 6379,
+                },
+                new: MasterAddress {
+                    host: "127.0.0.1".to_owned(),
+                    port: 6380,
+                },
+            }
+        );
+        assert_eq!(SentinelEvent::parse("+switch-master", "mymaster"), None);
+    }
+
+    #[test]
+    fn sentinel_instance_events() {
+        let event = SentinelEvent::parse(
+            "+sdown",
+            "slave 127.0.0.1:6380 127.0.0.1 6380 @ mymaster 127.0.0.1 6379",
+        );
+        let Some(SentinelEvent::Instance { event, instance }) = event else {
+            panic!("not an instance event: {event:?}");
+        };
+        assert_eq!(event, "+sdown");
+        assert_eq!(instance.kind, "slave");
+        assert_eq!(instance.port, 6380);
+        assert_eq!(instance.master.map(|x| x.0), Some("mymaster".to_owned()));
+
+        assert!(matches!(
+            SentinelEvent::parse("-odown", "master mymaster 127.0.0.1 6379"),
+            Some(SentinelEvent::Instance {
+                instance: SentinelInstance { master: None, .. },
+                ..
+            })
+        ));
+        assert_eq!(
+            SentinelEvent::parse("+new-epoch", "12"),
+            Some(SentinelEvent::Other {
+                event: "+new-epoch".to_owned(),
+                payload: "12".to_owned(),
+            })
+        );
+        assert_eq!(SentinelEvent::parse("news", "hi"), None);
+    }
+}
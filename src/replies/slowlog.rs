@@ -0,0 +1,96 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, integer, strings, text, unsigned};
+
+/// An entry of `SLOWLOG GET`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowlogEntry {
+    pub id: u64,
+    /// Unix time in seconds at which the command was run.
+    pub timestamp: i64,
+    pub duration_us: u64,
+    /// The command and its arguments, possibly truncated by the server.
+    pub args: Vec<String>,
+    /// Only sent by Redis 4 and later.
+    pub client_addr: Option<String>,
+    /// Only sent by Redis 4 and later, empty when the client has no name.
+    pub client_name: Option<String>,
+}
+
+impl SlowlogEntry {
+    /// Decodes the reply of `SLOWLOG GET`.
+    pub fn from_slowlog(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "SLOWLOG GET reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for SlowlogEntry {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = elements(value, "slowlog entry")?;
+        let (id, timestamp, duration, args, client) = match fields {
+            [id, timestamp, duration, args] => (id, timestamp, duration, args, None),
+            [id, timestamp, duration, args, addr, name] => {
+                (id, timestamp, duration, args, Some((addr, name)))
+            }
+            _ => return Err(ConversionError::unexpected_type("slowlog entry", value)),
+        };
+        let (client_addr, client_name) = match client {
+            Some((addr, name)) => (
+                Some(text(addr, "client address")?.to_owned()),
+                Some(text(name, "client name")?.to_owned()),
+            ),
+            None => (None, None),
+        };
+        Ok(Self {
+            id: unsigned(id, "slowlog id")?,
+            timestamp: integer(timestamp, "timestamp")?,
+            duration_us: unsigned(duration, "duration")?,
+            args: strings(args, "arguments")?,
+            client_addr,
+            client_name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slowlog_get() {
+        let reply = RESP::parse(
+            "*2\r\n\
+             *6\r\n:14\r\n:1309448221\r\n:15\r\n*2\r\n$4\r\nping\r\n$1\r\nx\r\n$15\r\n127.0.0.1:58217\r\n$0\r\n\r\n\
+             *4\r\n:13\r\n:1309448128\r\n:30\r\n*1\r\n$4\r\nKEYS\r\n",
+        )
+        .unwrap();
+        let entries = SlowlogEntry::from_slowlog(&reply).unwrap();
+        assert_eq!(
+            entries[0],
+            SlowlogEntry {
+                id: 14,
+                timestamp: 1309448221,
+                duration_us: 15,
+                args: vec!["ping".to_owned(), "x".to_owned()],
+                client_addr: Some("127.0.0.1:58217".to_owned()),
+                client_name: Some(String::new()),
+            }
+        );
+        assert_eq!(entries[1].args, vec!["KEYS"]);
+        assert_eq!(entries[1].client_addr, None);
+    }
+
+    #[test]
+    fn slowlog_invalid() {
+        let reply = RESP::parse("*1\r\n*3\r\n:1\r\n:2\r\n:3\r\n").unwrap();
+        assert!(matches!(
+            SlowlogEntry::from_slowlog(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+}
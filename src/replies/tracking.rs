@@ -0,0 +1,109 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, is_null, strip, text};
+
+/// Channel invalidations are published on when `CLIENT TRACKING` redirects
+/// them to another connection, as RESP2 has no push frames.
+const INVALIDATE_CHANNEL: &str = "__redis__:invalidate";
+
+/// Keys to evict from a client side cache, from an `invalidate` push sent
+/// under `CLIENT TRACKING`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalidation {
+    Keys(Vec<String>),
+    /// The whole cache is stale, e.g. after `FLUSHALL`.
+    All,
+}
+
+impl Invalidation {
+    fn from_keys(keys: &RESP) -> Result<Self, ConversionError> {
+        if is_null(keys) {
+            return Ok(Self::All);
+        }
+        match strip(keys) {
+            RESP::Array(x) => Self::from_elements(x),
+            #[cfg(feature = "resp3")]
+            RESP::Set(x) => Self::from_elements(x),
+            _ => Ok(Self::Keys(vec![text(keys, "invalidated keys")?.to_owned()])),
+        }
+    }
+
+    fn from_elements(keys: &[RESP]) -> Result<Self, ConversionError> {
+        keys.iter()
+            .map(|x| text(x, "invalidated key").map(str::to_owned))
+            .collect::<Result<_, _>>()
+            .map(Self::Keys)
+    }
+
+    /// Whether `key` has to be evicted.
+    pub fn covers(&self, key: &str) -> bool {
+        match self {
+            Self::Keys(keys) => keys.iter().any(|x| x == key),
+            Self::All => true,
+        }
+    }
+}
+
+impl TryFrom<&RESP> for Invalidation {
+    type Error = ConversionError;
+
+    /// Accepts `invalidate` pushes as well as messages of the
+    /// `__redis__:invalidate` channel, with one key, many or null.
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = elements(value, "invalidation")?;
+        let kind = fields.first().map(|x| text(x, "push kind")).transpose()?;
+        match (kind, fields) {
+            (Some("invalidate"), [_, keys]) => Self::from_keys(keys),
+            (Some("message"), [_, channel, keys])
+                if text(channel, "channel")? == INVALIDATE_CHANNEL =>
+            {
+                Self::from_keys(keys)
+            }
+            _ => Err(ConversionError::unexpected_type("invalidation", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn invalidate_push() {
+        let reply = RESP::parse(">2\r\n$10\r\ninvalidate\r\n*2\r\n$1\r\na\r\n$1\r\nb\r\n").unwrap();
+        let invalidation = Invalidation::try_from(&reply).unwrap();
+        assert_eq!(
+            invalidation,
+            Invalidation::Keys(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert!(invalidation.covers("b"));
+        assert!(!invalidation.covers("c"));
+
+        let reply = RESP::parse(">2\r\n$10\r\ninvalidate\r\n$1\r\na\r\n").unwrap();
+        assert_eq!(
+            Invalidation::try_from(&reply),
+            Ok(Invalidation::Keys(vec!["a".to_owned()]))
+        );
+
+        let reply = RESP::parse(">2\r\n$10\r\ninvalidate\r\n_\r\n").unwrap();
+        assert_eq!(Invalidation::try_from(&reply), Ok(Invalidation::All));
+    }
+
+    #[test]
+    fn invalidate_redirected() {
+        let reply =
+            RESP::parse("*3\r\n$7\r\nmessage\r\n$20\r\n__redis__:invalidate\r\n*1\r\n$1\r\na\r\n")
+                .unwrap();
+        assert_eq!(
+            Invalidation::try_from(&reply),
+            Ok(Invalidation::Keys(vec!["a".to_owned()]))
+        );
+
+        let reply = RESP::parse("*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n").unwrap();
+        assert!(matches!(
+            Invalidation::try_from(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, is_null, strip};
+
+/// Commands queued between `MULTI` and `EXEC`, to match with the results of
+/// `EXEC`.
+///
+/// ```
+/// use resp_parser_rs::replies::{ExecOutcome, Transaction};
+/// use resp_parser_rs::RESP;
+///
+/// let mut transaction = Transaction::new();
+/// transaction.queue("INCR").queue("GET");
+/// let reply = RESP::parse("*2\r\n:1\r\n-WRONGTYPE not a string\r\n").unwrap();
+/// let ExecOutcome::Committed(results) = transaction.exec(&reply).unwrap() else {
+///     panic!("transaction aborted");
+/// };
+/// assert_eq!(results[1].command, "GET");
+/// assert_eq!(results[1].error(), Some("WRONGTYPE not a string"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Transaction {
+    commands: Vec<String>,
+}
+
+/// Outcome of `EXEC`.
+#[derive(Debug, PartialEq)]
+pub enum ExecOutcome<'a> {
+    /// Every command ran, even if some of them failed.
+    Committed(Vec<CommandReply<'a>>),
+    /// A watched key was modified, so nothing ran.
+    Aborted,
+    /// A command was rejected while queuing, so nothing ran.
+    Discarded(&'a str),
+}
+
+/// Result of one of the commands of a transaction.
+#[derive(Debug, PartialEq)]
+pub struct CommandReply<'a> {
+    /// The command as given to [`Transaction::queue`].
+    pub command: &'a str,
+    pub reply: &'a RESP,
+}
+
+impl<'a> CommandReply<'a> {
+    /// Message of the error the command failed with.
+    pub fn error(&self) -> Option<&'a str> {
+        match strip(self.reply) {
+            RESP::SimpleError(x) => Some(x),
+            #[cfg(feature = "resp3")]
+            RESP::BulkError(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Decodes the reply as one of the types of this module.
+    pub fn decode<T>(&self) -> Result<T, ConversionError>
+    where
+        T: TryFrom<&'a RESP, Error = ConversionError>,
+    {
+        T::try_from(self.reply)
+    }
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `command` was queued, to label its result.
+    pub fn queue(&mut self, command: impl Into<String>) -> &mut Self {
+        self.commands.push(command.into());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Pairs the reply of `EXEC` with the queued commands, failing when it
+    /// doesn't hold a result for each of them.
+    pub fn exec<'a>(&'a self, reply: &'a RESP) -> Result<ExecOutcome<'a>, ConversionError> {
+        if is_null(reply) {
+            return Ok(ExecOutcome::Aborted);
+        }
+        match strip(reply) {
+            RESP::SimpleError(x) => return Ok(ExecOutcome::Discarded(x)),
+            #[cfg(feature = "resp3")]
+            RESP::BulkError(x) => return Ok(ExecOutcome::Discarded(x)),
+            _ => {}
+        }
+        let results = elements(reply, "EXEC reply")?;
+        if results.len() != self.commands.len() {
+            return Err(ConversionError::unexpected_type(
+                "EXEC reply with a result per queued command",
+                reply,
+            ));
+        }
+        Ok(ExecOutcome::Committed(
+            self.commands
+                .iter()
+                .zip(results)
+                .map(|(command, reply)| CommandReply { command, reply })
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replies::Role;
+
+    fn transaction() -> Transaction {
+        let mut transaction = Transaction::new();
+        transaction.queue("SET").queue("ROLE");
+        transaction
+    }
+
+    #[test]
+    fn exec_committed() {
+        let transaction = transaction();
+        let reply = RESP::parse("*2\r\n+OK\r\n*2\r\n$8\r\nsentinel\r\n*1\r\n$1\r\nm\r\n").unwrap();
+        let Ok(ExecOutcome::Committed(results)) = transaction.exec(&reply) else {
+            panic!("transaction not committed");
+        };
+        assert_eq!(results[0].command, "SET");
+        assert_eq!(results[0].error(), None);
+        assert_eq!(
+            results[1].decode::<Role>(),
+            Ok(Role::Sentinel {
+                masters: vec!["m".to_owned()],
+            })
+        );
+    }
+
+    #[test]
+    fn exec_aborted() {
+        let transaction = transaction();
+        assert_eq!(transaction.exec(&RESP::NullArray), Ok(ExecOutcome::Aborted));
+        let reply = RESP::parse("-EXECABORT Transaction discarded because of previous errors.\r\n")
+            .unwrap();
+        assert!(matches!(
+            transaction.exec(&reply),
+            Ok(ExecOutcome::Discarded(x)) if x.starts_with("EXECABORT")
+        ));
+        let reply = RESP::parse("*1\r\n+OK\r\n").unwrap();
+        assert!(matches!(
+            transaction.exec(&reply),
+            Err(ConversionError::UnexpectedType { .. })
+        ));
+    }
+}
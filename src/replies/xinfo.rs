@@ -0,0 +1,229 @@
+use crate::{ConversionError, RESP};
+
+use super::{elements, integer, is_null, optional_unsigned, text, unsigned, Fields};
+
+/// An entry of a stream, as found in `XINFO STREAM` or `XRANGE` replies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl TryFrom<&RESP> for StreamEntry {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let [id, fields] = elements(value, "stream entry")? else {
+            return Err(ConversionError::unexpected_type("stream entry", value));
+        };
+        let fields = elements(fields, "stream entry fields")?;
+        if !fields.len().is_multiple_of(2) {
+            return Err(ConversionError::unexpected_type(
+                "stream entry fields",
+                value,
+            ));
+        }
+        Ok(Self {
+            id: text(id, "entry id")?.to_owned(),
+            fields: fields
+                .chunks_exact(2)
+                .map(|x| {
+                    Ok((
+                        text(&x[0], "field")?.to_owned(),
+                        text(&x[1], "value")?.to_owned(),
+                    ))
+                })
+                .collect::<Result<_, ConversionError>>()?,
+        })
+    }
+}
+
+fn optional_entry(fields: &Fields, name: &str) -> Result<Option<StreamEntry>, ConversionError> {
+    match fields.get(name) {
+        Some(x) if !is_null(x) => StreamEntry::try_from(x).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Reply of `XINFO STREAM`, without the `FULL` modifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub length: u64,
+    pub last_generated_id: String,
+    /// Amount of consumer groups.
+    pub groups: u64,
+    /// Only sent by Redis 7 and later.
+    pub entries_added: Option<u64>,
+    pub first_entry: Option<StreamEntry>,
+    pub last_entry: Option<StreamEntry>,
+}
+
+impl TryFrom<&RESP> for StreamInfo {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "XINFO STREAM reply")?;
+        Ok(Self {
+            length: unsigned(fields.required("length")?, "length")?,
+            last_generated_id: text(fields.required("last-generated-id")?, "entry id")?.to_owned(),
+            groups: unsigned(fields.required("groups")?, "groups")?,
+            entries_added: fields
+                .get("entries-added")
+                .map(|x| unsigned(x, "entries-added"))
+                .transpose()?,
+            first_entry: optional_entry(&fields, "first-entry")?,
+            last_entry: optional_entry(&fields, "last-entry")?,
+        })
+    }
+}
+
+/// A consumer group, as listed by `XINFO GROUPS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInfo {
+    pub name: String,
+    pub consumers: u64,
+    /// Entries delivered but not acknowledged yet.
+    pub pending: u64,
+    pub last_delivered_id: String,
+    /// Only sent by Redis 7 and later, `None` as well when it's unknown.
+    pub entries_read: Option<u64>,
+    /// Only sent by Redis 7 and later, `None` as well when it's unknown.
+    pub lag: Option<u64>,
+}
+
+impl GroupInfo {
+    /// Decodes the reply of `XINFO GROUPS`.
+    pub fn from_groups(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "XINFO GROUPS reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for GroupInfo {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "consumer group")?;
+        Ok(Self {
+            name: text(fields.required("name")?, "group name")?.to_owned(),
+            consumers: unsigned(fields.required("consumers")?, "consumers")?,
+            pending: unsigned(fields.required("pending")?, "pending")?,
+            last_delivered_id: text(fields.required("last-delivered-id")?, "entry id")?.to_owned(),
+            entries_read: optional_unsigned(&fields, "entries-read")?,
+            lag: optional_unsigned(&fields, "lag")?,
+        })
+    }
+}
+
+/// A consumer of a group, as listed by `XINFO CONSUMERS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerInfo {
+    pub name: String,
+    /// Entries delivered to this consumer but not acknowledged yet.
+    pub pending: u64,
+    /// Milliseconds since the consumer last interacted with the server.
+    pub idle: i64,
+    /// Milliseconds since the last successful interaction, only sent by
+    /// Redis 7.2 and later.
+    pub inactive: Option<i64>,
+}
+
+impl ConsumerInfo {
+    /// Decodes the reply of `XINFO CONSUMERS`.
+    pub fn from_consumers(value: &RESP) -> Result<Vec<Self>, ConversionError> {
+        elements(value, "XINFO CONSUMERS reply")?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<&RESP> for ConsumerInfo {
+    type Error = ConversionError;
+
+    fn try_from(value: &RESP) -> Result<Self, Self::Error> {
+        let fields = Fields::new(value, "consumer")?;
+        Ok(Self {
+            name: text(fields.required("name")?, "consumer name")?.to_owned(),
+            pending: unsigned(fields.required("pending")?, "pending")?,
+            idle: integer(fields.required("idle")?, "idle")?,
+            inactive: fields
+                .get("inactive")
+                .map(|x| integer(x, "inactive"))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn xinfo_stream() {
+        let reply = RESP::parse(
+            "%5\r\n+length\r\n:2\r\n+last-generated-id\r\n$3\r\n2-0\r\n+groups\r\n:1\r\n\
+             +first-entry\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n+last-entry\r\n_\r\n",
+        )
+        .unwrap();
+        let info = StreamInfo::try_from(&reply).unwrap();
+        assert_eq!(info.length, 2);
+        assert_eq!(info.last_generated_id, "2-0");
+        assert_eq!(info.groups, 1);
+        assert_eq!(info.entries_added, None);
+        assert_eq!(
+            info.first_entry,
+            Some(StreamEntry {
+                id: "1-0".to_owned(),
+                fields: vec![("a".to_owned(), "1".to_owned())],
+            })
+        );
+        assert_eq!(info.last_entry, None);
+
+        let reply = RESP::parse("*2\r\n$6\r\nlength\r\n:2\r\n").unwrap();
+        let err = StreamInfo::try_from(&reply).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected field \"last-generated-id\", found array of 2 elements without it"
+        );
+    }
+
+    #[test]
+    fn xinfo_groups() {
+        // RESP2 flat arrays
+        let reply = RESP::parse(
+            "*1\r\n*12\r\n$4\r\nname\r\n$2\r\ng1\r\n$9\r\nconsumers\r\n:2\r\n$7\r\npending\r\n:3\r\n\
+             $17\r\nlast-delivered-id\r\n$3\r\n1-0\r\n$12\r\nentries-read\r\n:1\r\n$3\r\nlag\r\n$-1\r\n",
+        )
+        .unwrap();
+        let groups = GroupInfo::from_groups(&reply).unwrap();
+        assert_eq!(
+            groups,
+            vec![GroupInfo {
+                name: "g1".to_owned(),
+                consumers: 2,
+                pending: 3,
+                last_delivered_id: "1-0".to_owned(),
+                entries_read: Some(1),
+                lag: None,
+            }]
+        );
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn xinfo_consumers() {
+        let reply = RESP::parse(
+            "*1\r\n%3\r\n+name\r\n$5\r\nalice\r\n+pending\r\n:1\r\n+idle\r\n:9104628\r\n",
+        )
+        .unwrap();
+        let consumers = ConsumerInfo::from_consumers(&reply).unwrap();
+        assert_eq!(consumers[0].name, "alice");
+        assert_eq!(consumers[0].pending, 1);
+        assert_eq!(consumers[0].idle, 9104628);
+        assert_eq!(consumers[0].inactive, None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncWrite;
+use futures::Sink;
+
+use crate::RESP;
+
+/// Default amount of encoded bytes buffered before a write is forced.
+pub const DEFAULT_SINK_CAPACITY: usize = 8 * 1024;
+
+/// [`Sink`] encoding frames into an [`AsyncWrite`].
+///
+/// Frames are buffered until `capacity` bytes are pending or the sink is
+/// flushed, so pipelined sends are written together.
+#[derive(Debug)]
+pub struct FrameSink<W> {
+    writer: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: AsyncWrite + Unpin> FrameSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(DEFAULT_SINK_CAPACITY, writer)
+    }
+
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the inner writer, any buffered frames that were not flushed are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.buffer.is_empty() {
+            let written = match Pin::new(&mut self.writer).poll_write(cx, &self.buffer) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                Poll::Ready(Ok(x)) => x,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            };
+            self.buffer.drain(..written);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<RESP> for FrameSink<W> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.buffer.len() >= this.capacity {
+            this.poll_write_buffer(cx)
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: RESP) -> io::Result<()> {
+        Ok(item.write_to(&mut self.get_mut().buffer)?)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_buffer(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.writer).poll_flush(cx),
+            x => x,
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_buffer(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.writer).poll_close(cx),
+            x => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::SinkExt;
+
+    use super::*;
+
+    #[test]
+    fn send() {
+        let mut sink = FrameSink::new(Vec::new());
+        block_on(sink.send(RESP::SimpleString("OK".into()))).unwrap();
+        block_on(sink.send(RESP::Integer(1))).unwrap();
+        assert_eq!(sink.get_ref(), b"+OK\r\n:1\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn feed_buffers_until_flush() {
+        let mut sink = FrameSink::new(Vec::new());
+        block_on(sink.feed(RESP::SimpleString("OK".into()))).unwrap();
+        block_on(sink.feed(RESP::Null)).unwrap();
+        assert!(sink.get_ref().is_empty());
+        block_on(sink.flush()).unwrap();
+        assert_eq!(sink.get_ref(), b"+OK\r\n_\r\n");
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn feed_writes_over_capacity() {
+        let mut sink = FrameSink::with_capacity(4, Vec::new());
+        block_on(sink.feed(RESP::SimpleString("OK".into()))).unwrap();
+        assert!(sink.get_ref().is_empty());
+        block_on(sink.feed(RESP::Null)).unwrap();
+        assert_eq!(sink.get_ref(), b"+OK\r\n");
+        block_on(sink.close()).unwrap();
+        assert_eq!(sink.get_ref(), b"+OK\r\n_\r\n");
+    }
+}
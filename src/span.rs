@@ -0,0 +1,298 @@
+use std::ops::Range;
+
+use crate::framing::{frame_len, read_length};
+use crate::{ParseConfig, ParseError, RESP};
+
+/// A node of a frame along with the bytes it was parsed from, header and
+/// trailing CRLF included.
+#[derive(Debug, PartialEq)]
+pub struct Spanned {
+    pub span: Range<usize>,
+    pub node: SpannedNode,
+}
+
+/// Contents of a [`Spanned`] node. Scalars, null aggregates included, are
+/// decoded values while aggregates hold the spans of their children.
+#[derive(Debug, PartialEq)]
+pub enum SpannedNode {
+    Scalar(RESP),
+    Array(Vec<Spanned>),
+    #[cfg(feature = "resp3")]
+    Set(Vec<Spanned>),
+    #[cfg(feature = "resp3")]
+    Push(Vec<Spanned>),
+    #[cfg(feature = "resp3")]
+    Map(Vec<(Spanned, Spanned)>),
+    #[cfg(feature = "resp3")]
+    WithAttributes {
+        attributes: Vec<(Spanned, Spanned)>,
+        value: Box<Spanned>,
+    },
+}
+
+/// An aggregate whose children are still being parsed.
+struct Open {
+    kind: u8,
+    start: usize,
+    remaining: usize,
+    children: Vec<Spanned>,
+}
+
+#[cfg(feature = "resp3")]
+fn pairs(children: Vec<Spanned>) -> Vec<(Spanned, Spanned)> {
+    let mut children = children.into_iter();
+    let mut pairs = Vec::with_capacity(children.len() / 2);
+    while let (Some(key), Some(value)) = (children.next(), children.next()) {
+        pairs.push((key, value));
+    }
+    pairs
+}
+
+impl Open {
+    fn close(self, end: usize) -> Spanned {
+        let node = match self.kind {
+            #[cfg(feature = "resp3")]
+            b'~' => SpannedNode::Set(self.children),
+            #[cfg(feature = "resp3")]
+            b'>' => SpannedNode::Push(self.children),
+            #[cfg(feature = "resp3")]
+            b'%' => SpannedNode::Map(pairs(self.children)),
+            #[cfg(feature = "resp3")]
+            b'|' => {
+                let mut children = self.children;
+                let value = children.pop().expect("attributes are followed by a value");
+                SpannedNode::WithAttributes {
+                    attributes: pairs(children),
+                    value: Box::new(value),
+                }
+            }
+            _ => SpannedNode::Array(self.children),
+        };
+        Spanned {
+            span: self.start..end,
+            node,
+        }
+    }
+}
+
+fn end_of_input() -> ParseError {
+    ParseError::end_of_input("rest of the frame")
+}
+
+/// Decodes the scalar spanning `span`.
+fn scalar(data: &[u8], span: Range<usize>, config: &ParseConfig) -> Result<Spanned, ParseError> {
+    let text = std::str::from_utf8(&data[span.clone()])
+        .map_err(|_| ParseError::new("UTF-8 text", "invalid UTF-8 sequence"))?;
+    Ok(Spanned {
+        span,
+        node: SpannedNode::Scalar(RESP::try_parse_with(text, config)?),
+    })
+}
+
+impl Spanned {
+    /// Innermost node whose span holds `offset`.
+    pub fn node_at(&self, offset: usize) -> Option<&Spanned> {
+        if !self.span.contains(&offset) {
+            return None;
+        }
+        let mut node = self;
+        'descend: loop {
+            let children: Box<dyn Iterator<Item = &Spanned>> = match &node.node {
+                SpannedNode::Scalar(_) => return Some(node),
+                SpannedNode::Array(x) => Box::new(x.iter()),
+                #[cfg(feature = "resp3")]
+                SpannedNode::Set(x) | SpannedNode::Push(x) => Box::new(x.iter()),
+                #[cfg(feature = "resp3")]
+                SpannedNode::Map(x) => Box::new(x.iter().flat_map(|(k, v)| [k, v])),
+                #[cfg(feature = "resp3")]
+                SpannedNode::WithAttributes { attributes, value } => Box::new(
+                    attributes
+                        .iter()
+                        .flat_map(|(k, v)| [k, v])
+                        .chain([value.as_ref()]),
+                ),
+            };
+            for child in children {
+                if child.span.contains(&offset) {
+                    node = child;
+                    continue 'descend;
+                }
+            }
+            return Some(node);
+        }
+    }
+
+    /// Drops the spans, keeping the values.
+    pub fn into_resp(self) -> RESP {
+        let elements = |x: Vec<Spanned>| x.into_iter().map(Spanned::into_resp).collect();
+        #[cfg(feature = "resp3")]
+        let pairs = |x: Vec<(Spanned, Spanned)>| {
+            x.into_iter()
+                .map(|(key, value)| (key.into_resp(), value.into_resp()))
+                .collect()
+        };
+        match self.node {
+            SpannedNode::Scalar(x) => x,
+            SpannedNode::Array(x) => RESP::Array(elements(x)),
+            #[cfg(feature = "resp3")]
+            SpannedNode::Set(x) => RESP::Set(elements(x)),
+            #[cfg(feature = "resp3")]
+            SpannedNode::Push(x) => RESP::Push(elements(x)),
+            #[cfg(feature = "resp3")]
+            SpannedNode::Map(x) => RESP::Map(pairs(x)),
+            #[cfg(feature = "resp3")]
+            SpannedNode::WithAttributes { attributes, value } => RESP::WithAttributes {
+                attributes: pairs(attributes),
+                value: Box::new(value.into_resp()),
+            },
+        }
+    }
+}
+
+impl RESP {
+    /// Parses the first frame of `data` into a tree recording the byte range
+    /// of every node, for error reporting, highlighting or rewriting
+    /// captured traffic in place.
+    ///
+    /// Map entries are all kept as they appear in the input, whatever
+    /// [`ParseConfig::duplicate_keys`] says.
+    pub fn parse_spanned(data: &[u8]) -> Result<Spanned, ParseError> {
+        Self::parse_spanned_with(data, &ParseConfig::default())
+    }
+
+    pub fn parse_spanned_with(data: &[u8], config: &ParseConfig) -> Result<Spanned, ParseError> {
+        let mut stack: Vec<Open> = Vec::new();
+        let mut position = 0;
+        loop {
+            if stack.len() > config.max_depth {
+                return Err(ParseError::new(
+                    format!("at most {} levels of nesting", config.max_depth),
+                    "deeper value",
+                ));
+            }
+            let kind = *data.get(position).ok_or_else(end_of_input)?;
+            if kind == b'>' && !stack.is_empty() {
+                return Err(ParseError::new("value", "push inside an aggregate"));
+            }
+
+            #[cfg(feature = "resp3")]
+            let aggregate = matches!(kind, b'*' | b'~' | b'>' | b'%' | b'|');
+            // Other aggregates are left to the parser, which rejects them
+            #[cfg(not(feature = "resp3"))]
+            let aggregate = kind == b'*';
+            let mut node = match kind {
+                _ if aggregate => {
+                    let (length, start) =
+                        read_length(data, position + 1)?.ok_or_else(end_of_input)?;
+                    let remaining = match (kind, usize::try_from(length)) {
+                        (_, Err(_)) => None,
+                        (b'%', Ok(x)) => x.checked_mul(2),
+                        (b'|', Ok(x)) => x.checked_mul(2).and_then(|x| x.checked_add(1)),
+                        (_, Ok(x)) => Some(x),
+                    };
+                    match remaining {
+                        Some(remaining) if remaining > 0 => {
+                            stack.push(Open {
+                                kind,
+                                start: position,
+                                remaining,
+                                // Lengths aren't trusted for allocations
+                                children: Vec::new(),
+                            });
+                            position = start;
+                            continue;
+                        }
+                        Some(_) => Open {
+                            kind,
+                            start: position,
+                            remaining: 0,
+                            children: Vec::new(),
+                        }
+                        .close(start),
+                        // Null aggregates and invalid lengths
+                        None => scalar(data, position..start, config)?,
+                    }
+                }
+                _ => {
+                    let length = frame_len(&data[position..])?.ok_or_else(end_of_input)?;
+                    scalar(data, position..position + length, config)?
+                }
+            };
+            position = node.span.end;
+
+            // Hand finished nodes to their parents until one is still open
+            loop {
+                let Some(parent) = stack.last_mut() else {
+                    return Ok(node);
+                };
+                parent.children.push(node);
+                parent.remaining -= 1;
+                if parent.remaining > 0 {
+                    break;
+                }
+                let parent = stack.pop().expect("parent was just found");
+                node = parent.close(position);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn spans() {
+        let data = b"*3\r\n$5\r\nHello\r\n%1\r\n+k\r\n:1\r\n*0\r\n";
+        let spanned = RESP::parse_spanned(data).unwrap();
+        assert_eq!(spanned.span, 0..data.len());
+        let SpannedNode::Array(elements) = &spanned.node else {
+            panic!("{spanned:?}");
+        };
+        assert_eq!(elements[0].span, 4..15);
+        assert_eq!(elements[1].span, 15..27);
+        assert_eq!(elements[2].span, 27..31);
+        assert_eq!(&data[elements[1].span.clone()], b"%1\r\n+k\r\n:1\r\n");
+
+        let node = spanned.node_at(24).unwrap();
+        assert_eq!(node.span, 23..27);
+        assert_eq!(node.node, SpannedNode::Scalar(RESP::Integer(1)));
+        assert_eq!(spanned.node_at(2).unwrap().span, 0..31);
+        assert!(spanned.node_at(31).is_none());
+
+        let value = RESP::parse_spanned(data).unwrap().into_resp();
+        assert_eq!(Some(value), RESP::parse(std::str::from_utf8(data).unwrap()));
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn spans_attributes() {
+        let data = b"|1\r\n+ttl\r\n:3\r\n*-1\r\nPING\r\n";
+        let spanned = RESP::parse_spanned(data).unwrap();
+        assert_eq!(spanned.span, 0..19);
+        let SpannedNode::WithAttributes { attributes, value } = &spanned.node else {
+            panic!("{spanned:?}");
+        };
+        assert_eq!(attributes[0].0.span, 4..10);
+        assert_eq!(value.span, 14..19);
+        assert_eq!(value.node, SpannedNode::Scalar(RESP::NullArray));
+    }
+
+    #[test]
+    fn spans_invalid() {
+        assert!(matches!(RESP::parse_spanned(b"*2\r\n:1\r\n"), Err(_)));
+        assert!(matches!(RESP::parse_spanned(b"*1\r\n>0\r\n"), Err(_)));
+        assert!(matches!(RESP::parse_spanned(b"*1\r\n#x\r\n"), Err(_)));
+        assert!(matches!(RESP::parse_spanned(b"*-3\r\n"), Err(_)));
+        let config = ParseConfig {
+            max_depth: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            RESP::parse_spanned_with(b"*1\r\n*1\r\n*0\r\n", &config),
+            Err(_)
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+//! Protocol metrics reported through the [`metrics`] facade, to whichever
+//! recorder the application installed:
+//!
+//! - `resp_frames_decoded_total` and `resp_bytes_decoded_total`, counters of
+//!   the frames returned by a [`Decoder`](crate::Decoder) and their size.
+//! - `resp_decoded_frame_bytes`, a histogram of those sizes.
+//! - `resp_decode_errors_total`, a counter of decoding errors labelled by
+//!   `kind`: `parse`, `io`, `stalled` or `stream_in_progress`.
+//! - `resp_frames_encoded_total`, `resp_bytes_encoded_total` and
+//!   `resp_encoded_frame_bytes`, the same for values encoded with
+//!   [`RESP::write_to`](crate::RESP::write_to) and the writers built on it.
+
+use std::io::{self, Write};
+
+use metrics::{counter, histogram};
+
+use crate::DecodeError;
+
+pub(crate) fn frame_decoded(size: usize) {
+    counter!("resp_frames_decoded_total").increment(1);
+    counter!("resp_bytes_decoded_total").increment(size as u64);
+    histogram!("resp_decoded_frame_bytes").record(size as f64);
+}
+
+pub(crate) fn decode_failed(error: &DecodeError) {
+    let kind = match error {
+        DecodeError::Parse(_) => "parse",
+        DecodeError::Io(_) => "io",
+        DecodeError::Stalled { .. } => "stalled",
+        DecodeError::StreamInProgress => "stream_in_progress",
+    };
+    counter!("resp_decode_errors_total", "kind" => kind).increment(1);
+}
+
+pub(crate) fn frame_encoded(size: usize) {
+    counter!("resp_frames_encoded_total").increment(1);
+    counter!("resp_bytes_encoded_total").increment(size as u64);
+    histogram!("resp_encoded_frame_bytes").record(size as f64);
+}
+
+/// Writer counting the bytes going through it, to report encoded sizes.
+pub(crate) struct Counted<W> {
+    pub(crate) writer: W,
+    pub(crate) written: usize,
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(data)?;
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use metrics::{
+        Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString,
+        Unit,
+    };
+
+    use crate::{Decoder, RESP};
+
+    /// Histogram keeping every recorded value.
+    #[derive(Default)]
+    struct Samples(Mutex<Vec<f64>>);
+
+    impl HistogramFn for Samples {
+        fn record(&self, value: f64) {
+            self.0.lock().unwrap().push(value);
+        }
+    }
+
+    /// Recorder keeping metrics in memory, keyed by their name and labels.
+    #[derive(Default)]
+    struct Memory {
+        counters: Mutex<HashMap<String, Arc<AtomicU64>>>,
+        histograms: Mutex<HashMap<String, Arc<Samples>>>,
+    }
+
+    impl Memory {
+        fn counter(&self, key: &str) -> u64 {
+            let counters = self.counters.lock().unwrap();
+            counters.get(key).map_or(0, |x| x.load(Ordering::Relaxed))
+        }
+
+        fn samples(&self, key: &str) -> Vec<f64> {
+            let histograms = self.histograms.lock().unwrap();
+            histograms
+                .get(key)
+                .map_or(Vec::new(), |x| x.0.lock().unwrap().clone())
+        }
+    }
+
+    fn name(key: &Key) -> String {
+        let mut name = key.name().to_owned();
+        for label in key.labels() {
+            name.push_str(&format!(",{}={}", label.key(), label.value()));
+        }
+        name
+    }
+
+    impl Recorder for Memory {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let mut counters = self.counters.lock().unwrap();
+            Counter::from_arc(Arc::clone(counters.entry(name(key)).or_default()))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            let mut histograms = self.histograms.lock().unwrap();
+            Histogram::from_arc(Arc::clone(histograms.entry(name(key)).or_default()))
+        }
+    }
+
+    #[test]
+    fn decoder_metrics() {
+        let recorder = Memory::default();
+        metrics::with_local_recorder(&recorder, || {
+            let mut decoder = Decoder::new();
+            decoder.feed(b"+OK\r\n:12\r\n$x\r\n");
+            assert!(decoder.decode().is_ok());
+            assert!(decoder.decode().is_ok());
+            assert!(decoder.decode().is_err());
+        });
+        assert_eq!(recorder.counter("resp_frames_decoded_total"), 2);
+        assert_eq!(recorder.counter("resp_bytes_decoded_total"), 10);
+        assert_eq!(recorder.samples("resp_decoded_frame_bytes"), [5.0, 5.0]);
+        assert_eq!(recorder.counter("resp_decode_errors_total,kind=parse"), 1);
+    }
+
+    #[test]
+    fn encoder_metrics() {
+        let recorder = Memory::default();
+        metrics::with_local_recorder(&recorder, || {
+            assert!(RESP::Integer(1).encode().is_ok());
+            assert!(RESP::ok().encode().is_ok());
+            assert!(RESP::SimpleString("a\r\n".into()).encode().is_err());
+        });
+        assert_eq!(recorder.counter("resp_frames_encoded_total"), 2);
+        assert_eq!(recorder.counter("resp_bytes_encoded_total"), 9);
+        assert_eq!(recorder.samples("resp_encoded_frame_bytes"), [4.0, 5.0]);
+    }
+}
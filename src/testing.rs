@@ -0,0 +1,194 @@
+//! Helpers for tests comparing decoded frames, such as server conformance
+//! suites.
+
+use crate::RESP;
+
+/// Asserts that two [`RESP`] values are equal, listing where they differ by
+/// path instead of dumping both values on failure.
+///
+/// NaN doubles are considered equal to each other.
+#[macro_export]
+macro_rules! assert_resp_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let differences = $crate::testing::diff(left, right);
+                if !differences.is_empty() {
+                    panic!(
+                        "assertion `left == right` failed\n{}",
+                        differences.join("\n")
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Every difference between `left` and `right`, one line each, prefixed
+/// with the path to it, `$` being the root.
+pub fn diff(left: &RESP, right: &RESP) -> Vec<String> {
+    let mut differences = Vec::new();
+    walk("$", left, right, &mut differences);
+    differences
+}
+
+/// Label of a map entry, its key when it's a string.
+#[cfg(feature = "resp3")]
+fn key_label(key: &RESP, index: usize) -> String {
+    match key {
+        RESP::SimpleString(x) => format!("{{{:?}}}", x.as_str()),
+        RESP::BulkString(x) => format!("{{{x:?}}}"),
+        _ => format!("{{#{index}}}"),
+    }
+}
+
+fn walk_elements(path: &str, left: &[RESP], right: &[RESP], out: &mut Vec<String>) {
+    if left.len() != right.len() {
+        out.push(format!(
+            "{path}: left has {} elements, right has {}",
+            left.len(),
+            right.len()
+        ));
+    }
+    for (index, (left, right)) in left.iter().zip(right).enumerate() {
+        walk(&format!("{path}[{index}]"), left, right, out);
+    }
+}
+
+#[cfg(feature = "resp3")]
+fn walk_pairs(path: &str, left: &[(RESP, RESP)], right: &[(RESP, RESP)], out: &mut Vec<String>) {
+    if left.len() != right.len() {
+        out.push(format!(
+            "{path}: left has {} entries, right has {}",
+            left.len(),
+            right.len()
+        ));
+    }
+    for (index, ((left_key, left), (right_key, right))) in left.iter().zip(right).enumerate() {
+        let label = key_label(left_key, index);
+        walk(&format!("{path}{{#{index} key}}"), left_key, right_key, out);
+        walk(&format!("{path}{label}"), left, right, out);
+    }
+}
+
+fn walk(path: &str, left: &RESP, right: &RESP, out: &mut Vec<String>) {
+    use RESP::*;
+
+    match (left, right) {
+        (Array(left), Array(right)) => walk_elements(path, left, right, out),
+        #[cfg(feature = "resp3")]
+        (Set(left), Set(right)) | (Push(left), Push(right)) => {
+            walk_elements(path, left, right, out)
+        }
+        #[cfg(feature = "resp3")]
+        (Map(left), Map(right)) => walk_pairs(path, left, right, out),
+        #[cfg(feature = "resp3")]
+        (
+            WithAttributes {
+                attributes: left_attributes,
+                value: left,
+            },
+            WithAttributes {
+                attributes: right_attributes,
+                value: right,
+            },
+        ) => {
+            walk_pairs(
+                &format!("{path}|attributes"),
+                left_attributes,
+                right_attributes,
+                out,
+            );
+            walk(path, left, right, out);
+        }
+        #[cfg(feature = "resp3")]
+        (WithAttributes { .. }, _) => out.push(format!("{path}: only left has attributes")),
+        #[cfg(feature = "resp3")]
+        (_, WithAttributes { .. }) => out.push(format!("{path}: only right has attributes")),
+        #[cfg(feature = "resp3")]
+        (Double(left), Double(right)) if left.is_nan() && right.is_nan() => {}
+        #[cfg(feature = "resp3")]
+        (
+            VerbatimString {
+                encoding: left_encoding,
+                data: left_data,
+            },
+            VerbatimString {
+                encoding: right_encoding,
+                data: right_data,
+            },
+        ) if left_data == right_data && left_encoding != right_encoding => out.push(format!(
+            "{path}: left is encoded as {left_encoding:?}, right as {right_encoding:?}"
+        )),
+        _ if left != right => out.push(format!(
+            "{path}: left is {}, right is {}",
+            left.describe(),
+            right.describe()
+        )),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk(x: &str) -> RESP {
+        RESP::BulkString(x.to_owned())
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn diff_paths() {
+        let left = RESP::Array(vec![
+            bulk("a"),
+            RESP::Map(vec![
+                (bulk("name"), bulk("bob")),
+                (bulk("db"), RESP::Integer(0)),
+            ]),
+            RESP::Double(f64::NAN),
+        ]);
+        let right = RESP::Array(vec![
+            RESP::Integer(1),
+            RESP::Map(vec![
+                (bulk("name"), bulk("bob")),
+                (bulk("db"), RESP::Integer(3)),
+            ]),
+            RESP::Double(f64::NAN),
+            RESP::Null,
+        ]);
+        assert_eq!(
+            diff(&left, &right),
+            vec![
+                "$: left has 3 elements, right has 4",
+                "$[0]: left is bulk string \"a\", right is integer 1",
+                "$[1]{\"db\"}: left is integer 0, right is integer 3",
+            ]
+        );
+        assert!(diff(&left, &left).is_empty());
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn diff_attributes() {
+        let left = RESP::WithAttributes {
+            attributes: vec![(bulk("ttl"), RESP::Integer(10))],
+            value: Box::new(RESP::Integer(1)),
+        };
+        assert_eq!(
+            diff(&left, &RESP::Integer(1)),
+            vec!["$: only left has attributes"]
+        );
+    }
+
+    #[test]
+    fn assert_resp_eq_passes() {
+        assert_resp_eq!(RESP::Array(vec![bulk("a")]), RESP::Array(vec![bulk("a")]));
+    }
+
+    #[test]
+    #[should_panic(expected = "$[0]: left is bulk string \"a\", right is bulk string \"b\"")]
+    fn assert_resp_eq_fails() {
+        assert_resp_eq!(RESP::Array(vec![bulk("a")]), RESP::Array(vec![bulk("b")]));
+    }
+}
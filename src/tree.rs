@@ -0,0 +1,105 @@
+use crate::RESP;
+
+/// Node of the rendered tree, attributes being shown as a node of their own.
+enum Node<'a> {
+    Value(String, &'a RESP),
+    #[cfg(feature = "resp3")]
+    Attributes(&'a [(RESP, RESP)]),
+}
+
+#[cfg(feature = "resp3")]
+fn entries(pairs: &[(RESP, RESP)]) -> Vec<Node<'_>> {
+    pairs
+        .iter()
+        .map(|(key, value)| Node::Value(format!("{}: ", key.describe()), value))
+        .collect()
+}
+
+fn children(value: &RESP) -> Vec<Node<'_>> {
+    match value {
+        RESP::Array(x) => x.iter().map(|x| Node::Value(String::new(), x)).collect(),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) | RESP::Push(x) => x.iter().map(|x| Node::Value(String::new(), x)).collect(),
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => entries(x),
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { attributes, value } => {
+            let mut nodes = vec![Node::Attributes(attributes)];
+            nodes.extend(children(value));
+            nodes
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Writes the line of `node` and then its children, `prefix` preceding
+/// every line of the children.
+fn write_node(output: &mut String, node: &Node, prefix: &str) {
+    let nodes = match node {
+        Node::Value(label, value) => {
+            output.push_str(label);
+            output.push_str(&value.describe());
+            children(value)
+        }
+        #[cfg(feature = "resp3")]
+        Node::Attributes(x) => {
+            output.push_str(&format!("attributes of {} entries", x.len()));
+            entries(x)
+        }
+    };
+    output.push('\n');
+
+    let last = nodes.len().saturating_sub(1);
+    for (index, child) in nodes.iter().enumerate() {
+        let (branch, continuation) = if index == last {
+            ("`-- ", "    ")
+        } else {
+            ("|-- ", "|   ")
+        };
+        output.push_str(prefix);
+        output.push_str(branch);
+        write_node(output, child, &format!("{prefix}{continuation}"));
+    }
+}
+
+impl RESP {
+    /// Renders the value as an indented tree, one line per node with its type
+    /// and a preview of its value, for looking into deeply nested replies.
+    pub fn render_tree(&self) -> String {
+        let mut output = String::new();
+        write_node(&mut output, &Node::Value(String::new(), self), "");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn tree_nested() {
+        let parsed = RESP::parse(
+            "*3\r\n$1\r\na\r\n%2\r\n+id\r\n:7\r\n+tags\r\n*1\r\n+x\r\n|1\r\n+ttl\r\n:3\r\n*1\r\n_\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.render_tree(),
+            "array of 3 elements\n\
+             |-- bulk string \"a\"\n\
+             |-- map of 2 entries\n\
+             |   |-- simple string \"id\": integer 7\n\
+             |   `-- simple string \"tags\": array of 1 elements\n\
+             |       `-- simple string \"x\"\n\
+             `-- array of 1 elements\n    \
+                 |-- attributes of 1 entries\n    \
+                 |   `-- simple string \"ttl\": integer 3\n    \
+                 `-- null\n"
+        );
+    }
+
+    #[test]
+    fn tree_scalar() {
+        assert_eq!(RESP::Integer(1).render_tree(), "integer 1\n");
+    }
+}
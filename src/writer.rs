@@ -0,0 +1,391 @@
+use std::io::{self, Write};
+
+#[cfg(feature = "futures")]
+use futures::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::framing::{frame_len, write_len_header};
+use crate::{EncodeError, Protocol, ToResp, RESP};
+
+/// Default amount of encoded bytes buffered by a [`FrameWriter`] before it
+/// flushes.
+pub const DEFAULT_WRITER_CAPACITY: usize = 8 * 1024;
+
+/// Checks that `frame` holds exactly one complete frame, so splicing it can't
+/// desynchronize the stream.
+fn check_raw(frame: &[u8]) -> Result<(), EncodeError> {
+    match frame_len(frame) {
+        Ok(Some(length)) if length == frame.len() => Ok(()),
+        _ => Err(EncodeError::Invalid {
+            expected: "a single complete frame",
+            found: format!("{} raw bytes", frame.len()),
+        }),
+    }
+}
+
+/// Batches encoded frames into an internal buffer and writes them together,
+/// so pipelined frames don't cost a syscall each.
+///
+/// The buffer is flushed once it holds `capacity` bytes or, if set, a number
+/// of frames, and on [`flush`](Self::flush). Dropping the writer doesn't
+/// flush it.
+#[derive(Debug)]
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+    max_frames: Option<usize>,
+    frames: usize,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(DEFAULT_WRITER_CAPACITY, writer)
+    }
+
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            max_frames: None,
+            frames: 0,
+        }
+    }
+
+    /// Also flushes once `max_frames` frames are buffered, whatever their size.
+    pub fn flush_after_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Encodes `value` into the buffer, flushing if a threshold is reached.
+    /// Nothing is buffered when the value is invalid.
+    pub fn write_frame(&mut self, value: &RESP) -> Result<(), EncodeError> {
+        value.write_to(&mut self.buffer)?;
+        Ok(self.frame_buffered()?)
+    }
+
+    /// Writes an already encoded frame as is, such as a cached reply,
+    /// nothing being buffered unless it is a single complete frame.
+    pub fn write_raw(&mut self, frame: &[u8]) -> Result<(), EncodeError> {
+        check_raw(frame)?;
+        self.buffer.extend_from_slice(frame);
+        Ok(self.frame_buffered()?)
+    }
+
+    /// Counts a frame added to the buffer, flushing if a threshold is
+    /// reached.
+    fn frame_buffered(&mut self) -> io::Result<()> {
+        self.frames += 1;
+        if self.buffer.len() >= self.capacity || self.max_frames.is_some_and(|x| self.frames >= x) {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered frames and flushes the inner writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.frames = 0;
+        self.writer.flush()
+    }
+
+    /// Bytes encoded but not written yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flushes the buffered frames and returns the inner writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Encodes replies straight into a buffer, without building [`RESP`] values,
+/// for servers answering requests. Nothing is written out until
+/// [`flush`](Self::flush).
+///
+/// Line breaks in simple strings and errors are replaced with spaces, as
+/// Redis does. Nulls and maps are written for the protocol of the
+/// connection, RESP2 until [`set_protocol`](Self::set_protocol) is called
+/// after a `HELLO 3`.
+///
+/// ```
+/// use resp_parser_rs::ReplyWriter;
+///
+/// let mut output = Vec::new();
+/// let mut replies = ReplyWriter::new(&mut output);
+/// replies.ok().integer(3).array(2).bulk("a").null();
+/// replies.error("WRONGTYPE", "Operation against a key holding the wrong kind of value");
+/// replies.flush().unwrap();
+/// assert!(output.starts_with(b"+OK\r\n:3\r\n*2\r\n$1\r\na\r\n$-1\r\n-WRONGTYPE "));
+/// ```
+#[derive(Debug)]
+pub struct ReplyWriter<W> {
+    writer: W,
+    buffer: Vec<u8>,
+    protocol: Protocol,
+}
+
+impl<W> ReplyWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(DEFAULT_WRITER_CAPACITY),
+            protocol: Protocol::Resp2,
+        }
+    }
+
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    fn line(&mut self, kind: u8, text: &str) -> &mut Self {
+        self.buffer.push(kind);
+        self.line_end(text)
+    }
+
+    fn line_end(&mut self, text: &str) -> &mut Self {
+        self.push_text(text);
+        self.buffer.extend_from_slice(b"\r\n");
+        self
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.buffer.extend(
+            text.bytes()
+                .map(|x| if x == b'\r' || x == b'\n' { b' ' } else { x }),
+        );
+    }
+
+    pub fn ok(&mut self) -> &mut Self {
+        self.simple("OK")
+    }
+
+    pub fn simple(&mut self, text: &str) -> &mut Self {
+        self.line(b'+', text)
+    }
+
+    /// Writes an error made of its `code`, like `ERR` or `WRONGTYPE`, and a
+    /// message.
+    pub fn error(&mut self, code: &str, message: &str) -> &mut Self {
+        self.buffer.push(b'-');
+        self.push_text(code);
+        self.buffer.push(b' ');
+        self.line_end(message)
+    }
+
+    pub fn integer(&mut self, value: i64) -> &mut Self {
+        let _ = write!(self.buffer, ":{value}\r\n");
+        self
+    }
+
+    pub fn bulk(&mut self, data: impl AsRef<[u8]>) -> &mut Self {
+        let data = data.as_ref();
+        let _ = write_len_header(&mut self.buffer, b'$', Some(data.len()));
+        self.buffer.extend_from_slice(data);
+        self.buffer.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// Writes a null, as a null bulk string in RESP2.
+    pub fn null(&mut self) -> &mut Self {
+        match self.protocol {
+            Protocol::Resp2 => self.buffer.extend_from_slice(b"$-1\r\n"),
+            Protocol::Resp3 => self.buffer.extend_from_slice(b"_\r\n"),
+        }
+        self
+    }
+
+    /// Starts an array of `len` elements, to be written next.
+    pub fn array(&mut self, len: usize) -> &mut Self {
+        let _ = write_len_header(&mut self.buffer, b'*', Some(len));
+        self
+    }
+
+    /// Starts a map of `len` entries, keys and values to be written next in
+    /// turn. RESP2 has no maps, so they are flat arrays there.
+    pub fn map(&mut self, len: usize) -> &mut Self {
+        let _ = match self.protocol {
+            Protocol::Resp2 => write_len_header(&mut self.buffer, b'*', Some(len * 2)),
+            Protocol::Resp3 => write_len_header(&mut self.buffer, b'%', Some(len)),
+        };
+        self
+    }
+
+    /// Writes an already built value, nothing being written if it is
+    /// invalid.
+    pub fn value(&mut self, value: &RESP) -> Result<&mut Self, EncodeError> {
+        value.write_to(&mut self.buffer)?;
+        Ok(self)
+    }
+
+    /// Splices an already encoded frame, such as a cached reply, for example
+    /// as an element of an array. Nothing is written unless it is a single
+    /// complete frame.
+    pub fn raw(&mut self, frame: &[u8]) -> Result<&mut Self, EncodeError> {
+        check_raw(frame)?;
+        self.buffer.extend_from_slice(frame);
+        Ok(self)
+    }
+
+    /// Writes an array holding `elements`, nothing being written if one of
+    /// them is invalid.
+    pub fn array_of<I>(&mut self, elements: I) -> Result<&mut Self, EncodeError>
+    where
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: ToResp,
+    {
+        let start = self.buffer.len();
+        let elements = elements.into_iter();
+        self.array(elements.len());
+        for element in elements {
+            if let Err(err) = element.write_resp(&mut self.buffer) {
+                self.buffer.truncate(start);
+                return Err(err);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Bytes encoded but not written yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+}
+
+impl<W: Write> ReplyWriter<W> {
+    /// Writes the buffered replies and flushes the inner writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<W: AsyncWrite + Unpin> ReplyWriter<W> {
+    /// Like [`flush`](Self::flush), for an [`AsyncWrite`].
+    pub async fn flush_async(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer).await?;
+        self.buffer.clear();
+        self.writer.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_flush_on_capacity() {
+        let mut writer = FrameWriter::with_capacity(16, Vec::new());
+        writer.write_frame(&RESP::Integer(1)).unwrap();
+        writer.write_frame(&RESP::Integer(2)).unwrap();
+        assert!(writer.get_ref().is_empty());
+        assert_eq!(writer.buffered(), 8);
+        writer
+            .write_frame(&RESP::BulkString("Hello".to_owned()))
+            .unwrap();
+        assert_eq!(writer.get_ref(), b":1\r\n:2\r\n$5\r\nHello\r\n");
+        assert_eq!(writer.buffered(), 0);
+    }
+
+    #[cfg(feature = "resp3")]
+    #[test]
+    fn writer_flush_on_frames() {
+        let mut writer = FrameWriter::new(Vec::new()).flush_after_frames(2);
+        writer.write_frame(&RESP::Null).unwrap();
+        assert!(writer.get_ref().is_empty());
+        writer.write_frame(&RESP::Null).unwrap();
+        writer.write_frame(&RESP::Boolean(true)).unwrap();
+        assert_eq!(writer.get_ref(), b"_\r\n_\r\n");
+        assert_eq!(writer.into_inner().unwrap(), b"_\r\n_\r\n#t\r\n");
+    }
+
+    #[test]
+    fn writer_invalid_frame() {
+        let mut writer = FrameWriter::new(Vec::new());
+        let invalid = RESP::SimpleString("a\r\nb".into());
+        assert!(matches!(
+            writer.write_frame(&invalid),
+            Err(EncodeError::Invalid { .. })
+        ));
+        assert_eq!(writer.buffered(), 0);
+    }
+
+    #[test]
+    fn reply_writer() {
+        let mut replies = ReplyWriter::new(Vec::new());
+        replies
+            .simple("a\r\nb")
+            .error("ERR", "no\nway")
+            .map(1)
+            .bulk(b"k")
+            .null();
+        replies.array_of(["x", "y"]).unwrap();
+        assert_eq!(replies.buffered(), 54);
+        assert!(replies.get_ref().is_empty());
+        replies.flush().unwrap();
+        assert_eq!(
+            replies.get_ref(),
+            b"+a  b\r\n-ERR no way\r\n*2\r\n$1\r\nk\r\n$-1\r\n*2\r\n$1\r\nx\r\n$1\r\ny\r\n"
+        );
+
+        let mut replies = ReplyWriter::new(Vec::new());
+        replies.set_protocol(Protocol::Resp3);
+        replies.map(1).simple("k").null();
+        let invalid = RESP::SimpleString("a\r\nb".into());
+        assert!(matches!(
+            replies.value(&invalid),
+            Err(EncodeError::Invalid { .. })
+        ));
+        replies.flush().unwrap();
+        assert_eq!(replies.get_ref(), b"%1\r\n+k\r\n_\r\n");
+    }
+
+    #[test]
+    fn raw_frames() {
+        let cached = RESP::Array(vec![RESP::Integer(1)]).encode().unwrap();
+        let mut replies = ReplyWriter::new(Vec::new());
+        replies.array(2).raw(&cached).unwrap().ok();
+        for invalid in [
+            &b"+OK\r\n:1\r\n"[..],
+            b"$5\r\nab",
+            b"$x\r\n",
+            b"$3\r\nabcXY",
+        ] {
+            assert!(matches!(
+                replies.raw(invalid),
+                Err(EncodeError::Invalid { .. })
+            ));
+        }
+        replies.flush().unwrap();
+        assert_eq!(replies.get_ref(), b"*2\r\n*1\r\n:1\r\n+OK\r\n");
+
+        let mut writer = FrameWriter::new(Vec::new()).flush_after_frames(1);
+        writer.write_raw(&cached).unwrap();
+        assert!(writer.write_raw(b"").is_err());
+        assert_eq!(writer.get_ref(), &cached);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn reply_writer_async() {
+        let mut replies = ReplyWriter::new(futures::io::Cursor::new(Vec::new()));
+        replies.ok();
+        futures::executor::block_on(replies.flush_async()).unwrap();
+        assert_eq!(replies.get_ref().get_ref(), b"+OK\r\n");
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::Write;
+
+use crate::json::write_string;
+use crate::RESP;
+
+fn new_line(output: &mut String, indent: usize, marker: &str) {
+    output.push('\n');
+    output.extend(std::iter::repeat_n(' ', indent));
+    output.push_str(marker);
+}
+
+fn write_elements(output: &mut String, tag: &str, elements: &[RESP], indent: usize) {
+    output.push_str(tag);
+    if elements.is_empty() {
+        output.push_str(" []");
+    }
+    for element in elements {
+        new_line(output, indent, "- ");
+        write_value(output, element, indent + 2);
+    }
+}
+
+#[cfg(feature = "resp3")]
+fn write_pairs(output: &mut String, tag: &str, pairs: &[(RESP, RESP)], indent: usize) {
+    output.push_str(tag);
+    if pairs.is_empty() {
+        output.push_str(" {}");
+    }
+    for (key, value) in pairs {
+        new_line(output, indent, "? ");
+        write_value(output, key, indent + 2);
+        new_line(output, indent, ": ");
+        write_value(output, value, indent + 2);
+    }
+}
+
+/// Writes `value` where the cursor is, nested lines being indented by
+/// `indent`.
+fn write_value(output: &mut String, value: &RESP, indent: usize) {
+    let mut tagged = |tag: &str, text: &str| {
+        output.push_str(tag);
+        output.push(' ');
+        write_string(output, text);
+    };
+    match value {
+        RESP::SimpleString(x) => tagged("!simple", x),
+        RESP::SimpleError(x) => tagged("!error", x),
+        RESP::BulkString(x) => tagged("!bulk", x),
+        #[cfg(feature = "resp3")]
+        RESP::BulkError(x) => tagged("!bulk_error", x),
+        #[cfg(feature = "resp3")]
+        RESP::VerbatimString { encoding, data } => tagged(&format!("!verbatim/{encoding}"), data),
+        RESP::Integer(x) => {
+            let _ = write!(output, "!int {x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) if x.is_nan() => output.push_str("!double nan"),
+        #[cfg(feature = "resp3")]
+        RESP::Double(x) => {
+            let _ = write!(output, "!double {x}");
+        }
+        #[cfg(feature = "rust_decimal")]
+        RESP::Decimal(x) => {
+            let _ = write!(output, "!decimal {x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::BigNumber(x) => {
+            let _ = write!(output, "!big {x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Boolean(x) => {
+            let _ = write!(output, "!bool {x}");
+        }
+        #[cfg(feature = "resp3")]
+        RESP::Null => output.push_str("!null"),
+        RESP::NullBulkString => output.push_str("!bulk null"),
+        RESP::NullArray => output.push_str("!array null"),
+        RESP::Array(x) => write_elements(output, "!array", x, indent),
+        #[cfg(feature = "resp3")]
+        RESP::Set(x) => write_elements(output, "!set", x, indent),
+        #[cfg(feature = "resp3")]
+        RESP::Push(x) => write_elements(output, "!push", x, indent),
+        #[cfg(feature = "resp3")]
+        RESP::Map(x) => write_pairs(output, "!map", x, indent),
+        RESP::Inline(x) => {
+            output.push_str("!inline [");
+            for (index, argument) in x.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                write_string(output, argument);
+            }
+            output.push(']');
+        }
+        #[cfg(feature = "resp3")]
+        RESP::WithAttributes { attributes, value } => {
+            output.push_str("!attributed");
+            new_line(output, indent, "attributes: ");
+            write_pairs(output, "!map", attributes, indent + 2);
+            new_line(output, indent, "value: ");
+            write_value(output, value, indent + 2);
+        }
+    }
+}
+
+impl RESP {
+    /// Renders the value as YAML-like text for debugging, tagging every value
+    /// with its type so nothing is lost, unlike
+    /// [`to_json_string`](Self::to_json_string).
+    pub fn to_yaml_string(&self) -> String {
+        let mut output = String::new();
+        write_value(&mut output, self, 0);
+        output
+    }
+}
+
+#[cfg(all(test, feature = "resp3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_scalars() {
+        let parsed =
+            RESP::parse("*6\r\n$3\r\nfoo\r\n+foo\r\n,nan\r\n$-1\r\n=7\r\ntxt:a\"b\r\n*0\r\n")
+                .unwrap();
+        assert_eq!(
+            parsed.to_yaml_string(),
+            "!array\n\
+             - !bulk \"foo\"\n\
+             - !simple \"foo\"\n\
+             - !double nan\n\
+             - !bulk null\n\
+             - !verbatim/txt \"a\\\"b\"\n\
+             - !array []"
+        );
+    }
+
+    #[test]
+    fn yaml_nested() {
+        let parsed = RESP::parse("|1\r\n+ttl\r\n:3\r\n%1\r\n:1\r\n*2\r\n#t\r\n_\r\n").unwrap();
+        assert_eq!(
+            parsed.to_yaml_string(),
+            "!attributed\n\
+             attributes: !map\n  \
+               ? !simple \"ttl\"\n  \
+               : !int 3\n\
+             value: !map\n  \
+               ? !int 1\n  \
+               : !array\n    \
+                 - !bool true\n    \
+                 - !null"
+        );
+    }
+}